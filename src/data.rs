@@ -1,5 +1,7 @@
 #![allow(unused_imports)]
 
+pub use credentials::{Credential, CredentialBackend, CredentialRef, CredentialStore};
+pub use definition_import::{DefinitionImport, DefinitionMatch, ImportConflict};
 pub use field::kind;
 pub use field::kind::FieldLike;
 pub use field::kind::TagLike;
@@ -8,6 +10,7 @@ pub use field::KnownField;
 pub use field::SerialColour;
 pub use field::Type as FieldType;
 pub use field::Value as FieldValue;
+pub use field_store::FieldConflictPolicy;
 pub use field_store::FieldStore;
 pub use field_store::SimpleFieldStore;
 pub use filter::ExactTextSearchQuery;
@@ -17,16 +20,26 @@ pub use filter::ValueMatchExpression;
 pub use item::Item;
 pub use item_cache::ItemCache;
 pub use item_id::ItemId;
+pub use job::{Job, JobItemState, JobKind, JobPriority, JobQueue, JobStatus};
 pub use preview::DebugViewportClass;
 pub use preview::PreviewOptions;
-pub use shortcut::{ShortcutAction, ShortcutBehaviour};
+pub use review::ReviewSession;
+pub use shortcut::{
+    ShortcutAction, ShortcutBehaviour, ShortcutProfile, ShortcutProfileRef, ShortcutProfileScope,
+};
 pub use string::Utf32CachedString;
+pub use subscription::{Subscription, SubscriptionRun};
+pub use tag_stats::{SuggestionSource, TagStats, TagSuggestion};
+pub use task_log::{TaskLog, TaskLogEntry, TaskLogError, TaskLogItem};
 pub use thumbnail::{ThumbnailCache, ThumbnailCacheItem, ThumbnailParams};
 pub use transform::BulkParams as TransformBulkParams;
 pub use transform::ImageParams as TransformImageParams;
 pub use transform::PathParams as TransformPathParams;
+pub use transform::Preset as TransformPreset;
 pub use vault::Vault;
 
+mod credentials;
+mod definition_import;
 mod field;
 mod field_refs;
 mod field_store;
@@ -34,10 +47,15 @@ mod filter;
 mod item;
 mod item_cache;
 mod item_id;
+mod job;
 pub mod parse;
 mod preview;
+mod review;
 mod shortcut;
 mod string;
+mod subscription;
+pub mod tag_stats;
+mod task_log;
 mod thumbnail;
 pub mod transform;
 mod vault;
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::{anyhow, Context};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+
+const KEYRING_SERVICE: &str = "riiman";
+const SALT_LEN: usize = 16;
+
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credential {
+    pub username: String,
+    pub password: String,
+}
+
+impl Debug for Credential {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credential")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, Serialize, Deserialize)]
+pub enum CredentialBackend {
+    #[strum(to_string = "OS keyring")]
+    Keyring,
+    #[strum(to_string = "encrypted file")]
+    EncryptedFile,
+}
+
+/// Identifies a stored credential without containing any of its secrets, so that it can be
+/// persisted alongside other settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredentialRef {
+    pub id: Uuid,
+    pub label: String,
+    pub backend: CredentialBackend,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedFile {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+struct UnlockedFile {
+    key: Key,
+    salt: [u8; SALT_LEN],
+    credentials: HashMap<Uuid, Credential>,
+}
+
+/// Stores credentials in the OS keyring if one is available, otherwise in a file encrypted with
+/// a key derived from a passphrase.
+pub struct CredentialStore {
+    file_path: Option<PathBuf>,
+    use_keyring: bool,
+    keyring_available: OnceLock<bool>,
+    unlocked: Mutex<Option<UnlockedFile>>,
+}
+
+impl Debug for CredentialStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CredentialStore")
+            .field("file_path", &self.file_path)
+            .finish_non_exhaustive()
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> anyhow::Result<Key> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("failed to derive key: {e}"))?;
+    Ok(key)
+}
+
+impl CredentialStore {
+    pub fn new(file_path: Option<PathBuf>) -> Self {
+        Self {
+            file_path,
+            use_keyring: true,
+            keyring_available: OnceLock::new(),
+            unlocked: Mutex::new(None),
+        }
+    }
+
+    fn keyring_entry(id: &Uuid) -> keyring::Result<keyring::Entry> {
+        keyring::Entry::new(KEYRING_SERVICE, &id.to_string())
+    }
+
+    pub fn keyring_available(&self) -> bool {
+        self.use_keyring
+            && *self.keyring_available.get_or_init(|| {
+                match Self::keyring_entry(&Uuid::nil()).and_then(|e| e.get_password()) {
+                    Ok(_) | Err(keyring::Error::NoEntry) => true,
+                    Err(e) => {
+                        tracing::warn!("OS keyring is not available: {e}");
+                        false
+                    }
+                }
+            })
+    }
+
+    pub fn file_exists(&self) -> bool {
+        self.file_path.as_ref().is_some_and(|p| p.exists())
+    }
+
+    pub fn is_unlocked(&self) -> bool {
+        self.unlocked.lock().unwrap().is_some()
+    }
+
+    /// Decrypts the credential file with `passphrase`, or sets up a new file protected by
+    /// `passphrase` if there isn't one yet.
+    pub fn unlock(&self, passphrase: &str) -> anyhow::Result<()> {
+        let file_path = self
+            .file_path
+            .as_ref()
+            .ok_or(AppError::CredentialStoreUnavailable)?;
+
+        let unlocked = if file_path.exists() {
+            let contents = std::fs::read(file_path)
+                .with_context(|| format!("while reading {}", file_path.display()))?;
+            let file: EncryptedFile = serde_json::from_slice(&contents)?;
+            let salt: [u8; SALT_LEN] = BASE64
+                .decode(file.salt)?
+                .try_into()
+                .map_err(|_| anyhow!("invalid salt in credential file"))?;
+            let key = derive_key(passphrase, &salt)?;
+            let nonce = BASE64.decode(file.nonce)?;
+            let plaintext = ChaCha20Poly1305::new(&key)
+                .decrypt(
+                    Nonce::from_slice(&nonce),
+                    BASE64.decode(file.ciphertext)?.as_slice(),
+                )
+                .map_err(|_| AppError::InvalidPassphrase)?;
+            UnlockedFile {
+                key,
+                salt,
+                credentials: serde_json::from_slice(&plaintext)?,
+            }
+        } else {
+            let mut salt = [0u8; SALT_LEN];
+            rand::thread_rng().fill_bytes(&mut salt);
+            UnlockedFile {
+                key: derive_key(passphrase, &salt)?,
+                salt,
+                credentials: HashMap::new(),
+            }
+        };
+
+        *self.unlocked.lock().unwrap() = Some(unlocked);
+        Ok(())
+    }
+
+    pub fn lock(&self) {
+        *self.unlocked.lock().unwrap() = None;
+    }
+
+    fn write_file(&self, unlocked: &UnlockedFile) -> anyhow::Result<()> {
+        let file_path = self
+            .file_path
+            .as_ref()
+            .ok_or(AppError::CredentialStoreUnavailable)?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = ChaCha20Poly1305::new(&unlocked.key)
+            .encrypt(
+                &nonce,
+                serde_json::to_vec(&unlocked.credentials)?.as_slice(),
+            )
+            .map_err(|e| anyhow!("failed to encrypt credentials: {e}"))?;
+        let file = EncryptedFile {
+            salt: BASE64.encode(unlocked.salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        };
+
+        if let Some(parent) = file_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(file_path, serde_json::to_vec(&file)?)
+            .with_context(|| format!("while writing {}", file_path.display()))
+    }
+
+    pub fn save(&self, label: String, credential: &Credential) -> anyhow::Result<CredentialRef> {
+        let id = Uuid::new_v4();
+
+        if self.keyring_available() {
+            Self::keyring_entry(&id)?.set_password(&serde_json::to_string(credential)?)?;
+            return Ok(CredentialRef {
+                id,
+                label,
+                backend: CredentialBackend::Keyring,
+            });
+        }
+
+        let mut unlocked = self.unlocked.lock().unwrap();
+        let unlocked = unlocked.as_mut().ok_or(AppError::CredentialStoreLocked)?;
+        unlocked.credentials.insert(id, credential.clone());
+        self.write_file(unlocked)?;
+
+        Ok(CredentialRef {
+            id,
+            label,
+            backend: CredentialBackend::EncryptedFile,
+        })
+    }
+
+    pub fn get(&self, credential_ref: &CredentialRef) -> anyhow::Result<Credential> {
+        let missing = || AppError::MissingCredential {
+            label: credential_ref.label.clone(),
+        };
+
+        match credential_ref.backend {
+            CredentialBackend::Keyring => {
+                let secret = match Self::keyring_entry(&credential_ref.id)?.get_password() {
+                    Err(keyring::Error::NoEntry) => return Err(missing().into()),
+                    res => res?,
+                };
+                Ok(serde_json::from_str(&secret)?)
+            }
+            CredentialBackend::EncryptedFile => {
+                let unlocked = self.unlocked.lock().unwrap();
+                let unlocked = unlocked.as_ref().ok_or(AppError::CredentialStoreLocked)?;
+                Ok(unlocked
+                    .credentials
+                    .get(&credential_ref.id)
+                    .ok_or_else(missing)?
+                    .clone())
+            }
+        }
+    }
+
+    pub fn remove(&self, credential_ref: &CredentialRef) -> anyhow::Result<()> {
+        match credential_ref.backend {
+            CredentialBackend::Keyring => {
+                match Self::keyring_entry(&credential_ref.id)?.delete_credential() {
+                    Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+                    Err(e) => Err(e.into()),
+                }
+            }
+            CredentialBackend::EncryptedFile => {
+                let mut unlocked = self.unlocked.lock().unwrap();
+                let unlocked = unlocked.as_mut().ok_or(AppError::CredentialStoreLocked)?;
+                if unlocked.credentials.remove(&credential_ref.id).is_some() {
+                    self.write_file(unlocked)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_file_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.json");
+        let credential = Credential {
+            username: "user".to_string(),
+            password: "hunter2".to_string(),
+        };
+
+        let file_store = |path| CredentialStore {
+            use_keyring: false,
+            ..CredentialStore::new(Some(path))
+        };
+
+        let store = file_store(path.clone());
+        assert!(store.save("test".to_string(), &credential).is_err());
+        store.unlock("correct horse").unwrap();
+        let credential_ref = store.save("test".to_string(), &credential).unwrap();
+        assert_eq!(credential_ref.backend, CredentialBackend::EncryptedFile);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("hunter2"));
+
+        let store = file_store(path);
+        assert!(store.unlock("wrong").is_err());
+        assert!(store.get(&credential_ref).is_err());
+        store.unlock("correct horse").unwrap();
+        assert_eq!(store.get(&credential_ref).unwrap(), credential);
+    }
+}
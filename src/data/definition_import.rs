@@ -0,0 +1,391 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+use uuid::Uuid;
+
+use crate::data::{FieldDefinition, FieldStore, FieldType, Vault};
+use crate::errors::HierarchyError;
+use crate::fields;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, derive_more::Display)]
+pub enum DefinitionMatch {
+    #[display("ID")]
+    Id,
+    #[display("name")]
+    Name,
+    #[display("alias")]
+    Alias,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportConflict {
+    TypeMismatch {
+        source_id: Uuid,
+        target_id: Uuid,
+        name: String,
+        source_type: FieldType,
+        target_type: FieldType,
+    },
+    AmbiguousName {
+        source_id: Uuid,
+        name: String,
+        candidates: Vec<Uuid>,
+    },
+}
+
+impl Display for ImportConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TypeMismatch {
+                name,
+                source_type,
+                target_type,
+                ..
+            } => write!(
+                f,
+                "{name}: type {source_type} does not match existing type {target_type}"
+            ),
+            Self::AmbiguousName {
+                name, candidates, ..
+            } => write!(
+                f,
+                "{name}: matches {} existing definitions by name or alias",
+                candidates.len()
+            ),
+        }
+    }
+}
+
+/// A plan for copying definitions from one collection into a vault. Source definitions are
+/// matched to existing definitions by UUID, then by name, then by alias; the rest are created
+/// with their original UUIDs. Conflicting definitions are skipped and reported.
+#[derive(Debug, Default)]
+pub struct DefinitionImport {
+    pub id_map: HashMap<Uuid, Uuid>,
+    pub matched: Vec<(Uuid, Uuid, DefinitionMatch)>,
+    pub created: Vec<Uuid>,
+    pub conflicts: Vec<ImportConflict>,
+    definitions: Vec<FieldDefinition>,
+}
+
+fn aliases_of(def: &FieldDefinition) -> Vec<String> {
+    def.get_known_field_value(fields::meta::ALIASES)
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|v| v.as_str_opt())
+        .map(|s| s.to_lowercase())
+        .collect()
+}
+
+/// Returns the IDs of the given roots and all of their descendants within `source`.
+pub fn subtree_ids(
+    source: &HashMap<Uuid, FieldDefinition>,
+    root_ids: impl IntoIterator<Item = Uuid>,
+) -> HashSet<Uuid> {
+    let mut res = HashSet::new();
+    let mut queue = root_ids.into_iter().collect_vec();
+    while let Some(id) = queue.pop() {
+        let Some(def) = source.get(&id) else {
+            continue;
+        };
+        if res.insert(id) {
+            queue.extend(def.iter_child_ids().map(|cid| *cid));
+        }
+    }
+    res
+}
+
+fn find_match(
+    def: &FieldDefinition,
+    target: &Vault,
+    match_names: bool,
+) -> Result<Option<(Uuid, DefinitionMatch)>, ImportConflict> {
+    if target.has_definition(&def.id) {
+        return Ok(Some((def.id, DefinitionMatch::Id)));
+    }
+    if !match_names {
+        return Ok(None);
+    }
+
+    let name = def.name.to_lowercase();
+    let source_aliases = aliases_of(def);
+    let mut by_name = vec![];
+    let mut by_alias = vec![];
+    for target_def in target.iter_field_defs() {
+        let target_name = target_def.name.to_lowercase();
+        if target_name == name {
+            by_name.push(target_def.id);
+        } else if aliases_of(&target_def).contains(&name) || source_aliases.contains(&target_name) {
+            by_alias.push(target_def.id);
+        }
+    }
+
+    let (candidates, kind) = if by_name.is_empty() {
+        (by_alias, DefinitionMatch::Alias)
+    } else {
+        (by_name, DefinitionMatch::Name)
+    };
+    match candidates.as_slice() {
+        [] => Ok(None),
+        [id] => Ok(Some((*id, kind))),
+        _ => Err(ImportConflict::AmbiguousName {
+            source_id: def.id,
+            name: def.name.to_string(),
+            candidates,
+        }),
+    }
+}
+
+/// Returns the planned definition for `entry`, or otherwise a copy of the definition in
+/// `target` if it exists and `needs_update`.
+fn linked_or_cloned<'a>(
+    entry: Entry<'a, Uuid, FieldDefinition>,
+    target: &Vault,
+    needs_update: impl FnOnce(&FieldDefinition) -> bool,
+) -> Option<&'a FieldDefinition> {
+    match entry {
+        Entry::Occupied(entry) => Some(entry.into_mut()),
+        Entry::Vacant(entry) => {
+            let def = target.get_definition(entry.key())?;
+            needs_update(&def).then(|| &*entry.insert(def.clone()))
+        }
+    }
+}
+
+impl DefinitionImport {
+    /// Plans the import of the subtrees under `root_ids` from `source` into `target`, and checks
+    /// that the resulting hierarchy is valid. Nothing is changed until [`Self::apply`] is called.
+    pub fn plan(
+        source: &HashMap<Uuid, FieldDefinition>,
+        root_ids: impl IntoIterator<Item = Uuid>,
+        target: &Vault,
+    ) -> Result<Self, HierarchyError> {
+        Self::plan_ids(source, subtree_ids(source, root_ids), target, true)
+    }
+
+    /// Like [`Self::plan`], but for exactly the given definitions. If `match_names` is not set,
+    /// definitions are only matched by UUID.
+    pub fn plan_ids(
+        source: &HashMap<Uuid, FieldDefinition>,
+        ids: HashSet<Uuid>,
+        target: &Vault,
+        match_names: bool,
+    ) -> Result<Self, HierarchyError> {
+        let mut res = Self::default();
+        let ids = ids
+            .into_iter()
+            .filter(|id| source.contains_key(id))
+            .sorted()
+            .collect_vec();
+
+        for id in &ids {
+            let def = &source[id];
+            match find_match(def, target, match_names) {
+                Ok(Some((target_id, kind))) => {
+                    let target_type = target
+                        .get_definition(&target_id)
+                        .map(|d| d.field_type)
+                        .unwrap_or_default();
+                    if target_type == def.field_type {
+                        res.id_map.insert(*id, target_id);
+                        res.matched.push((*id, target_id, kind));
+                    } else {
+                        res.conflicts.push(ImportConflict::TypeMismatch {
+                            source_id: *id,
+                            target_id,
+                            name: def.name.to_string(),
+                            source_type: def.field_type,
+                            target_type,
+                        });
+                    }
+                }
+                Ok(None) => {
+                    res.id_map.insert(*id, *id);
+                    res.created.push(*id);
+                }
+                Err(conflict) => res.conflicts.push(conflict),
+            }
+        }
+
+        // links to definitions outside the selection are kept if the target already has them
+        let map_id = |id: &Uuid| -> Option<Uuid> {
+            res.id_map
+                .get(id)
+                .copied()
+                .or_else(|| target.has_definition(id).then_some(*id))
+        };
+
+        for id in &ids {
+            let Some(target_id) = res.id_map.get(id) else {
+                continue;
+            };
+            let source_def = &source[id];
+            let def = if let Some(existing) = target.get_definition(target_id) {
+                existing.clone()
+            } else {
+                let def = source_def.clone();
+                def.clear_links();
+                def
+            };
+
+            for parent_id in source_def.iter_parent_ids().filter_map(|p| map_id(&p)) {
+                def.add_parent(parent_id);
+            }
+            for child_id in source_def.iter_child_ids().filter_map(|c| map_id(&c)) {
+                def.add_child(child_id);
+            }
+            for implied_id in source_def.iter_implied_ids().filter_map(|i| map_id(&i)) {
+                def.add_implied(implied_id);
+            }
+            res.definitions.push(def);
+        }
+        res.link_both_ways(target);
+
+        let scratch = Vault::new(String::new());
+        for def in target.iter_field_defs() {
+            scratch.set_definition(def.clone());
+        }
+        for def in &res.definitions {
+            scratch.set_definition(def.clone());
+        }
+        for def in &res.definitions {
+            let def = scratch
+                .get_definition(&def.id)
+                .ok_or(HierarchyError::MissingFieldDefinition { id: def.id })?;
+            scratch.find_hierarchy_error(&def)?;
+            scratch.find_implication_error(&def)?;
+        }
+
+        Ok(res)
+    }
+
+    /// Adds the other side of every parent/child link of the planned definitions, including to
+    /// definitions in `target` that are outside the selection, so that applying the plan never
+    /// depends on the order the definitions are set in.
+    fn link_both_ways(&mut self, target: &Vault) {
+        let mut linked: HashMap<Uuid, FieldDefinition> = self
+            .definitions
+            .drain(..)
+            .map(|def| (def.id, def))
+            .collect();
+        let links = linked
+            .values()
+            .flat_map(|def| {
+                let parents = def.iter_parent_ids().map(|p| (*p, def.id)).collect_vec();
+                let children = def.iter_child_ids().map(|c| (def.id, *c)).collect_vec();
+                parents.into_iter().chain(children)
+            })
+            .collect_vec();
+
+        for (parent_id, child_id) in links {
+            let parent = linked.entry(parent_id);
+            if let Some(parent) = linked_or_cloned(parent, target, |def| {
+                !def.iter_child_ids().any(|c| *c == child_id)
+            }) {
+                parent.add_child(child_id);
+            }
+            let child = linked.entry(child_id);
+            if let Some(child) = linked_or_cloned(child, target, |def| {
+                !def.iter_parent_ids().any(|p| *p == parent_id)
+            }) {
+                child.add_parent(parent_id);
+            }
+        }
+
+        self.definitions = linked.into_values().sorted_by_key(|def| def.id).collect();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    pub fn apply(self, target: &Vault) {
+        for def in self.definitions {
+            target.set_definition(def);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_plan_definition_import() {
+        let source = Vault::new("source".to_string());
+        let root = FieldDefinition::tag(Uuid::new_v4(), "Character".into());
+        let child = FieldDefinition::tag(Uuid::new_v4(), "alice".into()).with_parent(root.id);
+        let other = FieldDefinition::tag(Uuid::new_v4(), "unselected".into());
+        let (root_id, child_id) = (root.id, child.id);
+        source.set_definition(root);
+        source.set_definition(child);
+        source.set_definition(other);
+        let source: HashMap<_, _> = source
+            .iter_field_defs()
+            .map(|def| (def.id, def.clone()))
+            .collect();
+
+        let target = Vault::new("target".to_string());
+        let existing = FieldDefinition::tag(Uuid::new_v4(), "character".into());
+        let existing_id = existing.id;
+        target.set_definition(existing);
+
+        let plan = DefinitionImport::plan(&source, [root_id], &target).unwrap();
+        assert_eq!(
+            plan.matched,
+            vec![(root_id, existing_id, DefinitionMatch::Name)]
+        );
+        assert_eq!(plan.created, vec![child_id]);
+        assert!(plan.conflicts.is_empty());
+
+        plan.apply(&target);
+        let child = target.get_definition(&child_id).unwrap();
+        assert_eq!(
+            child.iter_parent_ids().map(|p| *p).collect_vec(),
+            vec![existing_id]
+        );
+        let existing = target.get_definition(&existing_id).unwrap();
+        assert_eq!(
+            existing.iter_child_ids().map(|c| *c).collect_vec(),
+            vec![child_id]
+        );
+        assert_eq!(target.iter_field_defs().count(), fields::defs().len() + 2);
+    }
+
+    #[test]
+    fn test_plan_links_outside_selection() {
+        let parent = FieldDefinition::tag(Uuid::new_v4(), "Character".into());
+        let child = FieldDefinition::tag(Uuid::new_v4(), "alice".into()).with_parent(parent.id);
+        let (parent_id, child_id) = (parent.id, child.id);
+        let source: HashMap<_, _> = [parent.clone(), child]
+            .into_iter()
+            .map(|def| (def.id, def))
+            .collect();
+
+        let target = Vault::new("target".to_string());
+        target.set_definition(parent);
+
+        let plan = DefinitionImport::plan_ids(&source, [child_id].into(), &target, false).unwrap();
+        assert_eq!(plan.created, vec![child_id]);
+        let planned_parent = plan
+            .definitions
+            .iter()
+            .find(|def| def.id == parent_id)
+            .unwrap();
+        assert_eq!(
+            planned_parent.iter_child_ids().map(|c| *c).collect_vec(),
+            vec![child_id]
+        );
+
+        plan.apply(&target);
+        let parent = target.get_definition(&parent_id).unwrap();
+        assert_eq!(
+            parent.iter_child_ids().map(|c| *c).collect_vec(),
+            vec![child_id]
+        );
+    }
+}
@@ -17,6 +17,8 @@ pub struct Definition {
     pub field_type: Type,
     parents: DashSet<Uuid>,
     children: DashSet<Uuid>,
+    #[serde(default)]
+    implies: DashSet<Uuid>,
     fields: DashMap<Uuid, Value>,
 }
 
@@ -64,6 +66,10 @@ impl Definition {
         self.children.iter()
     }
 
+    pub fn iter_implied_ids(&self) -> impl Iterator<Item = impl Deref<Target = Uuid> + '_> {
+        self.implies.iter()
+    }
+
     pub fn add_parent(&self, parent_id: Uuid) {
         self.parents.insert(parent_id);
     }
@@ -79,6 +85,21 @@ impl Definition {
     pub fn remove_child(&self, child_id: Uuid) {
         self.children.remove(&child_id);
     }
+
+    pub fn add_implied(&self, implied_id: Uuid) {
+        self.implies.insert(implied_id);
+    }
+
+    pub fn remove_implied(&self, implied_id: Uuid) {
+        self.implies.remove(&implied_id);
+    }
+
+    /// Removes all parent, child and implication links, leaving the definition standalone.
+    pub fn clear_links(&self) {
+        self.parents.clear();
+        self.children.clear();
+        self.implies.clear();
+    }
 }
 
 impl FieldStore for Definition {
@@ -291,6 +312,42 @@ impl SerialColour {
     pub fn as_mut_slice(&mut self) -> &mut [u8; 3] {
         &mut self.0
     }
+
+    /// Converts the colour from sRGB into CIELAB under a D65 white point.
+    pub fn to_lab(self) -> [f64; 3] {
+        let linear = self.0.map(|c| {
+            let c = f64::from(c) / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        });
+        let [r, g, b] = linear;
+        let xyz = [
+            (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.950_47,
+            0.2126 * r + 0.7152 * g + 0.0722 * b,
+            (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.088_83,
+        ];
+        let [fx, fy, fz] = xyz.map(|t| {
+            if t > 0.008_856 {
+                t.cbrt()
+            } else {
+                7.787 * t + 16.0 / 116.0
+            }
+        });
+        [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+    }
+
+    /// The CIE76 colour difference, where a difference of around 2.3 is just noticeable.
+    pub fn perceptual_distance(self, other: Self) -> f64 {
+        let (a, b) = (self.to_lab(), other.to_lab());
+        a.iter()
+            .zip(b)
+            .map(|(x, y)| (x - y).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
 }
 
 impl Display for SerialColour {
@@ -11,6 +11,7 @@ use dashmap::mapref::one::Ref;
 use dashmap::DashMap;
 use eframe::egui;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::ops::Deref;
@@ -213,6 +214,65 @@ pub trait FieldStore: Debug {
             self.set_field_value(*field.key(), field.value().clone());
         }
     }
+
+    /// Copies the fields of `src` into this store. Lists and dictionaries are combined, and
+    /// other differing values are resolved according to `policy`.
+    fn merge(&self, src: &impl FieldStore, policy: FieldConflictPolicy) {
+        for field in src.iter_fields() {
+            let id = *field.key();
+            let incoming = field.value();
+            let merged = match self.get_field_value(&id).as_deref() {
+                None => incoming.clone(),
+                Some(FieldValue::List(existing)) => match incoming {
+                    FieldValue::List(values) => {
+                        let mut list = existing.clone();
+                        for value in values {
+                            if !list.contains(value) {
+                                list.push(value.clone());
+                            }
+                        }
+                        FieldValue::list(list)
+                    }
+                    _ => continue,
+                },
+                Some(FieldValue::Dictionary(existing)) => match incoming {
+                    FieldValue::Dictionary(entries) => {
+                        let mut dict = existing.clone();
+                        for (key, value) in entries {
+                            match dict.iter_mut().find(|(k, _)| k == key) {
+                                None => dict.push((key.clone(), value.clone())),
+                                Some((_, v)) if policy == FieldConflictPolicy::TakeSource => {
+                                    *v = value.clone();
+                                }
+                                Some(_) => {}
+                            }
+                        }
+                        FieldValue::dictionary(dict)
+                    }
+                    _ => continue,
+                },
+                Some(existing) if existing == incoming => continue,
+                Some(_) => match policy {
+                    FieldConflictPolicy::KeepTarget => continue,
+                    FieldConflictPolicy::TakeSource => incoming.clone(),
+                },
+            };
+            self.set_field_value(id, merged);
+        }
+    }
+}
+
+/// How to resolve a field that has different values in the item being merged and the item it
+/// is being merged into.
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, derive_more::Display,
+)]
+pub enum FieldConflictPolicy {
+    #[default]
+    #[display("Keep existing value")]
+    KeepTarget,
+    #[display("Take incoming value")]
+    TakeSource,
 }
 
 #[allow(clippy::module_name_repetitions)]
@@ -12,6 +12,7 @@ use nom::multi::{count, fold_many0, many0, many1};
 use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
 use nom::{Compare, IResult, InputLength, InputTake, InputTakeAtPosition, Parser};
 use nom_locate::LocatedSpan;
+use ordered_float::OrderedFloat;
 use regex::Regex;
 use serde::{Deserializer, Serializer};
 use serde_regex::Serde;
@@ -58,6 +59,9 @@ pub enum ValueMatchExpression {
     StartsWith(FieldValue),
     EndsWith(FieldValue),
     Regex(SerdeRegex),
+    /// Matches colours that are perceptually close to the given colour. For a weighted colour
+    /// palette, the given share of the image must be made up of these colours.
+    Near(FieldValue, OrderedFloat<f64>),
 }
 
 impl PartialEq for ValueMatchExpression {
@@ -72,6 +76,7 @@ impl PartialEq for ValueMatchExpression {
             | (Self::GreaterThanOrEqual(x), Self::GreaterThanOrEqual(y))
             | (Self::StartsWith(x), Self::StartsWith(y))
             | (Self::EndsWith(x), Self::EndsWith(y)) => x.eq(y),
+            (Self::Near(x, a), Self::Near(y, b)) => x.eq(y) && a.eq(b),
             (Self::IsOneOf(x), Self::IsOneOf(y)) => {
                 x.len() == y.len() && x.intersection(y).count() == x.len()
             }
@@ -266,6 +271,8 @@ pub enum FilterExpression {
     FolderMatch(Box<Path>),
     TagMatch(Uuid),
     FieldMatch(Uuid, ValueMatchExpression),
+    /// Items without any fields besides the standard ones.
+    Untagged,
     Not(Box<FilterExpression>),
     Or(Box<FilterExpression>, Box<FilterExpression>),
     And(Box<FilterExpression>, Box<FilterExpression>),
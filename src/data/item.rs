@@ -78,6 +78,98 @@ impl Item {
 
         Ok(links.into_iter().map(|l| l.into()).collect())
     }
+
+    pub fn implied_ids(&self) -> anyhow::Result<HashSet<Uuid>> {
+        Ok(self
+            .get_known_field_value(fields::general::IMPLIED)?
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|v| v.as_str_opt().and_then(|s| s.parse().ok()))
+            .collect())
+    }
+
+    /// Replaces references to moved items, where `moved` maps each old `(vault, path)` pair to
+    /// its new one. Returns whether any field was changed.
+    pub fn rewrite_item_refs(&self, moved: &HashMap<(String, String), (String, String)>) -> bool {
+        let mut changed = false;
+        for mut field in self.fields.iter_mut() {
+            if let Some(value) = rewrite_value(field.value(), moved) {
+                *field.value_mut() = value;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Marks a field as having been set explicitly, so that it is kept even once the tags that
+    /// implied it are removed.
+    pub fn mark_manual(&self, id: &Uuid) {
+        let mut implied = self.implied_ids().unwrap_or_default();
+        if implied.remove(id) {
+            self.set_implied_ids(&implied);
+        }
+    }
+
+    pub fn set_implied_ids(&self, ids: &HashSet<Uuid>) {
+        if ids.is_empty() {
+            self.remove_field(&fields::general::IMPLIED.id);
+        } else {
+            let mut ids = ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+            ids.sort();
+            self.set_known_field_value(
+                fields::general::IMPLIED,
+                ids.into_iter()
+                    .map(|id| FieldValue::string(id.into()))
+                    .collect::<Vec<_>>(),
+            );
+        }
+    }
+}
+
+fn rewrite_value(
+    value: &FieldValue,
+    moved: &HashMap<(String, String), (String, String)>,
+) -> Option<FieldValue> {
+    match value {
+        FieldValue::ItemRef(r) => {
+            let (vault_name, path) = r;
+            let (new_vault_name, new_path) =
+                moved.get(&(vault_name.to_string(), path.to_string()))?;
+            Some(FieldValue::itemref((
+                new_vault_name.clone().into(),
+                new_path.clone().into(),
+            )))
+        }
+        FieldValue::List(list) => {
+            let mut changed = false;
+            let list = list
+                .iter()
+                .map(|v| match rewrite_value(v, moved) {
+                    Some(new_v) => {
+                        changed = true;
+                        new_v
+                    }
+                    None => v.clone(),
+                })
+                .collect();
+            changed.then(|| FieldValue::list(list))
+        }
+        FieldValue::Dictionary(dict) => {
+            let mut changed = false;
+            let dict = dict
+                .iter()
+                .map(|(k, v)| match rewrite_value(v, moved) {
+                    Some(new_v) => {
+                        changed = true;
+                        (k.clone(), new_v)
+                    }
+                    None => (k.clone(), v.clone()),
+                })
+                .collect();
+            changed.then(|| FieldValue::dictionary(dict))
+        }
+        _ => None,
+    }
 }
 
 impl FieldStore for Item {
@@ -89,7 +181,8 @@ impl FieldStore for Item {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::data::field_store::FieldStore;
+    use crate::data::field_store::{FieldConflictPolicy, FieldStore};
+    use crate::errors::HierarchyError;
     use std::path::Path;
 
     #[test]
@@ -105,4 +198,108 @@ mod test {
 
         assert!(item.has_tag(&vault, &fields::image::NAMESPACE.id).unwrap());
     }
+
+    #[test]
+    fn test_apply_implications() {
+        let vault = Vault::new("test".to_string());
+        let character = FieldDefinition::tag(Uuid::new_v4(), "character".into());
+        let series = FieldDefinition::tag(Uuid::new_v4(), "series".into());
+        let (character_id, series_id) = (character.id, series.id);
+        character.add_implied(series_id);
+        vault.set_definition(character);
+        vault.set_definition(series);
+
+        let item = vault.get_item_or_init(Path::new("path")).unwrap();
+        item.set_field_value(character_id, FieldValue::Tag);
+        vault.apply_implications(&item).unwrap();
+
+        assert!(item.has_field(&series_id));
+        assert!(item.implied_ids().unwrap().contains(&series_id));
+        assert!(!item.implied_ids().unwrap().contains(&character_id));
+
+        item.remove_field(&character_id);
+        vault.apply_implications(&item).unwrap();
+
+        assert!(!item.has_field(&series_id));
+        assert!(item.implied_ids().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_manual_implied_tag() {
+        let vault = Vault::new("test".to_string());
+        let character = FieldDefinition::tag(Uuid::new_v4(), "character".into());
+        let series = FieldDefinition::tag(Uuid::new_v4(), "series".into());
+        let (character_id, series_id) = (character.id, series.id);
+        character.add_implied(series_id);
+        vault.set_definition(character);
+        vault.set_definition(series);
+
+        let item = vault.get_item_or_init(Path::new("path")).unwrap();
+        item.set_field_value(character_id, FieldValue::Tag);
+        vault.apply_implications(&item).unwrap();
+        assert!(item.implied_ids().unwrap().contains(&series_id));
+
+        item.set_field_value(series_id, FieldValue::Tag);
+        item.mark_manual(&series_id);
+        vault.apply_implications(&item).unwrap();
+        assert!(item.implied_ids().unwrap().is_empty());
+
+        item.remove_field(&character_id);
+        vault.apply_implications(&item).unwrap();
+        assert!(item.has_field(&series_id));
+    }
+
+    #[test]
+    fn test_find_implication_error() {
+        let vault = Vault::new("test".to_string());
+        let a = FieldDefinition::tag(Uuid::new_v4(), "a".into());
+        let b = FieldDefinition::tag(Uuid::new_v4(), "b".into());
+        b.add_implied(a.id);
+        vault.set_definition(b.clone());
+        a.add_implied(b.id);
+
+        assert_eq!(
+            vault.find_implication_error(&a),
+            Err(HierarchyError::ImplicationLoop { field_id: a.id })
+        );
+    }
+
+    #[test]
+    fn test_merge_and_rewrite_refs() {
+        let target = Item::new("a.png".to_string());
+        target.set_known_field_value(fields::image::WIDTH, 100);
+        target.set_known_field_value(
+            fields::general::DERIVED,
+            vec![FieldValue::itemref(("x".into(), "1.png".into()))],
+        );
+        let source = Item::new("a.png".to_string());
+        source.set_known_field_value(fields::image::WIDTH, 200);
+        source.set_known_field_value(fields::image::HEIGHT, 300);
+        source.set_known_field_value(
+            fields::general::DERIVED,
+            vec![FieldValue::itemref(("y".into(), "2.png".into()))],
+        );
+
+        target.merge(&source, FieldConflictPolicy::KeepTarget);
+        assert_eq!(
+            target.get_known_field_value(fields::image::WIDTH).unwrap(),
+            Some(100)
+        );
+        assert_eq!(
+            target.get_known_field_value(fields::image::HEIGHT).unwrap(),
+            Some(300)
+        );
+
+        let moved = HashMap::from([(
+            ("y".to_string(), "2.png".to_string()),
+            ("x".to_string(), "2.png".to_string()),
+        )]);
+        assert!(target.rewrite_item_refs(&moved));
+        assert_eq!(target.links().unwrap().len(), 2);
+        assert!(target
+            .links()
+            .unwrap()
+            .iter()
+            .all(|kind::ItemRef((vault_name, _))| vault_name.as_str() == "x"));
+    }
 }
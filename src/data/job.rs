@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::data::{TransformBulkParams, TransformImageParams, TransformPathParams};
+use crate::tasks::CancellationToken;
+
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+)]
+pub enum JobPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, derive_more::Display,
+)]
+pub enum JobStatus {
+    #[default]
+    Queued,
+    Running,
+    Paused,
+    Completed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum JobKind {
+    TransformImages {
+        bulk: TransformBulkParams,
+        params: TransformImageParams,
+    },
+    TransformPaths {
+        bulk: TransformBulkParams,
+        params: TransformPathParams,
+    },
+    /// Imports files that are already in the vault's root directory.
+    Import,
+}
+
+impl JobKind {
+    pub fn bulk_params(&self) -> Option<&TransformBulkParams> {
+        match self {
+            Self::TransformImages { bulk, .. } | Self::TransformPaths { bulk, .. } => Some(bulk),
+            Self::Import => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobItemState {
+    Pending,
+    Done,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobItem {
+    /// Relative to the vault root.
+    pub path: String,
+    pub state: JobItemState,
+}
+
+/// A long-running bulk operation whose progress is recorded per item, so that it can be
+/// resumed after the app is closed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: JobKind,
+    pub priority: JobPriority,
+    pub status: JobStatus,
+    pub created: DateTime<Utc>,
+    pub items: Vec<JobItem>,
+}
+
+impl Job {
+    pub fn new(name: String, kind: JobKind, paths: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            kind,
+            priority: JobPriority::default(),
+            status: JobStatus::default(),
+            created: Utc::now(),
+            items: paths
+                .into_iter()
+                .map(|path| JobItem {
+                    path,
+                    state: JobItemState::Pending,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn with_priority(mut self, priority: JobPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn pending_paths(&self) -> impl Iterator<Item = &str> {
+        self.items
+            .iter()
+            .filter(|item| item.state == JobItemState::Pending)
+            .map(|item| item.path.as_str())
+    }
+
+    pub fn len_pending(&self) -> usize {
+        self.pending_paths().count()
+    }
+
+    pub fn len_failed(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| matches!(item.state, JobItemState::Failed(_)))
+            .count()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.status == JobStatus::Completed
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub fn progress(&self) -> f32 {
+        if self.items.is_empty() {
+            return 1.0;
+        }
+        1.0 - self.len_pending() as f32 / self.items.len() as f32
+    }
+
+    pub fn set_item_states(&mut self, mut states: HashMap<String, JobItemState>) {
+        for item in &mut self.items {
+            if let Some(state) = states.remove(&item.path) {
+                item.state = state;
+            }
+        }
+    }
+}
+
+/// The jobs of a vault, which are stored in a file beside the vault rather than in the vault
+/// itself so that they can be updated after every batch of items without rewriting the vault.
+#[derive(Debug, Default)]
+pub struct JobQueue {
+    jobs: Mutex<Vec<Job>>,
+    cancellations: DashMap<Uuid, CancellationToken>,
+    runner_active: AtomicBool,
+}
+
+impl JobQueue {
+    pub fn path_for_vault(vault_path: &Path) -> PathBuf {
+        vault_path.with_extension("jobs.json")
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, Vec<Job>> {
+        self.jobs.lock().unwrap()
+    }
+
+    /// Replaces the jobs with those loaded from disk. Jobs that were running when the app
+    /// closed are paused so that the user can choose when to resume them.
+    pub fn set_loaded(&self, mut jobs: Vec<Job>) {
+        for job in &mut jobs {
+            if job.status == JobStatus::Running {
+                job.status = JobStatus::Paused;
+            }
+        }
+        *self.lock() = jobs;
+    }
+
+    pub fn get(&self, id: &Uuid) -> Option<Job> {
+        self.lock().iter().find(|job| &job.id == id).cloned()
+    }
+
+    pub fn update(&self, id: &Uuid, f: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.lock().iter_mut().find(|job| &job.id == id) {
+            f(job);
+        }
+    }
+
+    pub fn push(&self, job: Job) {
+        self.lock().push(job);
+    }
+
+    pub fn remove(&self, id: &Uuid) {
+        self.cancel(id);
+        self.lock().retain(|job| &job.id != id);
+    }
+
+    pub fn len_unfinished(&self) -> usize {
+        self.lock().iter().filter(|job| !job.is_finished()).count()
+    }
+
+    /// The queued job that should run next, with higher priorities first and then the oldest.
+    pub fn next_queued(&self) -> Option<Job> {
+        self.lock()
+            .iter()
+            .filter(|job| job.status == JobStatus::Queued)
+            .min_by_key(|job| (std::cmp::Reverse(job.priority), job.created))
+            .cloned()
+    }
+
+    pub fn set_status(&self, id: &Uuid, status: JobStatus) {
+        self.update(id, |job| job.status = status);
+    }
+
+    pub fn pause(&self, id: &Uuid) {
+        self.update(id, |job| {
+            if matches!(job.status, JobStatus::Queued | JobStatus::Running) {
+                job.status = JobStatus::Paused;
+            }
+        });
+        self.cancel(id);
+    }
+
+    pub fn resume(&self, id: &Uuid) {
+        self.update(id, |job| {
+            if job.status == JobStatus::Paused {
+                job.status = JobStatus::Queued;
+            }
+        });
+    }
+
+    pub fn resume_all(&self) {
+        for job in self.lock().iter_mut() {
+            if job.status == JobStatus::Paused {
+                job.status = JobStatus::Queued;
+            }
+        }
+    }
+
+    pub fn start(&self, id: Uuid, cancellation: CancellationToken) {
+        self.set_status(&id, JobStatus::Running);
+        self.cancellations.insert(id, cancellation);
+    }
+
+    pub fn finish(&self, id: &Uuid) {
+        self.cancellations.remove(id);
+    }
+
+    fn cancel(&self, id: &Uuid) {
+        if let Some((_, cancellation)) = self.cancellations.remove(id) {
+            cancellation.cancel();
+        }
+    }
+
+    /// Marks the queue as being processed, returning false if it already was.
+    pub fn try_start_runner(&self) -> bool {
+        !self.runner_active.swap(true, Ordering::SeqCst)
+    }
+
+    pub fn stop_runner(&self) {
+        self.runner_active.store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn job(name: &str, priority: JobPriority) -> Job {
+        Job::new(
+            name.to_string(),
+            JobKind::TransformPaths {
+                bulk: Default::default(),
+                params: Default::default(),
+            },
+            ["a.png".to_string(), "b.png".to_string()],
+        )
+        .with_priority(priority)
+    }
+
+    #[test]
+    fn test_job_queue_order() {
+        let queue = JobQueue::default();
+        let low = job("low", JobPriority::Low);
+        let normal = job("normal", JobPriority::Normal);
+        let high = job("high", JobPriority::High);
+        let (low_id, normal_id, high_id) = (low.id, normal.id, high.id);
+        queue.push(low);
+        queue.push(normal);
+        queue.push(high);
+
+        assert_eq!(queue.next_queued().unwrap().id, high_id);
+        queue.pause(&high_id);
+        assert_eq!(queue.next_queued().unwrap().id, normal_id);
+        queue.set_status(&normal_id, JobStatus::Completed);
+        assert_eq!(queue.next_queued().unwrap().id, low_id);
+        queue.resume(&high_id);
+        assert_eq!(queue.next_queued().unwrap().id, high_id);
+        assert_eq!(queue.len_unfinished(), 2);
+    }
+
+    #[test]
+    fn test_job_items() {
+        let mut job = job("job", JobPriority::Normal);
+        assert!(job.progress().abs() < f32::EPSILON);
+        job.set_item_states(HashMap::from([
+            ("a.png".to_string(), JobItemState::Done),
+            (
+                "b.png".to_string(),
+                JobItemState::Failed("error".to_string()),
+            ),
+        ]));
+        assert_eq!(job.len_pending(), 0);
+        assert_eq!(job.len_failed(), 1);
+        assert!((job.progress() - 1.0).abs() < f32::EPSILON);
+
+        let queue = JobQueue::default();
+        job.status = JobStatus::Running;
+        queue.set_loaded(vec![job.clone()]);
+        assert_eq!(queue.get(&job.id).unwrap().status, JobStatus::Paused);
+    }
+}
@@ -279,6 +279,48 @@ fn colour_value(s: Span) -> IResult<Span, FieldValue> {
     preceded(opt(tag("#")), hex_colour_value)(s)
 }
 
+fn named_colour_value(s: Span) -> IResult<Span, FieldValue> {
+    let named = |name: &'static str, rgb: [u8; 3]| {
+        map(tag_no_case(name), move |_| FieldValue::colour(rgb.into()))
+    };
+    alt((
+        named("black", [0, 0, 0]),
+        named("white", [255, 255, 255]),
+        named("grey", [128, 128, 128]),
+        named("gray", [128, 128, 128]),
+        named("red", [220, 30, 30]),
+        named("orange", [255, 140, 0]),
+        named("yellow", [250, 220, 40]),
+        named("green", [40, 160, 50]),
+        named("teal", [0, 128, 128]),
+        named("cyan", [0, 220, 230]),
+        named("blue", [30, 80, 220]),
+        named("purple", [130, 50, 170]),
+        named("pink", [250, 150, 190]),
+        named("brown", [130, 80, 40]),
+    ))(s)
+}
+
+/// A share of an image given as a percentage, such as `30%`.
+fn percentage(s: Span) -> IResult<Span, f64> {
+    map_opt(terminated(double, tag("%")), |d| {
+        (0.0..=100.0).contains(&d).then_some(d / 100.0)
+    })(s)
+}
+
+fn near_match(s: Span) -> IResult<Span, ValueMatchExpression> {
+    // by default, the colour must make up most of the image
+    const DEFAULT_SHARE: f64 = 0.5;
+
+    map(
+        pair(
+            alt((named_colour_value, colour_value)),
+            opt(preceded(ws, percentage)),
+        ),
+        |(colour, share)| ValueMatchExpression::Near(colour, share.unwrap_or(DEFAULT_SHARE).into()),
+    )(s)
+}
+
 fn local_date_value(s: Span) -> IResult<Span, FieldValue> {
     match NaiveDate::parse_and_remainder(s.as_str(), "%Y-%m-%d") {
         Ok((naive_d, i)) => match local!()
@@ -503,6 +545,12 @@ fn folder_match(s: Span) -> IResult<Span, FilterExpressionParseNode> {
     ))(s)
 }
 
+fn untagged_match(s: Span) -> IResult<Span, FilterExpressionParseNode> {
+    record_range(map(tag_no_case("is:untagged"), |_| {
+        FilterExpression::Untagged
+    }))(s)
+}
+
 fn tag_match(s: Span) -> IResult<Span, FilterExpressionParseNode> {
     record_range(map(preceded(tag("field:"), uuid), |id| {
         FilterExpression::TagMatch(id)
@@ -526,6 +574,7 @@ fn filter_atom(s: Span) -> IResult<Span, FilterExpressionParseNode> {
         delimited(tag_ws("("), filter_expression, tag_ws(")")),
         with_ws(alt((
             folder_match,
+            untagged_match,
             field_match,
             tag_match,
             exact_text_search,
@@ -567,6 +616,10 @@ fn not_expression(s: Span) -> IResult<Span, FilterExpressionParseNode> {
 
 fn field_match_operator(s: Span) -> IResult<Span, ValueMatchExpressionDiscriminants> {
     with_ws(alt((
+        // must come before `ne`
+        map(alt((tag("\u{2248}"), tag_no_case("near"))), |_| {
+            ValueMatchExpressionDiscriminants::Near
+        }),
         map(
             alt((
                 tag("~"),
@@ -625,9 +678,38 @@ fn field_match_operator(s: Span) -> IResult<Span, ValueMatchExpressionDiscrimina
     )))(s)
 }
 
+/// Fields that can be matched by name instead of by `field:<uuid>`.
+fn known_field_id(s: Span) -> IResult<Span, Uuid> {
+    with_ws(alt((
+        map(tag_no_case("rating"), |_| crate::fields::general::RATING.id),
+        map(
+            alt((
+                tag_no_case("palette"),
+                tag_no_case("colour"),
+                tag_no_case("color"),
+            )),
+            |_| crate::fields::image::PALETTE.id,
+        ),
+        map(
+            alt((
+                tag_no_case("favourite"),
+                tag_no_case("favorite"),
+                tag_no_case("fav"),
+            )),
+            |_| crate::fields::general::FAVOURITE.id,
+        ),
+    )))(s)
+}
+
 fn field_match(s: Span) -> IResult<Span, FilterExpressionParseNode> {
-    record_range(|s| {
-        let (s, (id, op)) = pair(preceded(tag_ws("field:"), uuid), field_match_operator)(s)?;
+    let by_name = !s
+        .trim_start_matches(|c| WHITESPACE.contains(c))
+        .starts_with("field:");
+    let (s, mut node) = record_range::<FilterExpressionParseNode>(|s| {
+        let (s, (id, op)) = pair(
+            alt((preceded(tag_ws("field:"), uuid), known_field_id)),
+            field_match_operator,
+        )(s)?;
 
         let (s, expr) = match op {
             ValueMatchExpressionDiscriminants::Equals => {
@@ -665,10 +747,13 @@ fn field_match(s: Span) -> IResult<Span, FilterExpressionParseNode> {
             ValueMatchExpressionDiscriminants::Regex => {
                 map(regex_literal, |re| ValueMatchExpression::Regex(re.into()))(s)
             }
+            ValueMatchExpressionDiscriminants::Near => near_match(s),
         }?;
 
         Ok((s, FilterExpression::FieldMatch(id, expr)))
-    })(s)
+    })(s)?;
+    node.by_name = by_name;
+    Ok((s, node))
 }
 
 fn filter_expression_implicit_and(s: Span) -> IResult<Span, FilterExpressionParseNode> {
@@ -802,6 +887,9 @@ pub struct FilterExpressionParseNode {
     pub expr: FilterExpression,
     children: Vec<FilterExpressionParseNode>,
     pub range: Range<usize>,
+    /// Set for field matches written with a field name such as `rating` instead of `field:<uuid>`.
+    #[serde(default)]
+    by_name: bool,
 }
 
 impl ParseNode for FilterExpressionParseNode {
@@ -822,6 +910,7 @@ impl FilterExpressionParseNode {
             expr,
             children: vec![],
             range,
+            by_name: false,
         }
     }
 
@@ -834,6 +923,7 @@ impl FilterExpressionParseNode {
             expr,
             children,
             range,
+            by_name: false,
         }
     }
 
@@ -854,7 +944,7 @@ impl ReplacementNode for FilterExpressionParseNode {
 
         match &self.expr {
             FilterExpression::TagMatch(_) => Some(self.range.clone()),
-            FilterExpression::FieldMatch(_, _) => {
+            FilterExpression::FieldMatch(_, _) if !self.by_name => {
                 Some(self.range.start..(self.range.start + FIELD_MATCH_REPLACEMENT_LENGTH))
             }
             _ => None,
@@ -1710,9 +1800,53 @@ mod test {
                 regex(id1, r"6..\.."),
                 filter_expression(s(&format!(r"field:{id1} like /6..\../"))),
             );
+
+            let rating = crate::fields::general::RATING.id;
+            let favourite = crate::fields::general::FAVOURITE.id;
+            assert_ok_node(ge(rating, V::int(4)), filter_expression(s("rating>=4")));
+            let (_, node) = filter_expression(s("rating>=4")).unwrap();
+            assert_eq!(node.replacement_range(), None);
+
+            assert_ok_node(
+                FilterExpression::And(
+                    Box::new(FilterExpression::Untagged),
+                    Box::new(FilterExpression::Not(Box::new(FilterExpression::TagMatch(
+                        id1,
+                    )))),
+                ),
+                filter_expression(s(&format!("is:untagged -field:{id1}"))),
+            );
+            assert_ok_node(
+                eq(favourite, V::boolean(true)),
+                filter_expression(s("fav = yes")),
+            );
+
+            assert_ok_node(
+                FilterExpression::TextSearch("ratings".into()),
+                filter_expression(s("ratings")),
+            );
         });
     }
 
+    #[test]
+    fn test_near_match() {
+        let palette = crate::fields::image::PALETTE.id;
+        let near = |colour: [u8; 3], share: f64| {
+            FilterExpression::FieldMatch(
+                palette,
+                ValueMatchExpression::Near(V::colour(colour.into()), share.into()),
+            )
+        };
+        assert_ok_node(
+            near([0, 128, 128], 0.5),
+            filter_expression(s("colour near teal")),
+        );
+        assert_ok_node(
+            near([0x12, 0x34, 0x56], 0.2),
+            filter_expression(s("palette \u{2248} #123456 20%")),
+        );
+    }
+
     fn field_replacement_parse_node(id: Uuid, range: Range<usize>) -> FieldReplacementParseNode {
         FieldReplacementParseNode {
             node: FieldReplacementNode::Field(id),
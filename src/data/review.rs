@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A fixed queue of items to be tagged one at a time, stored in the vault so that it can be
+/// resumed later.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewSession {
+    pub filter: String,
+    pub paths: Vec<String>,
+    pub position: usize,
+    /// The furthest the session has gotten, which stays the same when going back.
+    pub reviewed: usize,
+    pub started: DateTime<Utc>,
+}
+
+impl ReviewSession {
+    pub fn new(filter: String, paths: Vec<String>) -> Self {
+        Self {
+            filter,
+            paths,
+            position: 0,
+            reviewed: 0,
+            started: Utc::now(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.paths.get(self.position).map(String::as_str)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.paths.len()
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.position > 0
+    }
+
+    pub fn advance(&mut self) {
+        if !self.is_finished() {
+            self.position += 1;
+            self.reviewed = self.reviewed.max(self.position);
+        }
+    }
+
+    pub fn back(&mut self) {
+        self.position = self.position.saturating_sub(1);
+    }
+
+    /// Drops the current item from the queue, such as when it's no longer in the vault.
+    pub fn remove_current(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+        self.paths.remove(self.position);
+        if self.position < self.reviewed {
+            self.reviewed -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_review_progress() {
+        let paths = ["a.png", "b.png", "c.png"].map(String::from).to_vec();
+        let mut session = ReviewSession::new(String::new(), paths);
+        assert_eq!(session.current(), Some("a.png"));
+        assert!(!session.can_go_back());
+
+        session.advance();
+        session.advance();
+        assert_eq!(session.current(), Some("c.png"));
+        assert_eq!(session.reviewed, 2);
+
+        session.back();
+        assert_eq!(session.current(), Some("b.png"));
+        assert_eq!(session.reviewed, 2);
+
+        session.remove_current();
+        assert_eq!(session.current(), Some("c.png"));
+        assert_eq!(session.reviewed, 1);
+        assert_eq!(session.len(), 2);
+
+        session.advance();
+        session.advance();
+        assert!(session.is_finished());
+        assert_eq!(session.current(), None);
+        assert_eq!(session.reviewed, 2);
+    }
+}
@@ -1,15 +1,188 @@
 use eframe::egui;
+use eframe::egui::KeyboardShortcut;
+use serde::{Deserialize, Serialize};
+use strum::Display;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+use crate::data::FieldValue;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ShortcutAction {
     #[default]
     None,
     ToggleTag(Uuid),
+    SetField(Uuid, FieldValue),
+    RemoveField(Uuid),
+    TagSelection(Uuid),
+    ToggleSkip,
+    OpenPreview,
+    RemoveItem,
+    Search(String),
+    RunPreset(String),
+}
+
+impl ShortcutAction {
+    /// One action of each kind, in the order they are offered when editing a shortcut.
+    pub fn kinds() -> [Self; 10] {
+        [
+            Self::None,
+            Self::ToggleTag(Uuid::nil()),
+            Self::SetField(Uuid::nil(), FieldValue::Tag),
+            Self::RemoveField(Uuid::nil()),
+            Self::TagSelection(Uuid::nil()),
+            Self::ToggleSkip,
+            Self::OpenPreview,
+            Self::RemoveItem,
+            Self::Search(String::new()),
+            Self::RunPreset(String::new()),
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::None => "Do nothing",
+            Self::ToggleTag(_) => "Toggle tag",
+            Self::SetField(..) => "Set field",
+            Self::RemoveField(_) => "Remove field",
+            Self::TagSelection(_) => "Tag all selected",
+            Self::ToggleSkip => "Toggle skip",
+            Self::OpenPreview => "Open preview",
+            Self::RemoveItem => "Remove from vault",
+            Self::Search(_) => "Search",
+            Self::RunPreset(_) => "Run transform preset",
+        }
+    }
+
+    pub fn is_same_kind(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ShortcutBehaviour {
     pub action: ShortcutAction,
     pub move_next: bool,
 }
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
+pub enum ShortcutProfileScope {
+    #[default]
+    #[strum(to_string = "All vaults")]
+    App,
+    #[strum(to_string = "This vault")]
+    Vault,
+}
+
+/// Identifies a profile by name, either among the app's profiles or the current vault's.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ShortcutProfileRef {
+    pub name: String,
+    pub scope: ShortcutProfileScope,
+}
+
+impl std::fmt::Display for ShortcutProfileRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.scope {
+            ShortcutProfileScope::App => write!(f, "{}", self.name),
+            ShortcutProfileScope::Vault => write!(f, "{} (vault)", self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShortcutProfile {
+    pub name: String,
+    pub shortcuts: Vec<(KeyboardShortcut, ShortcutBehaviour)>,
+}
+
+macro_rules! shortcut {
+    ($modifier:ident + $key:ident) => {
+        KeyboardShortcut::new(egui::Modifiers::$modifier, egui::Key::$key)
+    };
+}
+
+const DEFAULT_SHORTCUTS: [KeyboardShortcut; 10] = [
+    shortcut!(CTRL + Num1),
+    shortcut!(CTRL + Num2),
+    shortcut!(CTRL + Num3),
+    shortcut!(CTRL + Num4),
+    shortcut!(CTRL + Num5),
+    shortcut!(CTRL + Num6),
+    shortcut!(CTRL + Num7),
+    shortcut!(CTRL + Num8),
+    shortcut!(CTRL + Num9),
+    shortcut!(CTRL + Num0),
+];
+
+impl ShortcutProfile {
+    pub const DEFAULT_NAME: &'static str = "Default";
+
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            shortcuts: vec![],
+        }
+    }
+
+    /// A profile with empty bindings for Ctrl+1 to Ctrl+0.
+    pub fn with_default_shortcuts(mut self) -> Self {
+        for shortcut in DEFAULT_SHORTCUTS {
+            self.set(shortcut, ShortcutBehaviour::default());
+        }
+        self
+    }
+
+    pub fn get(&self, shortcut: KeyboardShortcut) -> Option<&ShortcutBehaviour> {
+        self.shortcuts
+            .iter()
+            .find_map(|(k, v)| (*k == shortcut).then_some(v))
+    }
+
+    pub fn set(&mut self, shortcut: KeyboardShortcut, behaviour: ShortcutBehaviour) {
+        match self.shortcuts.iter_mut().find(|(k, _)| *k == shortcut) {
+            Some((_, v)) => *v = behaviour,
+            None => self.shortcuts.push((shortcut, behaviour)),
+        }
+    }
+
+    pub fn remove(&mut self, shortcut: KeyboardShortcut) {
+        self.shortcuts.retain(|(k, _)| *k != shortcut);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_profile_bindings() {
+        let mut profile = ShortcutProfile::new("test".to_string()).with_default_shortcuts();
+        assert_eq!(profile.shortcuts.len(), 10);
+
+        let key = KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::R);
+        let behaviour = ShortcutBehaviour {
+            action: ShortcutAction::ToggleSkip,
+            move_next: true,
+        };
+        profile.set(key, behaviour.clone());
+        profile.set(DEFAULT_SHORTCUTS[0], behaviour.clone());
+        assert_eq!(profile.shortcuts.len(), 11);
+        assert_eq!(profile.get(key), Some(&behaviour));
+        assert_eq!(profile.get(DEFAULT_SHORTCUTS[0]), Some(&behaviour));
+
+        profile.remove(key);
+        assert_eq!(profile.get(key), None);
+        assert_eq!(
+            profile.shortcuts.first().map(|(k, _)| *k),
+            Some(DEFAULT_SHORTCUTS[0])
+        );
+    }
+
+    #[test]
+    fn test_action_compat() {
+        let id = Uuid::new_v4();
+        let json = format!("{{\"ToggleTag\":\"{id}\"}}");
+        let action: ShortcutAction = serde_json::from_str(&json).unwrap();
+        assert_eq!(action, ShortcutAction::ToggleTag(id));
+    }
+}
@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::tasks::download::GalleryDLParams;
+
+const MAX_HISTORY_LEN: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubscriptionRun {
+    pub started: DateTime<Utc>,
+    pub finished: DateTime<Utc>,
+    pub new_files: usize,
+    pub error: Option<String>,
+}
+
+/// A named gallery-dl download that is re-run periodically into the vault's root directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subscription {
+    pub id: Uuid,
+    pub name: String,
+    pub params: GalleryDLParams,
+    pub interval_hours: Option<u32>,
+    pub last_run: Option<DateTime<Utc>>,
+    /// Relative to the vault root, passed to gallery-dl as `--download-archive` so that files
+    /// already downloaded are skipped without needing to check the disk.
+    pub archive_file: String,
+    #[serde(default)]
+    pub history: Vec<SubscriptionRun>,
+}
+
+impl Subscription {
+    pub fn new(name: String, params: GalleryDLParams) -> Self {
+        let id = Uuid::new_v4();
+        Self {
+            id,
+            name,
+            params,
+            interval_hours: None,
+            last_run: None,
+            archive_file: format!(".gallery-dl-archive-{id}.sqlite3"),
+            history: vec![],
+        }
+    }
+
+    pub fn with_interval_hours(mut self, interval_hours: Option<u32>) -> Self {
+        self.interval_hours = interval_hours;
+        self
+    }
+
+    pub fn archive_path(&self, root_dir: &Path) -> PathBuf {
+        root_dir.join(&self.archive_file)
+    }
+
+    pub fn next_run(&self) -> Option<DateTime<Utc>> {
+        let interval = Duration::hours(self.interval_hours?.into());
+        Some(
+            self.last_run
+                .map_or(DateTime::<Utc>::MIN_UTC, |last| last + interval),
+        )
+    }
+
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.next_run().is_some_and(|next| next <= now)
+    }
+
+    pub fn total_new_files(&self) -> usize {
+        self.history.iter().map(|r| r.new_files).sum()
+    }
+
+    pub fn record_run(&mut self, run: SubscriptionRun) {
+        self.last_run = Some(run.started);
+        self.history.push(run);
+        if self.history.len() > MAX_HISTORY_LEN {
+            self.history.drain(..self.history.len() - MAX_HISTORY_LEN);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_overdue() {
+        let now = Utc::now();
+        let mut sub = Subscription::new("likes".to_string(), GalleryDLParams::default());
+        assert!(!sub.is_overdue(now));
+
+        sub = sub.with_interval_hours(Some(24));
+        assert!(sub.is_overdue(now));
+
+        sub.record_run(SubscriptionRun {
+            started: now - Duration::hours(2),
+            finished: now - Duration::hours(1),
+            new_files: 3,
+            error: None,
+        });
+        assert!(!sub.is_overdue(now));
+        assert!(sub.is_overdue(now + Duration::hours(23)));
+        assert_eq!(sub.total_new_files(), 3);
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::sync::Mutex;
+
+use itertools::Itertools;
+use uuid::Uuid;
+
+use crate::data::{FieldStore, FieldType, Item, Vault};
+use crate::fields;
+
+const FOLDER_WEIGHT: f32 = 0.5;
+const AUTHOR_WEIGHT: f32 = 0.75;
+const LINK_WEIGHT: f32 = 1.0;
+const MIN_SCORE: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SuggestionSource {
+    CoOccurrence,
+    Folder,
+    Author,
+    Link,
+}
+
+impl Display for SuggestionSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CoOccurrence => write!(f, "often appears with these tags"),
+            Self::Folder => write!(f, "common in this folder"),
+            Self::Author => write!(f, "common for this author"),
+            Self::Link => write!(f, "present on a linked item"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagSuggestion {
+    pub id: Uuid,
+    pub score: f32,
+    pub sources: Vec<SuggestionSource>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct ItemSnapshot {
+    tags: HashSet<Uuid>,
+    folder: String,
+    author_id: Option<i64>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Counts {
+    total: usize,
+    tags: HashMap<Uuid, usize>,
+}
+
+impl Counts {
+    fn add(&mut self, tags: &HashSet<Uuid>) {
+        self.total += 1;
+        for tag in tags {
+            *self.tags.entry(*tag).or_default() += 1;
+        }
+    }
+
+    fn remove(&mut self, tags: &HashSet<Uuid>) {
+        self.total = self.total.saturating_sub(1);
+        for tag in tags {
+            if let Some(count) = self.tags.get_mut(tag) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    self.tags.remove(tag);
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn frequency(&self, tag: &Uuid) -> f32 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.tags
+            .get(tag)
+            .map_or(0.0, |c| *c as f32 / self.total as f32)
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Inner {
+    items: HashMap<String, ItemSnapshot>,
+    tag_counts: HashMap<Uuid, usize>,
+    co_counts: HashMap<Uuid, HashMap<Uuid, usize>>,
+    folders: HashMap<String, Counts>,
+    authors: HashMap<i64, Counts>,
+}
+
+impl Inner {
+    fn add(&mut self, snapshot: &ItemSnapshot) {
+        for tag in &snapshot.tags {
+            *self.tag_counts.entry(*tag).or_default() += 1;
+            let co = self.co_counts.entry(*tag).or_default();
+            for other in snapshot.tags.iter().filter(|o| *o != tag) {
+                *co.entry(*other).or_default() += 1;
+            }
+        }
+
+        self.folders
+            .entry(snapshot.folder.clone())
+            .or_default()
+            .add(&snapshot.tags);
+        if let Some(author_id) = snapshot.author_id {
+            self.authors
+                .entry(author_id)
+                .or_default()
+                .add(&snapshot.tags);
+        }
+    }
+
+    /// Undoes [`Inner::add`], dropping any counts that reach zero so that the result is the
+    /// same as if the item had never been added.
+    fn remove(&mut self, snapshot: &ItemSnapshot) {
+        for tag in &snapshot.tags {
+            if let Some(count) = self.tag_counts.get_mut(tag) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    self.tag_counts.remove(tag);
+                    self.co_counts.remove(tag);
+                    continue;
+                }
+            }
+            if let Some(co) = self.co_counts.get_mut(tag) {
+                for other in snapshot.tags.iter().filter(|o| *o != tag) {
+                    if let Some(count) = co.get_mut(other) {
+                        *count = count.saturating_sub(1);
+                        if *count == 0 {
+                            co.remove(other);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(counts) = self.folders.get_mut(&snapshot.folder) {
+            counts.remove(&snapshot.tags);
+            if counts.total == 0 {
+                self.folders.remove(&snapshot.folder);
+            }
+        }
+        if let Some(author_id) = snapshot.author_id {
+            if let Some(counts) = self.authors.get_mut(&author_id) {
+                counts.remove(&snapshot.tags);
+                if counts.total == 0 {
+                    self.authors.remove(&author_id);
+                }
+            }
+        }
+    }
+}
+
+/// Tag co-occurrence statistics of a vault, used to suggest tags for items. The statistics are
+/// built once when the vault is loaded and then updated incrementally as items are committed.
+#[derive(Debug, Default)]
+pub struct TagStats {
+    inner: Mutex<Inner>,
+}
+
+fn is_user_tag(vault: &Vault, id: &Uuid) -> bool {
+    vault.get_definition(id).is_some_and(|def| {
+        def.field_type == FieldType::Tag && !def.has_field(&fields::meta::NO_LINK.id)
+    })
+}
+
+pub fn folder_of(path: &str) -> String {
+    Path::new(path)
+        .parent()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn snapshot_of(vault: &Vault, item: &Item) -> ItemSnapshot {
+    ItemSnapshot {
+        tags: item
+            .iter_fields()
+            .map(|f| *f.key())
+            .filter(|id| is_user_tag(vault, id))
+            .collect(),
+        folder: folder_of(item.path()),
+        author_id: item
+            .get_known_field_value(fields::tweet::AUTHOR_ID)
+            .ok()
+            .flatten(),
+    }
+}
+
+impl TagStats {
+    pub fn rebuild(&self, vault: &Vault) {
+        let mut inner = Inner::default();
+        for item in vault.iter_items() {
+            let snapshot = snapshot_of(vault, &item);
+            inner.add(&snapshot);
+            inner.items.insert(item.path().to_string(), snapshot);
+        }
+        *self.inner.lock().unwrap() = inner;
+    }
+
+    pub fn update_item(&self, vault: &Vault, item: &Item) {
+        let snapshot = snapshot_of(vault, item);
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(old) = inner.items.remove(item.path()) {
+            inner.remove(&old);
+        }
+        inner.add(&snapshot);
+        inner.items.insert(item.path().to_string(), snapshot);
+    }
+
+    pub fn remove_item(&self, path: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(old) = inner.items.remove(path) {
+            inner.remove(&old);
+        }
+    }
+
+    /// Ranks tags not already in `tags` by how likely they are to apply to an item with the
+    /// given tags, folder, author and tags of linked items.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn suggest(
+        &self,
+        vault: &Vault,
+        tags: &HashSet<Uuid>,
+        folder: &str,
+        author_id: Option<i64>,
+        linked_tags: &HashSet<Uuid>,
+        max_suggestions: usize,
+    ) -> Vec<TagSuggestion> {
+        let inner = self.inner.lock().unwrap();
+        let mut scores: HashMap<Uuid, (f32, Vec<SuggestionSource>)> = HashMap::new();
+        let mut add_score = |id: Uuid, score: f32, source: SuggestionSource| {
+            if score <= 0.0 || tags.contains(&id) {
+                return;
+            }
+            let entry = scores.entry(id).or_default();
+            entry.0 += score;
+            if !entry.1.contains(&source) {
+                entry.1.push(source);
+            }
+        };
+
+        let n_tags = tags.len().max(1) as f32;
+        for tag in tags {
+            let Some(tag_count) = inner.tag_counts.get(tag).filter(|c| **c > 0) else {
+                continue;
+            };
+            for (other, count) in inner.co_counts.get(tag).into_iter().flatten() {
+                let score = *count as f32 / *tag_count as f32 / n_tags;
+                add_score(*other, score, SuggestionSource::CoOccurrence);
+            }
+        }
+
+        if let Some(counts) = inner.folders.get(folder) {
+            for id in counts.tags.keys() {
+                let score = FOLDER_WEIGHT * counts.frequency(id);
+                add_score(*id, score, SuggestionSource::Folder);
+            }
+        }
+
+        if let Some(counts) = author_id.and_then(|a| inner.authors.get(&a)) {
+            for id in counts.tags.keys() {
+                let score = AUTHOR_WEIGHT * counts.frequency(id);
+                add_score(*id, score, SuggestionSource::Author);
+            }
+        }
+
+        for id in linked_tags {
+            add_score(*id, LINK_WEIGHT, SuggestionSource::Link);
+        }
+
+        scores
+            .into_iter()
+            .filter(|(id, (score, _))| *score >= MIN_SCORE && is_user_tag(vault, id))
+            .map(|(id, (score, sources))| TagSuggestion { id, score, sources })
+            .sorted_by(|a, b| b.score.total_cmp(&a.score).then(a.id.cmp(&b.id)))
+            .take(max_suggestions)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::{FieldDefinition, FieldValue};
+
+    fn test_vault() -> (Vault, [Uuid; 5]) {
+        let vault = Vault::new("test".to_string());
+        let ids = ["cat", "dog", "tree", "sky", "sea"].map(|name| {
+            let def = FieldDefinition::tag(Uuid::new_v4(), name.into());
+            let id = def.id;
+            vault.set_definition(def);
+            id
+        });
+        let [cat, dog, tree, sky, _] = ids;
+        for (path, tags, author_id) in [
+            ("x/1.png", vec![cat, dog], None),
+            ("x/2.png", vec![cat, dog], None),
+            ("x/3.png", vec![tree], None),
+            ("y/4.png", vec![sky], Some(7)),
+        ] {
+            let item = vault.get_item_or_init(Path::new(path)).unwrap();
+            for tag in tags {
+                item.set_field_value(tag, FieldValue::Tag);
+            }
+            if let Some(author_id) = author_id {
+                item.set_known_field_value(fields::tweet::AUTHOR_ID, author_id);
+            }
+        }
+        (vault, ids)
+    }
+
+    fn rebuilt(vault: &Vault) -> TagStats {
+        let stats = TagStats::default();
+        stats.rebuild(vault);
+        stats
+    }
+
+    fn assert_same(stats: &TagStats, vault: &Vault) {
+        assert_eq!(
+            *stats.inner.lock().unwrap(),
+            *rebuilt(vault).inner.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_suggest() {
+        let (vault, [cat, dog, tree, sky, sea]) = test_vault();
+        let stats = rebuilt(&vault);
+
+        let suggestions = stats.suggest(
+            &vault,
+            &HashSet::from([cat]),
+            "x",
+            Some(7),
+            &HashSet::from([sea]),
+            10,
+        );
+        let ranked = suggestions.iter().map(|s| s.id).collect_vec();
+        assert_eq!(ranked, vec![dog, sea, sky, tree]);
+        assert_eq!(
+            suggestions[0].sources,
+            vec![SuggestionSource::CoOccurrence, SuggestionSource::Folder]
+        );
+        assert_eq!(suggestions[1].sources, vec![SuggestionSource::Link]);
+        assert_eq!(suggestions[2].sources, vec![SuggestionSource::Author]);
+        assert_eq!(suggestions[3].sources, vec![SuggestionSource::Folder]);
+
+        let suggestions = stats.suggest(&vault, &HashSet::new(), "y", None, &HashSet::new(), 10);
+        assert_eq!(suggestions.iter().map(|s| s.id).collect_vec(), vec![sky]);
+    }
+
+    #[test]
+    fn test_update_item() {
+        let (vault, [cat, dog, tree, _, sea]) = test_vault();
+        let stats = rebuilt(&vault);
+
+        let item = vault.get_item(Path::new("x/1.png")).unwrap();
+        item.set_field_value(sea, FieldValue::Tag);
+        stats.update_item(&vault, &item);
+        assert_same(&stats, &vault);
+
+        let item = vault.get_item(Path::new("x/2.png")).unwrap();
+        item.remove_field(&cat);
+        item.remove_field(&dog);
+        stats.update_item(&vault, &item);
+        assert_same(&stats, &vault);
+
+        let item = vault.get_item(Path::new("x/3.png")).unwrap();
+        item.remove_field(&tree);
+        stats.update_item(&vault, &item);
+        assert_same(&stats, &vault);
+
+        let item = vault.get_item_or_init(Path::new("z/5.png")).unwrap();
+        item.set_field_value(tree, FieldValue::Tag);
+        stats.update_item(&vault, &item);
+        assert_same(&stats, &vault);
+    }
+
+    #[test]
+    fn test_remove_item() {
+        let (vault, [cat, dog, _, sky, _]) = test_vault();
+        let stats = rebuilt(&vault);
+
+        for path in ["x/1.png", "y/4.png"] {
+            vault.remove_item(Path::new(path)).unwrap();
+            stats.remove_item(path);
+            assert_same(&stats, &vault);
+        }
+
+        // nothing is left in folder y or by author 7, so only the co-occurrence remains
+        let suggestions = stats.suggest(
+            &vault,
+            &HashSet::from([cat]),
+            "y",
+            Some(7),
+            &HashSet::new(),
+            10,
+        );
+        assert_eq!(suggestions.iter().map(|s| s.id).collect_vec(), vec![dog]);
+        assert!(suggestions.iter().all(|s| s.id != sky));
+    }
+}
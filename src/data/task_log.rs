@@ -0,0 +1,266 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::errors::AppError;
+use crate::tasks::transform::PathContext;
+use crate::tasks::{AsyncTaskResult, AsyncTaskReturn};
+
+const MAX_LOG_LEN: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskLogError {
+    /// The name of the [`AppError`] variant that caused the error, if any.
+    pub kind: Option<String>,
+    /// The outermost context first, down to the root cause.
+    pub chain: Vec<String>,
+}
+
+impl TaskLogError {
+    pub fn from_error(e: &anyhow::Error) -> Self {
+        Self {
+            kind: e
+                .downcast_ref::<AppError>()
+                .map(|app_e| <&str>::from(app_e).to_string()),
+            chain: e.chain().map(|c| c.to_string()).collect(),
+        }
+    }
+}
+
+impl Display for TaskLogError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.chain.join(": "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskLogItem {
+    pub path: Option<String>,
+    pub error: TaskLogError,
+}
+
+impl TaskLogItem {
+    pub fn from_error(e: &anyhow::Error) -> Self {
+        Self {
+            path: e
+                .downcast_ref::<PathContext>()
+                .map(|p| p.0.to_string_lossy().into_owned()),
+            error: TaskLogError::from_error(e),
+        }
+    }
+}
+
+/// A record of a finished task, kept so that its failures can be looked at after any dialogs
+/// showing its result have been closed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskLogEntry {
+    pub name: String,
+    /// The vault the task ran on, which the paths of failed items are resolved against.
+    pub vault: Option<String>,
+    pub started: DateTime<Utc>,
+    pub finished: DateTime<Utc>,
+    pub succeeded: usize,
+    /// Items that were not processed because the task was cancelled.
+    pub cancelled: usize,
+    pub failed: Vec<TaskLogItem>,
+    /// Set if the task as a whole failed.
+    pub error: Option<TaskLogError>,
+}
+
+fn count_results<T>(results: &[anyhow::Result<T>]) -> (usize, Vec<TaskLogItem>) {
+    let failed = results
+        .iter()
+        .filter_map(|r| r.as_ref().err())
+        .map(TaskLogItem::from_error)
+        .collect::<Vec<_>>();
+    (results.len() - failed.len(), failed)
+}
+
+impl TaskLogEntry {
+    /// Creates an entry for the result of a task, or returns [`None`] if the result is not
+    /// worth recording. Only tasks that failed or that have per-item results are recorded, so
+    /// that routine tasks such as saving the vault don't crowd out the rest of the log.
+    pub fn new(
+        name: String,
+        vault: Option<String>,
+        started: DateTime<Utc>,
+        result: &AsyncTaskReturn,
+    ) -> Option<Self> {
+        let mut entry = Self {
+            name,
+            vault,
+            started,
+            finished: Utc::now(),
+            succeeded: 0,
+            cancelled: 0,
+            failed: vec![],
+            error: None,
+        };
+        if entry.name.is_empty() {
+            return None;
+        }
+
+        match result {
+            Ok(
+                AsyncTaskResult::ImportComplete { results, .. }
+                | AsyncTaskResult::InboxImportComplete { results, .. }
+                | AsyncTaskResult::LinkComplete { results, .. }
+                | AsyncTaskResult::BatchComplete { results, .. },
+            ) => {
+                (entry.succeeded, entry.failed) = count_results(results);
+            }
+            Ok(AsyncTaskResult::TransformationComplete { results, cancelled }) => {
+                (entry.succeeded, entry.failed) = count_results(results);
+                entry.cancelled = *cancelled;
+            }
+            Ok(
+                AsyncTaskResult::None
+                | AsyncTaskResult::VaultLoaded { .. }
+                | AsyncTaskResult::VaultSaved(_)
+                | AsyncTaskResult::DefinitionsLoaded { .. }
+                | AsyncTaskResult::VaultStats(_)
+                | AsyncTaskResult::ThumbnailLoaded { .. }
+                | AsyncTaskResult::FoundGalleryDl { .. }
+                | AsyncTaskResult::PreviewReady { .. }
+                | AsyncTaskResult::SelectedDirectory(_)
+                | AsyncTaskResult::SelectedFile(_)
+                | AsyncTaskResult::QueryResult(_)
+                | AsyncTaskResult::NextItem,
+            ) => return None,
+            Err(e) if AppError::UserCancelled.is_err(e) => return None,
+            Err(e) => entry.error = Some(TaskLogError::from_error(e)),
+        }
+
+        Some(entry)
+    }
+
+    pub fn duration(&self) -> chrono::Duration {
+        self.finished - self.started
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.error.is_none() && self.failed.is_empty()
+    }
+
+    /// The errors of the task, whether of the task as a whole or of individual items.
+    pub fn iter_errors(&self) -> impl Iterator<Item = &TaskLogError> {
+        self.error
+            .iter()
+            .chain(self.failed.iter().map(|item| &item.error))
+    }
+}
+
+/// The history of finished tasks, which is persisted with the rest of the app's state.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(transparent)]
+pub struct TaskLog {
+    entries: VecDeque<TaskLogEntry>,
+}
+
+impl TaskLog {
+    /// Reads a saved log, skipping any entries that can't be read (e.g. because they were saved
+    /// by a newer version) instead of failing the whole log.
+    pub fn deserialize_lenient<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let entries = Vec::<serde_json::Value>::deserialize(deserializer)?
+            .into_iter()
+            .filter_map(|value| match serde_json::from_value(value) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    tracing::warn!("skipping task log entry that could not be read: {e}");
+                    None
+                }
+            })
+            .take(MAX_LOG_LEN)
+            .collect();
+        Ok(Self { entries })
+    }
+
+    pub fn push(&mut self, entry: TaskLogEntry) {
+        self.entries.push_front(entry);
+        self.entries.truncate(MAX_LOG_LEN);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The most recent entries first.
+    pub fn iter(&self) -> impl Iterator<Item = &TaskLogEntry> {
+        self.entries.iter()
+    }
+
+    pub fn error_kinds(&self) -> BTreeSet<String> {
+        self.entries
+            .iter()
+            .flat_map(TaskLogEntry::iter_errors)
+            .filter_map(|e| e.kind.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tasks::transform::TransformResult;
+    use anyhow::Context;
+    use itertools::Itertools;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_task_log_entry() {
+        let results: Vec<anyhow::Result<TransformResult>> = vec![
+            Ok(TransformResult::NoTransform(PathBuf::from("/vault/a.png"))),
+            Err(anyhow::Error::from(AppError::MissingFile {
+                abs_path: PathBuf::from("/vault/b.png"),
+            }))
+            .context("while moving")
+            .with_context(|| PathContext(PathBuf::from("/vault/b.png"))),
+        ];
+        let entry = TaskLogEntry::new(
+            "Transform images".to_string(),
+            Some("vault".to_string()),
+            Utc::now(),
+            &Ok(AsyncTaskResult::TransformationComplete {
+                results,
+                cancelled: 3,
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(entry.succeeded, 1);
+        assert_eq!(entry.cancelled, 3);
+        assert!(!entry.is_success());
+        let failed = &entry.failed[0];
+        assert_eq!(failed.path.as_deref(), Some("/vault/b.png"));
+        assert_eq!(failed.error.kind.as_deref(), Some("MissingFile"));
+        assert_eq!(failed.error.chain.len(), 3);
+        assert_eq!(failed.error.chain[1], "while moving");
+
+        let mut log = TaskLog::default();
+        log.push(entry);
+        assert_eq!(
+            log.error_kinds().into_iter().collect::<Vec<_>>(),
+            vec!["MissingFile".to_string()]
+        );
+
+        let json = serde_json::to_string(&log).unwrap();
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        let loaded = TaskLog::deserialize_lenient(&mut deserializer).unwrap();
+        assert_eq!(loaded.iter().collect_vec(), log.iter().collect_vec());
+        let mut deserializer = serde_json::Deserializer::from_str(r#"[{"name": 1}]"#);
+        let loaded = TaskLog::deserialize_lenient(&mut deserializer).unwrap();
+        assert_eq!(loaded.iter().count(), 0);
+
+        let cancelled: AsyncTaskReturn = Err(AppError::UserCancelled.into());
+        assert!(TaskLogEntry::new("Import".to_string(), None, Utc::now(), &cancelled).is_none());
+
+        let saved: AsyncTaskReturn = Ok(AsyncTaskResult::VaultSaved("vault".to_string()));
+        assert!(TaskLogEntry::new("Save vault".to_string(), None, Utc::now(), &saved).is_none());
+        let failed: AsyncTaskReturn = Err(anyhow::anyhow!("disk full"));
+        assert!(TaskLogEntry::new("Save vault".to_string(), None, Utc::now(), &failed).is_some());
+    }
+}
@@ -1,9 +1,11 @@
 use eframe::egui::Color32;
 use ordered_float::OrderedFloat;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ImageParams {
+    pub geometry: GeometryOptions,
     pub scale: ScaleOptions,
     pub infill: InfillOptions,
     pub compression: CompressionOptions,
@@ -11,6 +13,7 @@ pub struct ImageParams {
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
 pub struct BulkParams {
     pub source: SourceOptions,
     pub destination: DestinationOptions,
@@ -30,6 +33,7 @@ pub enum SourceKind {
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SourceOptions {
     pub kind: SourceKind,
     pub delete_source: bool,
@@ -65,6 +69,7 @@ pub enum DestinationExistingBehaviour {
 
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DestinationOptions {
     pub kind: DestinationKind,
     pub vault_subdirectory: String,
@@ -79,6 +84,94 @@ pub struct DestinationOptions {
 
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeometryOptions {
+    pub enabled: bool,
+    pub auto_orient: bool,
+    pub rotation: Rotation,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    pub trim_borders: bool,
+    pub trim_tolerance: OrderedFloat<f32>,
+    pub crop: bool,
+    pub crop_aspect_ratio: (OrderedFloat<f32>, OrderedFloat<f32>),
+    pub crop_anchor: CropAnchor,
+    /// The focal point of the item being transformed as a fraction of its width and height,
+    /// which is taken from the item rather than saved with the options.
+    #[serde(skip)]
+    pub focal_point: Option<(OrderedFloat<f32>, OrderedFloat<f32>)>,
+    /// The EXIF orientation of the item being transformed, which is also taken from the item.
+    #[serde(skip)]
+    pub orientation: Option<i64>,
+}
+
+impl Default for GeometryOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auto_orient: true,
+            rotation: Default::default(),
+            flip_horizontal: false,
+            flip_vertical: false,
+            trim_borders: false,
+            trim_tolerance: 0.02.into(),
+            crop: false,
+            crop_aspect_ratio: (16.0.into(), 9.0.into()),
+            crop_anchor: Default::default(),
+            focal_point: None,
+            orientation: None,
+        }
+    }
+}
+
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, derive_more::Display,
+)]
+pub enum Rotation {
+    #[default]
+    #[display("None")]
+    None,
+    #[display("90\u{b0}")]
+    Rotate90,
+    #[display("180\u{b0}")]
+    Rotate180,
+    #[display("270\u{b0}")]
+    Rotate270,
+}
+
+impl Rotation {
+    pub fn degrees(self) -> f64 {
+        match self {
+            Self::None => 0.0,
+            Self::Rotate90 => 90.0,
+            Self::Rotate180 => 180.0,
+            Self::Rotate270 => 270.0,
+        }
+    }
+
+    pub fn swaps_sides(self) -> bool {
+        matches!(self, Self::Rotate90 | Self::Rotate270)
+    }
+}
+
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, derive_more::Display,
+)]
+pub enum CropAnchor {
+    #[default]
+    #[display("Centre")]
+    Centre,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    #[display("Focal point")]
+    FocalPoint,
+}
+
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ScaleOptions {
     pub enabled: bool,
     pub use_target_width: bool,
@@ -151,10 +244,12 @@ pub enum FitAlgorithm {
 
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
 pub struct InfillOptions {
     pub enabled: bool,
     pub target_aspect_ratio: (OrderedFloat<f32>, OrderedFloat<f32>),
     pub technique: InfillTechnique,
+    pub colour_tolerance: OrderedFloat<f32>,
     pub use_auto_solid: bool,
     pub manual_solid_colour: Color32,
     pub use_gaussian: bool,
@@ -171,6 +266,7 @@ impl Default for InfillOptions {
             enabled: false,
             target_aspect_ratio: (16.0.into(), 9.0.into()),
             technique: Default::default(),
+            colour_tolerance: 0.02.into(),
             use_auto_solid: true,
             manual_solid_colour: Color32::BLACK,
             use_gaussian: true,
@@ -191,14 +287,28 @@ pub enum InfillTechnique {
     Automatic,
     Blur,
     Solid,
+    Mirror,
+    #[display("Extend edges")]
+    EdgeExtend,
+    Gradient,
+    Tile,
+    #[display("Blurred tile")]
+    BlurredTile,
+    #[display("Smart crop")]
+    SmartCrop,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CompressionOptions {
     pub enabled: bool,
     pub file_type: CompressionFileType,
+    pub mode: CompressionMode,
     pub quality: u16,
+    pub max_file_size_kib: u32,
+    pub allow_downscale: bool,
     pub chroma_subsampling: ChromaSubsampling,
+    pub strip_metadata: bool,
 }
 
 impl Default for CompressionOptions {
@@ -206,12 +316,27 @@ impl Default for CompressionOptions {
         Self {
             enabled: false,
             file_type: Default::default(),
+            mode: Default::default(),
             quality: 90,
+            max_file_size_kib: 1024,
+            allow_downscale: false,
             chroma_subsampling: Default::default(),
+            strip_metadata: false,
         }
     }
 }
 
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, derive_more::Display,
+)]
+pub enum CompressionMode {
+    #[default]
+    #[display("Fixed quality")]
+    Quality,
+    #[display("Maximum file size")]
+    TargetSize,
+}
+
 #[derive(
     Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, derive_more::Display,
 )]
@@ -223,8 +348,40 @@ pub enum CompressionFileType {
     Png,
     #[display("WEBP")]
     Webp,
+    #[display("WEBP (lossless)")]
+    WebpLossless,
     #[display("AVIF")]
     Avif,
+    #[display("JPEG XL")]
+    JpegXl,
+}
+
+impl CompressionFileType {
+    /// The name of the format as understood by magick.
+    pub fn magick_format(self) -> &'static str {
+        match self {
+            Self::Jpeg => "JPEG",
+            Self::Png => "PNG",
+            Self::Webp | Self::WebpLossless => "WEBP",
+            Self::Avif => "AVIF",
+            Self::JpegXl => "JXL",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::Webp | Self::WebpLossless => "webp",
+            Self::Avif => "avif",
+            Self::JpegXl => "jxl",
+        }
+    }
+
+    /// Whether the quality setting has no effect on how much of the image is kept.
+    pub fn is_lossless(self) -> bool {
+        matches!(self, Self::Png | Self::WebpLossless)
+    }
 }
 
 #[derive(
@@ -242,8 +399,137 @@ pub enum ChromaSubsampling {
     Chroma420,
 }
 
+impl ChromaSubsampling {
+    pub fn sampling_factor(self) -> &'static str {
+        match self {
+            Self::Chroma444 => "4:4:4",
+            Self::Chroma440 => "4:4:0",
+            Self::Chroma422 => "4:2:2",
+            Self::Chroma420 => "4:2:0",
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PathParams {
     pub format: String,
     pub dry_run: bool,
 }
+
+/// The version of [`Preset`] written by this build. Options added later take their default value
+/// when missing, so this only needs to change when existing options are renamed or change
+/// meaning, with the old form handled in [`Preset::upgrade`].
+pub const PRESET_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum PresetParams {
+    Image {
+        bulk: BulkParams,
+        params: ImageParams,
+    },
+    Path {
+        bulk: BulkParams,
+        params: PathParams,
+    },
+}
+
+impl PresetParams {
+    pub fn bulk(&self) -> &BulkParams {
+        match self {
+            Self::Image { bulk, .. } | Self::Path { bulk, .. } => bulk,
+        }
+    }
+}
+
+/// A named set of transform options which can be saved, shared and run without opening the
+/// transform window.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Preset {
+    #[serde(default)]
+    pub version: u32,
+    pub name: String,
+    #[serde(flatten)]
+    pub params: PresetParams,
+}
+
+impl Preset {
+    pub fn new(name: String, params: PresetParams) -> Self {
+        Self {
+            version: PRESET_VERSION,
+            name,
+            params,
+        }
+    }
+
+    pub fn upgrade(mut self) -> Self {
+        self.version = PRESET_VERSION;
+        self
+    }
+
+    /// Reads a list of presets, skipping any that can't be read (e.g. because they were saved by
+    /// a newer version) instead of failing the whole list.
+    pub fn from_values(values: Vec<serde_json::Value>) -> Vec<Self> {
+        values
+            .into_iter()
+            .filter_map(|value| match serde_json::from_value(value) {
+                Ok(preset) => Some(preset),
+                Err(e) => {
+                    tracing::warn!("skipping transform preset that could not be read: {e}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn deserialize_list_lenient<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Self>, D::Error> {
+        Ok(Self::from_values(Vec::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_preset_compat() {
+        let json = r#"{
+            "name": "Wallpaper",
+            "kind": "Image",
+            "bulk": { "destination": { "kind": "Directory", "directory_path": "out" } },
+            "params": { "scale": { "enabled": true, "target_width": 3840 } }
+        }"#;
+        let preset: Preset = serde_json::from_str(json).unwrap();
+        assert_eq!(preset.version, 0);
+
+        let preset = preset.upgrade();
+        assert_eq!(preset.version, PRESET_VERSION);
+        let PresetParams::Image { bulk, params } = &preset.params else {
+            panic!("expected image preset");
+        };
+        assert_eq!(bulk.destination.kind, DestinationKind::Directory);
+        assert_eq!(bulk.source.kind, SourceKind::Selection);
+        assert!(params.scale.enabled);
+        assert_eq!(params.scale.target_width, 3840);
+        assert_eq!(params.scale.target_height, 1080);
+        assert_eq!(params.infill, InfillOptions::default());
+
+        let json = serde_json::to_string(&preset).unwrap();
+        assert_eq!(serde_json::from_str::<Preset>(&json).unwrap(), preset);
+    }
+
+    #[test]
+    fn test_presets_lenient() {
+        let json = r#"[
+            { "name": "Rename", "kind": "Path", "bulk": {}, "params": {} },
+            { "name": "Unknown", "kind": "Video" },
+            { "kind": "Image" }
+        ]"#;
+        let presets = Preset::from_values(serde_json::from_str(json).unwrap());
+        assert_eq!(presets.len(), 1);
+        assert_eq!(presets[0].name, "Rename");
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Context};
 use chrono::{DateTime, TimeZone, Utc};
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::{Debug, Formatter};
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
@@ -16,7 +16,11 @@ use tracing::info;
 use uuid::Uuid;
 
 use crate::data::field_refs::FieldDefRefOrPlaceholder;
-use crate::data::{kind, FieldDefinition, FieldStore, FieldValue, Item, ItemId};
+use crate::data::tag_stats::TagStats;
+use crate::data::{
+    kind, FieldDefinition, FieldStore, FieldValue, Item, ItemId, JobQueue, ReviewSession,
+    ShortcutProfile, Subscription,
+};
 use crate::errors::{AppError, HierarchyError};
 use crate::fields;
 use crate::state::AppStateRef;
@@ -29,11 +33,21 @@ pub struct Vault {
     fields: DashMap<Uuid, FieldValue>,
     items: DashMap<String, Arc<Item>>,
     last_updated: Mutex<Option<DateTime<Utc>>>,
+    #[serde(default)]
+    subscriptions: DashMap<Uuid, Subscription>,
+    #[serde(default)]
+    shortcut_profiles: DashMap<String, ShortcutProfile>,
+    #[serde(default)]
+    review_session: Mutex<Option<ReviewSession>>,
 
     #[serde(skip)]
     pub file_path: Option<Box<Path>>,
     #[serde(skip)]
     items_by_id: DashMap<ItemId, Weak<Item>>,
+    #[serde(skip)]
+    tag_stats: TagStats,
+    #[serde(skip)]
+    jobs: JobQueue,
 }
 
 impl Debug for Vault {
@@ -93,6 +107,11 @@ impl Vault {
         self
     }
 
+    pub fn with_tag_stats(self) -> Self {
+        self.tag_stats.rebuild(&self);
+        self
+    }
+
     pub fn with_standard_defs(self) -> Self {
         for def in fields::defs() {
             self.set_definition((*def).clone());
@@ -157,6 +176,10 @@ impl Vault {
                 item.remove_field(id);
             }
 
+            for def in self.iter_field_defs() {
+                def.remove_implied(*id);
+            }
+
             let desc_ids: Vec<_> = self
                 .iter_descendants(id)
                 .into_iter()
@@ -252,10 +275,15 @@ impl Vault {
     pub fn remove_item(&self, path: &Path) -> anyhow::Result<()> {
         let rel_path = self.resolve_rel_path(path)?;
         self.items.remove(rel_path);
+        self.tag_stats.remove_item(rel_path);
 
         Ok(())
     }
 
+    pub fn tag_stats(&self) -> &TagStats {
+        &self.tag_stats
+    }
+
     pub fn len_items(&self) -> usize {
         self.items.len()
     }
@@ -314,6 +342,73 @@ impl Vault {
         res
     }
 
+    /// Returns all tags implied by the given tags, following implication rules transitively.
+    /// Rules on ancestors also apply, as an item with a tag also has all of its ancestors.
+    pub fn resolve_implied_ids(&self, ids: impl Iterator<Item = Uuid>) -> HashSet<Uuid> {
+        let mut seen = HashSet::new();
+        let mut queue = vec![];
+        for id in ids {
+            queue.extend(self.iter_field_ancestor_paths(&id).into_iter().flatten());
+        }
+
+        let mut implied = HashSet::new();
+        while let Some(id) = queue.pop() {
+            if !seen.insert(id) {
+                continue;
+            }
+
+            let Some(implied_ids) = self
+                .get_definition(&id)
+                .map(|def| def.iter_implied_ids().map(|iid| *iid).collect_vec())
+            else {
+                continue;
+            };
+            for implied_id in implied_ids {
+                if implied.insert(implied_id) {
+                    queue.extend(
+                        self.iter_field_ancestor_paths(&implied_id)
+                            .into_iter()
+                            .flatten(),
+                    );
+                }
+            }
+        }
+
+        implied
+    }
+
+    /// Adds the tags implied by the item's manually applied tags, and removes previously implied
+    /// tags whose rules no longer apply.
+    pub fn apply_implications(&self, item: &Item) -> anyhow::Result<()> {
+        let prev_implied = item.implied_ids()?;
+        let manual_ids: HashSet<_> = item
+            .iter_fields()
+            .map(|f| *f.key())
+            .filter(|id| !prev_implied.contains(id))
+            .collect();
+
+        let implied: HashSet<_> = self
+            .resolve_implied_ids(manual_ids.iter().copied())
+            .into_iter()
+            .filter(|id| !manual_ids.contains(id))
+            .filter(|id| {
+                self.get_definition(id)
+                    .is_some_and(|def| def.field_type == kind::Type::Tag)
+            })
+            .collect();
+
+        for id in prev_implied.difference(&implied) {
+            item.remove_field(id);
+        }
+        for id in &implied {
+            item.set_field_value(*id, FieldValue::Tag);
+        }
+
+        item.set_implied_ids(&implied);
+
+        Ok(())
+    }
+
     pub fn iter_linked_vault_names(&self) -> HashSet<String> {
         self.items
             .iter()
@@ -323,6 +418,113 @@ impl Vault {
             .collect()
     }
 
+    /// Rewrites references to moved items throughout the vault. Returns the number of items that
+    /// were changed.
+    pub fn rewrite_item_refs(&self, moved: &HashMap<(String, String), (String, String)>) -> usize {
+        let count = self
+            .items
+            .iter()
+            .filter(|item| item.rewrite_item_refs(moved))
+            .count();
+        if count > 0 {
+            self.set_last_updated();
+        }
+        count
+    }
+
+    /// Finds the tag at the end of a `/`-separated path of tag names, creating any tags along the
+    /// path that don't exist yet.
+    pub fn get_or_create_tag_path(&self, path: &str) -> Option<Uuid> {
+        let mut parent_id: Option<Uuid> = None;
+        for name in path.split('/').map(str::trim).filter(|n| !n.is_empty()) {
+            let existing = self
+                .definitions
+                .iter()
+                .find(|d| {
+                    *d.name == *name
+                        && match parent_id {
+                            Some(parent_id) => d.iter_parent_ids().any(|p| *p == parent_id),
+                            None => d.iter_parent_ids().next().is_none(),
+                        }
+                })
+                .map(|d| d.id);
+
+            let id = existing.unwrap_or_else(|| {
+                let mut def = FieldDefinition::new();
+                def.name = name.to_string().into();
+                if let Some(parent_id) = parent_id {
+                    def = def.with_parent(parent_id);
+                }
+                let id = def.id;
+                self.set_definition(def);
+                id
+            });
+            parent_id = Some(id);
+        }
+        parent_id
+    }
+
+    pub fn iter_subscriptions(&self) -> impl Iterator<Item = RefMulti<'_, Uuid, Subscription>> {
+        self.subscriptions.iter()
+    }
+
+    pub fn get_subscription(&self, id: &Uuid) -> Option<Subscription> {
+        self.subscriptions.get(id).map(|s| s.clone())
+    }
+
+    pub fn set_subscription(&self, subscription: Subscription) {
+        self.subscriptions.insert(subscription.id, subscription);
+        self.set_last_updated();
+    }
+
+    pub fn remove_subscription(&self, id: &Uuid) {
+        self.subscriptions.remove(id);
+        self.set_last_updated();
+    }
+
+    pub fn overdue_subscription_ids(&self, now: DateTime<Utc>) -> Vec<Uuid> {
+        self.subscriptions
+            .iter()
+            .filter(|s| s.is_overdue(now))
+            .map(|s| s.id)
+            .collect()
+    }
+
+    pub fn shortcut_profile_names(&self) -> Vec<String> {
+        self.shortcut_profiles
+            .iter()
+            .map(|p| p.key().clone())
+            .sorted()
+            .collect()
+    }
+
+    pub fn get_shortcut_profile(&self, name: &str) -> Option<ShortcutProfile> {
+        self.shortcut_profiles.get(name).map(|p| p.clone())
+    }
+
+    pub fn set_shortcut_profile(&self, profile: ShortcutProfile) {
+        self.shortcut_profiles.insert(profile.name.clone(), profile);
+        self.set_last_updated();
+    }
+
+    pub fn remove_shortcut_profile(&self, name: &str) {
+        self.shortcut_profiles.remove(name);
+        self.set_last_updated();
+    }
+
+    pub fn jobs(&self) -> &JobQueue {
+        &self.jobs
+    }
+
+    pub fn review_session(&self) -> Option<ReviewSession> {
+        self.review_session.lock().unwrap().clone()
+    }
+
+    pub fn set_review_session(&self, session: Option<ReviewSession>) {
+        *self.review_session.lock().unwrap() = session;
+        self.set_last_updated();
+    }
+
     pub fn find_items_by_tag(&self, id: &Uuid) -> Vec<RefMulti<'_, String, Arc<Item>>> {
         self.iter_items()
             .filter(|item| item.has_tag(self, id).is_ok_and(|v| v))
@@ -395,6 +597,27 @@ impl Vault {
 
         Ok(())
     }
+
+    pub fn find_implication_error(&self, def: &FieldDefinition) -> Result<(), HierarchyError> {
+        let mut seen = HashSet::new();
+        let mut queue = def.iter_implied_ids().map(|id| *id).collect_vec();
+
+        while let Some(id) = queue.pop() {
+            if id == def.id {
+                return Err(HierarchyError::ImplicationLoop { field_id: id });
+            }
+            if !seen.insert(id) {
+                continue;
+            }
+
+            let implied_def = self
+                .get_definition(&id)
+                .ok_or(HierarchyError::MissingFieldDefinition { id })?;
+            queue.extend(implied_def.iter_implied_ids().map(|iid| *iid));
+        }
+
+        Ok(())
+    }
 }
 
 impl FieldStore for Vault {
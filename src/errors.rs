@@ -5,7 +5,7 @@ use std::path::PathBuf;
 use thiserror::Error;
 use uuid::Uuid;
 
-#[derive(Error, Debug, PartialEq, Eq)]
+#[derive(Error, Debug, PartialEq, Eq, strum::IntoStaticStr)]
 pub enum AppError {
     #[error("user cancelled")]
     UserCancelled,
@@ -21,6 +21,10 @@ pub enum AppError {
     VaultNoPath,
     #[error("vault with name {name} does not exist")]
     VaultDoesNotExist { name: String },
+    #[error("vault with name {name} already exists")]
+    VaultAlreadyExists { name: String },
+    #[error("cannot merge vault {name} into itself")]
+    CannotMergeVaultIntoItself { name: String },
     #[error("wrong field type; expected {expected:?}, got {got:?}")]
     WrongFieldType {
         expected: FieldType,
@@ -54,6 +58,24 @@ pub enum AppError {
     },
     #[error("cannot remove current vault (name: {current_vault_name})")]
     CannotRemoveCurrentVault { current_vault_name: String },
+    #[error("credential store is locked")]
+    CredentialStoreLocked,
+    #[error("no location is available for the credential store")]
+    CredentialStoreUnavailable,
+    #[error("invalid passphrase for the credential store")]
+    InvalidPassphrase,
+    #[error("missing stored credential {label}")]
+    MissingCredential { label: String },
+    #[error("inbox folder {path} must be a relative path inside the vault")]
+    InvalidInboxFolder { path: String },
+    #[error("the clipboard does not contain an image or any file paths")]
+    NothingToPaste,
+    #[error("missing transform preset {name}")]
+    MissingPreset { name: String },
+    #[error(
+        "could not compress image below {max_size} bytes; the smallest output was {size} bytes"
+    )]
+    FileSizeTooLarge { max_size: u64, size: u64 },
 }
 
 impl AppError {
@@ -72,4 +94,6 @@ pub enum HierarchyError {
     MissingFieldDefinition { id: Uuid },
     #[error("found infinite loop that contains field ID {field_id}")]
     FieldTreeLoop { field_id: Uuid },
+    #[error("found implication cycle that contains field ID {field_id}")]
+    ImplicationLoop { field_id: Uuid },
 }
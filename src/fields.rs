@@ -96,7 +96,22 @@ field_defs! {
         width: Int,
         #[id("dabb8289-62e3-47b8-bfea-90891cdaf858")]
         #[tag(meta::no_link)]
-        height: Int
+        height: Int,
+        #[id("3f0c1a4e-8d52-4b7e-9a61-2c5e7f9d04b8")]
+        #[tag(meta::no_link)]
+        focal_x: Float,
+        #[id("b7e2d915-46a3-4c0f-8e1b-9d3a6f2c58e1")]
+        #[tag(meta::no_link)]
+        focal_y: Float,
+        #[id("5d8a1f63-2c7e-4b94-a0e6-7f3c9b15d248")]
+        #[tag(meta::no_link)]
+        orientation: Int,
+        #[id("8e3b5c27-1f6a-4d09-b2c4-6a7d0e9f3b15")]
+        #[tag(meta::no_link)]
+        palette: List,
+        #[id("c41f7a92-5b3e-4e68-9d0a-3f8b2c6e1d74")]
+        #[tag(meta::no_link)]
+        palette_weights: List
     },
     #[id("59589bd3-f9b9-49c1-9969-1d3714fa68db")]
     general {
@@ -120,7 +135,14 @@ field_defs! {
         derived: List,
         #[id("ba3ef373-a26a-48d6-9205-5ded815c6f73")]
         #[tag(meta::no_link)]
-        skip: Tag
+        skip: Tag,
+        #[id("db28f8fa-865b-47c8-b749-3ef914d1c788")]
+        #[tag(meta::no_link)]
+        implied: List,
+        #[id("13f9e28f-0a2b-4e4c-88ed-fb292c807476")]
+        rating: Int,
+        #[id("685c6581-0a17-4348-bf30-2dff67ded57e")]
+        favourite: Boolean
     },
     #[id("49b61dab-ce73-4ac9-ac3a-fb20f928e1e3")]
     meta {
@@ -151,5 +173,37 @@ field_defs! {
         liked_date: DateTime,
         #[id("6beb172f-4876-49e3-9f0b-4222ec4fe7c8")]
         image_number: Int
+    },
+    #[id("7cad64b3-6f67-4ae4-860c-0c4e09aee4ac")]
+    post {
+        #[id("3b3445de-6e88-4a08-abc3-b48933719a78")]
+        site: String,
+        #[id("1f0283d4-b60a-4f01-a9a6-1dcb26713e2f")]
+        id: String,
+        #[id("19e50592-4c6b-4808-9093-18dbe114a9e8")]
+        url: String,
+        #[id("1371b027-d7c1-4f47-832b-c25ca5089764")]
+        title: String,
+        #[id("467b4a14-a64d-489e-9a0d-1df1daf4d616")]
+        content: String,
+        #[id("fbcf9125-fea3-47da-8c21-6468d200cf92")]
+        tags: List,
+        #[id("5f986899-34f0-4c5b-9d10-2a3b0b0b71cb")]
+        author_id: String,
+        #[id("4b3908d3-5086-477c-b405-65ffebae6f81")]
+        author_handle: String,
+        #[id("7f507c1d-cfa1-4e8b-b527-e3dc3eb3eb12")]
+        author_name: String,
+        #[id("8b32c8e7-872f-4ce2-bde5-40239bce96aa")]
+        post_date: DateTime,
+        #[id("528c9763-bedb-434f-8957-f0be2847dfaa")]
+        image_number: Int
     }
 }
+
+/// Whether the field is one of the standard fields above rather than one created by the user.
+pub fn is_known(id: &Uuid) -> bool {
+    static IDS: OnceLock<std::collections::HashSet<Uuid>> = OnceLock::new();
+    IDS.get_or_init(|| defs().iter().map(|def| def.id).collect())
+        .contains(id)
+}
@@ -29,6 +29,7 @@ mod data;
 pub(crate) mod debug;
 mod errors;
 mod fields;
+mod server;
 mod state;
 mod tasks;
 mod ui;
@@ -0,0 +1,677 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use axum::extract::{Path as UrlPath, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use ordered_float::OrderedFloat;
+use poll_promise::Promise;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::oneshot;
+use tokio::task::{block_in_place, JoinHandle};
+use uuid::Uuid;
+
+use crate::data::parse::FilterExpressionParseResult;
+use crate::data::transform::SourceKind;
+use crate::data::{
+    FieldStore, FieldType, FieldValue, FilterExpression, Item, ItemId, SerialColour,
+    ThumbnailParams, Vault,
+};
+use crate::errors::AppError;
+use crate::fields;
+use crate::state::{AppStateRef, THUMBNAIL_LOW_QUALITY_HEIGHT};
+use crate::tasks::export::value_to_json;
+use crate::tasks::preset::preset_source_ids;
+use crate::tasks::sort::{get_filtered_and_sorted_items, SortDirection, SortExpression};
+
+const DEFAULT_PORT: u16 = 7373;
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_THUMBNAIL_HEIGHT: usize = 1024;
+/// How long open connections are given to finish when the server is stopped.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiServerSettings {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String,
+}
+
+impl Default for ApiServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+            token: generate_token(),
+        }
+    }
+}
+
+pub fn generate_token() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// A running server, which is shut down when this is dropped.
+#[derive(Debug)]
+pub struct ApiServerHandle {
+    pub addr: SocketAddr,
+    port: u16,
+    token: Arc<RwLock<String>>,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl ApiServerHandle {
+    /// The port that was requested, which is 0 if any free port could be used.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Changes the access token of the running server, without interrupting it.
+    pub fn set_token(&self, token: &str) {
+        token.clone_into(&mut self.token.write().unwrap());
+    }
+
+    /// Stops the server and waits until its port is free to be bound again.
+    pub async fn stop(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(mut task) = self.task.take() {
+            if tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut task)
+                .await
+                .is_err()
+            {
+                task.abort();
+                let _ = task.await;
+            }
+        }
+    }
+}
+
+impl Drop for ApiServerHandle {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+#[derive(Clone)]
+struct ApiState {
+    app: AppStateRef,
+    token: Arc<RwLock<String>>,
+}
+
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        let status = match e.downcast_ref::<AppError>() {
+            Some(
+                AppError::VaultDoesNotExist { .. }
+                | AppError::MissingItem { .. }
+                | AppError::MissingFieldDefinition { .. },
+            ) => StatusCode::NOT_FOUND,
+            Some(AppError::WrongFieldType { .. }) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self(status, format!("{e:#}"))
+    }
+}
+
+impl From<AppError> for ApiError {
+    fn from(e: AppError) -> Self {
+        anyhow!(e).into()
+    }
+}
+
+fn bad_request(msg: impl Into<String>) -> ApiError {
+    ApiError(StatusCode::BAD_REQUEST, msg.into())
+}
+
+/// Compares without exiting early so that the token can't be guessed from response times.
+fn token_matches(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len()
+        && given
+            .iter()
+            .zip(expected)
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+async fn authenticate(
+    State(api): State<ApiState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let given = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    let matches = token_matches(given.as_bytes(), api.token.read().unwrap().as_bytes());
+    if !matches {
+        return Err(ApiError(
+            StatusCode::UNAUTHORIZED,
+            "missing or invalid token".to_string(),
+        ));
+    }
+    Ok(next.run(request).await)
+}
+
+fn item_json(vault: &Vault, item: &Item) -> serde_json::Value {
+    let fields = item
+        .iter_fields()
+        .map(|r| {
+            let def = vault.get_definition(r.key());
+            json!({
+                "id": r.key(),
+                "name": def.as_ref().map(|d| d.name.to_string()),
+                "type": def.as_ref().map(|d| d.field_type.to_string()),
+                "value": value_to_json(r.value()),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "path": item.path(), "fields": fields })
+}
+
+fn scalar_from_json(json: &serde_json::Value) -> Option<FieldValue> {
+    Some(match json {
+        serde_json::Value::Bool(b) => FieldValue::Boolean(*b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => FieldValue::Int(i),
+            None => FieldValue::Float(OrderedFloat(n.as_f64()?)),
+        },
+        serde_json::Value::String(s) => FieldValue::string(s.clone().into()),
+        _ => return None,
+    })
+}
+
+/// Reads a value of the given type from the same JSON representation used to return values.
+fn value_from_json(field_type: FieldType, json: &serde_json::Value) -> Option<FieldValue> {
+    Some(match field_type {
+        FieldType::Tag => FieldValue::Tag,
+        FieldType::Container => FieldValue::Container,
+        FieldType::Boolean => FieldValue::Boolean(json.as_bool()?),
+        FieldType::Int => FieldValue::Int(json.as_i64()?),
+        FieldType::Float => FieldValue::Float(OrderedFloat(json.as_f64()?)),
+        FieldType::String => FieldValue::string(json.as_str()?.to_string().into()),
+        FieldType::ItemRef => {
+            let (vault_name, path) = json.as_str()?.split_once(':')?;
+            FieldValue::ItemRef((vault_name.into(), path.into()))
+        }
+        FieldType::Colour => {
+            let hex = json.as_str()?.strip_prefix('#')?;
+            let rgb = u32::from_str_radix(hex, 16)
+                .ok()
+                .filter(|_| hex.len() == 6)?;
+            let [_, r, g, b] = rgb.to_be_bytes();
+            FieldValue::Colour(SerialColour([r, g, b]))
+        }
+        FieldType::DateTime => {
+            let date = chrono::DateTime::parse_from_rfc3339(json.as_str()?).ok()?;
+            FieldValue::DateTime(date.into())
+        }
+        FieldType::List => FieldValue::List(
+            json.as_array()?
+                .iter()
+                .map(scalar_from_json)
+                .collect::<Option<_>>()?,
+        ),
+        FieldType::Dictionary => FieldValue::Dictionary(
+            json.as_object()?
+                .iter()
+                .map(|(k, v)| Some((k.clone().into(), scalar_from_json(v)?)))
+                .collect::<Option<_>>()?,
+        ),
+    })
+}
+
+async fn list_vaults(State(api): State<ApiState>) -> Json<serde_json::Value> {
+    let current = api.app.current_vault_opt().map(|v| v.name.clone());
+    let vaults = api
+        .app
+        .valid_vault_names()
+        .into_iter()
+        .filter_map(|name| api.app.get_vault(&name).ok())
+        .map(|vault| {
+            json!({
+                "name": vault.name,
+                "items": vault.len_items(),
+                "current": current.as_ref() == Some(&vault.name),
+            })
+        })
+        .collect::<Vec<_>>();
+    Json(json!(vaults))
+}
+
+#[derive(Deserialize)]
+struct ItemsQuery {
+    #[serde(default)]
+    filter: String,
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+fn parse_filter(filter: &str) -> Result<FilterExpression, ApiError> {
+    if filter.trim().is_empty() {
+        return Ok(FilterExpression::None);
+    }
+    Ok(filter
+        .parse::<FilterExpressionParseResult>()
+        .map_err(|()| bad_request("invalid filter expression"))?
+        .expr)
+}
+
+async fn list_items(
+    State(api): State<ApiState>,
+    UrlPath(vault_name): UrlPath<String>,
+    Query(query): Query<ItemsQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let vault = api.app.get_vault(&vault_name)?;
+    let filter = parse_filter(&query.filter)?;
+
+    let sorts = [SortExpression::Path(SortDirection::Ascending)];
+    let items = block_in_place(|| get_filtered_and_sorted_items(&vault, &filter, &sorts))?;
+    let paths = items
+        .iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(DEFAULT_PAGE_SIZE))
+        .map(|i| i.path().to_string())
+        .collect::<Vec<_>>();
+
+    Ok(Json(json!({ "total": items.len(), "items": paths })))
+}
+
+#[derive(Deserialize)]
+struct ItemQuery {
+    path: String,
+    height: Option<usize>,
+}
+
+async fn get_item(
+    State(api): State<ApiState>,
+    UrlPath(vault_name): UrlPath<String>,
+    Query(query): Query<ItemQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let vault = api.app.get_vault(&vault_name)?;
+    let item = vault.get_item(Path::new(&query.path))?;
+    Ok(Json(item_json(&vault, &item)))
+}
+
+async fn get_thumbnail(
+    State(api): State<ApiState>,
+    UrlPath(vault_name): UrlPath<String>,
+    Query(query): Query<ItemQuery>,
+) -> Result<Response, ApiError> {
+    let vault = api.app.get_vault(&vault_name)?;
+    let item = vault.get_item(Path::new(&query.path))?;
+    let params = ThumbnailParams {
+        rel_path: item.path().to_string(),
+        abs_path: vault.resolve_abs_path(Path::new(item.path()))?,
+        last_modified: item.get_known_field_value(fields::general::LAST_MODIFIED)?,
+        height: query
+            .height
+            .unwrap_or(THUMBNAIL_LOW_QUALITY_HEIGHT)
+            .clamp(1, MAX_THUMBNAIL_HEIGHT),
+        transform_params: None,
+    };
+
+    let hash_file = std::env::temp_dir().join(params.hash_path());
+    if !hash_file.exists() {
+        crate::tasks::thumbnail::commit_thumbnail_to_fs(&params).await?;
+    }
+    let bytes = tokio::fs::read(&hash_file).await.map_err(|e| anyhow!(e))?;
+
+    Ok(([(header::CONTENT_TYPE, "image/jpeg")], bytes).into_response())
+}
+
+#[derive(Deserialize)]
+struct SetFieldBody {
+    path: String,
+    field_id: Uuid,
+    #[serde(default)]
+    value: serde_json::Value,
+}
+
+async fn set_field(
+    State(api): State<ApiState>,
+    UrlPath(vault_name): UrlPath<String>,
+    Json(body): Json<SetFieldBody>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let vault = api.app.get_vault(&vault_name)?;
+    let item = vault.get_item(Path::new(&body.path))?;
+    let field_type = vault
+        .get_definition(&body.field_id)
+        .ok_or(AppError::MissingFieldDefinition { id: body.field_id })?
+        .field_type;
+    let value = value_from_json(field_type, &body.value)
+        .ok_or_else(|| bad_request(format!("expected a value of type {field_type}")))?;
+
+    item.set_field_value(body.field_id, value);
+    item.mark_manual(&body.field_id);
+    api.app.commit_item(Arc::clone(&vault), &item, false)?;
+    Ok(Json(item_json(&vault, &item)))
+}
+
+#[derive(Deserialize)]
+struct RemoveFieldQuery {
+    path: String,
+    field_id: Uuid,
+}
+
+async fn remove_field(
+    State(api): State<ApiState>,
+    UrlPath(vault_name): UrlPath<String>,
+    Query(query): Query<RemoveFieldQuery>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let vault = api.app.get_vault(&vault_name)?;
+    let item = vault.get_item(Path::new(&query.path))?;
+    item.remove_field(&query.field_id);
+    api.app.commit_item(Arc::clone(&vault), &item, false)?;
+    Ok(Json(item_json(&vault, &item)))
+}
+
+#[derive(Deserialize)]
+struct ImportBody {
+    path: PathBuf,
+}
+
+async fn import(
+    State(api): State<ApiState>,
+    UrlPath(vault_name): UrlPath<String>,
+    Json(body): Json<ImportBody>,
+) -> Result<(StatusCode, Json<serde_json::Value>), ApiError> {
+    let vault = api.app.get_vault(&vault_name)?;
+    if body.path.components().any(|c| c == Component::ParentDir) {
+        return Err(bad_request("path must not contain '..'"));
+    }
+    let rel_path = vault
+        .resolve_rel_path(&body.path)
+        .map_err(|_| bad_request("path must be inside the vault's root directory"))?
+        .to_string();
+    let abs_path = vault.resolve_abs_path(&body.path)?;
+    if !abs_path.is_file() {
+        return Err(bad_request(format!("{} is not a file", abs_path.display())));
+    }
+
+    api.app
+        .add_global_task(format!("Import {rel_path}"), move |_, p| {
+            Promise::spawn_async(crate::tasks::import::import_path(vault, abs_path, p))
+        });
+    Ok((StatusCode::ACCEPTED, Json(json!({ "path": rel_path }))))
+}
+
+async fn list_presets(State(api): State<ApiState>) -> Json<serde_json::Value> {
+    let presets = api
+        .app
+        .transform_presets()
+        .into_iter()
+        .map(|preset| {
+            json!({
+                "name": preset.name,
+                "source": preset.params.bulk().source.kind,
+            })
+        })
+        .collect::<Vec<_>>();
+    Json(json!(presets))
+}
+
+#[derive(Deserialize, Default)]
+struct RunPresetBody {
+    /// Runs the preset on the items matching this filter rather than its own source.
+    filter: Option<String>,
+}
+
+async fn run_preset(
+    State(api): State<ApiState>,
+    UrlPath((vault_name, preset_name)): UrlPath<(String, String)>,
+    body: Option<Json<RunPresetBody>>,
+) -> Result<(StatusCode, Json<serde_json::Value>), ApiError> {
+    let vault = api.app.get_vault(&vault_name)?;
+    let preset = api.app.get_transform_preset(&preset_name).ok_or_else(|| {
+        ApiError(
+            StatusCode::NOT_FOUND,
+            format!("no preset named {preset_name}"),
+        )
+    })?;
+    let body = body.map(|Json(body)| body).unwrap_or_default();
+
+    let item_ids = if let Some(filter) = body.filter {
+        let filter = parse_filter(&filter)?;
+        let items = block_in_place(|| get_filtered_and_sorted_items(&vault, &filter, &[]))?;
+        items
+            .iter()
+            .map(|item| ItemId::from_item(&vault, item))
+            .collect()
+    } else {
+        let is_current = api.app.current_vault_name().as_ref() == Some(&vault.name);
+        if preset.params.bulk().source.kind != SourceKind::All && !is_current {
+            return Err(bad_request(
+                "the preset runs on the selected or filtered items of the current vault, \
+                 so a filter must be given to run it on another vault",
+            ));
+        }
+        preset_source_ids(&api.app, &vault, &preset)
+    };
+
+    let count = item_ids.len();
+    crate::tasks::preset::run_preset(&api.app, vault, item_ids, preset);
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({ "preset": preset_name, "items": count })),
+    ))
+}
+
+fn router(app: AppStateRef, token: Arc<RwLock<String>>) -> Router {
+    let api = ApiState { app, token };
+    Router::new()
+        .route("/api/vaults", get(list_vaults))
+        .route("/api/vaults/:vault/items", get(list_items))
+        .route("/api/vaults/:vault/item", get(get_item))
+        .route("/api/vaults/:vault/thumbnail", get(get_thumbnail))
+        .route(
+            "/api/vaults/:vault/fields",
+            post(set_field).delete(remove_field),
+        )
+        .route("/api/vaults/:vault/import", post(import))
+        .route("/api/presets", get(list_presets))
+        .route("/api/vaults/:vault/presets/:preset/run", post(run_preset))
+        .layer(middleware::from_fn_with_state(api.clone(), authenticate))
+        .with_state(api)
+}
+
+/// Starts serving the API on localhost. Must be called from within the tokio runtime.
+pub fn start(app: AppStateRef, settings: &ApiServerSettings) -> anyhow::Result<ApiServerHandle> {
+    if settings.token.is_empty() {
+        return Err(anyhow!(
+            "an access token is required to start the API server"
+        ));
+    }
+
+    let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, settings.port))?;
+    listener.set_nonblocking(true)?;
+    let listener = tokio::net::TcpListener::from_std(listener)?;
+    let addr = listener.local_addr()?;
+
+    let token = Arc::new(RwLock::new(settings.token.clone()));
+    let router = router(app, Arc::clone(&token));
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let task = tokio::spawn(async move {
+        let res = axum::serve(listener, router)
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            })
+            .await;
+        if let Err(e) = res {
+            tracing::error!("API server stopped: {e}");
+        }
+    });
+    tracing::info!("API server listening on {addr}");
+
+    Ok(ApiServerHandle {
+        addr,
+        port: settings.port,
+        token,
+        shutdown: Some(shutdown_tx),
+        task: Some(task),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::state::AppState;
+
+    async fn request(addr: SocketAddr, request: &str) -> (u16, serde_json::Value) {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(body).unwrap_or_default())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_api() {
+        let app = AppStateRef::new(AppState::default());
+        let vault = Vault::new("test".to_string()).with_file_path(Path::new("/vaults/test.riiman"));
+        let tag_id = Uuid::new_v4();
+        vault.set_definition(crate::data::FieldDefinition::tag(tag_id, "cat".to_string()));
+        vault.get_item_or_init(Path::new("a.jpg")).unwrap();
+        vault.get_item_or_init(Path::new("b.jpg")).unwrap();
+        app.load_vault(vault, true);
+
+        let settings = ApiServerSettings {
+            enabled: true,
+            port: 0,
+            token: "secret".to_string(),
+        };
+        let server = start(app.clone(), &settings).unwrap();
+        let addr = server.addr;
+
+        let (status, _) = request(
+            addr,
+            "GET /api/vaults HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        assert_eq!(status, 401);
+
+        let auth = "Host: localhost\r\nAuthorization: Bearer secret\r\nConnection: close";
+        let (status, body) =
+            request(addr, &format!("GET /api/vaults HTTP/1.1\r\n{auth}\r\n\r\n")).await;
+        assert_eq!(status, 200);
+        assert_eq!(body[0]["name"], "test");
+        assert_eq!(body[0]["items"], 2);
+
+        let set_body = json!({ "path": "a.jpg", "field_id": tag_id }).to_string();
+        let (status, _) = request(
+            addr,
+            &format!(
+                "POST /api/vaults/test/fields HTTP/1.1\r\n{auth}\r\n\
+                 Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{set_body}",
+                set_body.len()
+            ),
+        )
+        .await;
+        assert_eq!(status, 200);
+
+        let (status, body) = request(
+            addr,
+            &format!("GET /api/vaults/test/items?filter=field:{tag_id} HTTP/1.1\r\n{auth}\r\n\r\n"),
+        )
+        .await;
+        assert_eq!(status, 200);
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["items"][0], "a.jpg");
+
+        let (status, _) = request(
+            addr,
+            &format!("GET /api/vaults/missing/items HTTP/1.1\r\n{auth}\r\n\r\n"),
+        )
+        .await;
+        assert_eq!(status, 404);
+
+        app.set_transform_preset(crate::data::TransformPreset::new(
+            "rename".to_string(),
+            crate::data::transform::PresetParams::Path {
+                bulk: Default::default(),
+                params: Default::default(),
+            },
+        ));
+        let run_body = json!({ "filter": format!("field:{tag_id}") }).to_string();
+        let (status, body) = request(
+            addr,
+            &format!(
+                "POST /api/vaults/test/presets/rename/run HTTP/1.1\r\n{auth}\r\n\
+                 Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{run_body}",
+                run_body.len()
+            ),
+        )
+        .await;
+        assert_eq!(status, 202);
+        assert_eq!(body["items"], 1);
+
+        let (status, _) = request(
+            addr,
+            &format!("POST /api/vaults/test/presets/missing/run HTTP/1.1\r\n{auth}\r\n\r\n"),
+        )
+        .await;
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_restart() {
+        let app = AppStateRef::new(AppState::default());
+        let mut settings = ApiServerSettings {
+            enabled: true,
+            port: 0,
+            token: "first".to_string(),
+        };
+        app.set_api_server_settings(settings.clone()).await.unwrap();
+        let addr = app.api_server_addr().unwrap();
+
+        // binding to the port still held by the old server must wait for it to stop
+        settings.port = addr.port();
+        settings.token = "second".to_string();
+        app.set_api_server_settings(settings.clone()).await.unwrap();
+        assert_eq!(app.api_server_addr(), Some(addr));
+
+        // the token is swapped without restarting
+        settings.token = "third".to_string();
+        app.set_api_server_settings(settings.clone()).await.unwrap();
+        let request_with = |token: &str| {
+            format!(
+                "GET /api/vaults HTTP/1.1\r\nHost: localhost\r\n\
+                 Authorization: Bearer {token}\r\nConnection: close\r\n\r\n"
+            )
+        };
+        assert_eq!(request(addr, &request_with("second")).await.0, 401);
+        assert_eq!(request(addr, &request_with("third")).await.0, 200);
+
+        settings.enabled = false;
+        app.set_api_server_settings(settings).await.unwrap();
+        assert_eq!(app.api_server_addr(), None);
+    }
+}
@@ -1,16 +1,19 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
+use std::net::SocketAddr;
 use std::ops::Deref;
 use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard};
 
 use crate::data::{
-    kind, FieldStore, FilterExpression, Item, ItemCache, ItemId, KnownField, ShortcutBehaviour,
-    ThumbnailCache, ThumbnailCacheItem, ThumbnailParams, Vault,
+    kind, CredentialStore, FieldStore, FilterExpression, Item, ItemCache, ItemId, KnownField,
+    ShortcutBehaviour, ShortcutProfile, ShortcutProfileRef, ShortcutProfileScope, TaskLog,
+    TaskLogEntry, ThumbnailCache, ThumbnailCacheItem, ThumbnailParams, TransformPreset, Vault,
 };
 use crate::errors::AppError;
 use crate::fields;
+use crate::server::{ApiServerHandle, ApiServerSettings};
 use crate::tasks::sort::{SortDirection, SortExpression};
 use crate::tasks::{AsyncTaskReturn, ProgressSenderRef, TaskFactory};
 use crate::ui::AppModal;
@@ -29,6 +32,8 @@ pub const THUMBNAIL_LOW_QUALITY_HEIGHT: usize = 128;
 pub enum TaskInfo {
     GlobalTask {
         name: String,
+        /// The vault the task runs on, used to find the items it failed on afterwards.
+        vault_name: Option<String>,
         task_factory: TaskFactory,
     },
     GlobalMessage {
@@ -50,12 +55,18 @@ pub(crate) struct AppState {
     results: DashMap<egui::Id, AsyncTaskReturn>,
     error_queue: Mutex<Vec<anyhow::Error>>,
     dialog_queue: Mutex<Vec<Box<dyn AppModal>>>,
+    search_text_request: Mutex<Option<String>>,
+    selection_request: Mutex<Option<Vec<ItemId>>>,
+    task_log: Mutex<TaskLog>,
     vaults: DashMap<String, Arc<Vault>>,
     unresolved_vaults: DashSet<String>,
     current_vault_name: Mutex<Option<String>>,
     vault_loading: AtomicBool,
 
-    shortcuts: Mutex<IndexMap<KeyboardShortcut, ShortcutBehaviour>>,
+    shortcut_profiles: Mutex<IndexMap<String, ShortcutProfile>>,
+    active_shortcut_profile: Mutex<ShortcutProfileRef>,
+
+    transform_presets: Mutex<IndexMap<String, TransformPreset>>,
 
     thumbnail_cache: ThumbnailCache,
     thumbnail_cache_lq: ThumbnailCache,
@@ -67,6 +78,11 @@ pub(crate) struct AppState {
     item_list_is_new: AtomicBool,
 
     selected_item_ids: Mutex<Vec<ItemId>>,
+
+    credentials: CredentialStore,
+
+    api_server_settings: Mutex<ApiServerSettings>,
+    api_server: Mutex<Option<ApiServerHandle>>,
 }
 
 impl Debug for AppState {
@@ -75,40 +91,30 @@ impl Debug for AppState {
     }
 }
 
-macro_rules! shortcut {
-    ($modifier:ident + $key:ident) => {
-        KeyboardShortcut::new(egui::Modifiers::$modifier, egui::Key::$key)
-    };
-    ($key:ident) => {
-        KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::$key)
-    };
-}
-
-const DEFAULT_SHORTCUTS: [KeyboardShortcut; 10] = [
-    shortcut!(CTRL + Num1),
-    shortcut!(CTRL + Num2),
-    shortcut!(CTRL + Num3),
-    shortcut!(CTRL + Num4),
-    shortcut!(CTRL + Num5),
-    shortcut!(CTRL + Num6),
-    shortcut!(CTRL + Num7),
-    shortcut!(CTRL + Num8),
-    shortcut!(CTRL + Num9),
-    shortcut!(CTRL + Num0),
-];
-
 impl Default for AppState {
     fn default() -> Self {
-        let res = Self {
+        Self {
             task_queue: Default::default(),
             results: Default::default(),
             error_queue: Default::default(),
             dialog_queue: Default::default(),
+            search_text_request: Default::default(),
+            selection_request: Default::default(),
+            task_log: Default::default(),
             vaults: Default::default(),
             unresolved_vaults: Default::default(),
             current_vault_name: Default::default(),
             vault_loading: Default::default(),
-            shortcuts: Default::default(),
+            shortcut_profiles: Mutex::new(IndexMap::from([(
+                ShortcutProfile::DEFAULT_NAME.to_string(),
+                ShortcutProfile::new(ShortcutProfile::DEFAULT_NAME.to_string())
+                    .with_default_shortcuts(),
+            )])),
+            active_shortcut_profile: Mutex::new(ShortcutProfileRef {
+                name: ShortcutProfile::DEFAULT_NAME.to_string(),
+                scope: ShortcutProfileScope::App,
+            }),
+            transform_presets: Default::default(),
             thumbnail_cache: ThumbnailCache::new(
                 THUMBNAIL_CACHE_SIZE,
                 TimeDelta::milliseconds(THUMBNAIL_LOAD_INTERVAL_MS),
@@ -124,16 +130,12 @@ impl Default for AppState {
             filtered_item_list: Default::default(),
             item_list_is_new: Default::default(),
             selected_item_ids: Default::default(),
-        };
-
-        {
-            let mut shortcuts = res.shortcuts.lock().unwrap();
-            for shortcut in DEFAULT_SHORTCUTS {
-                shortcuts.insert(shortcut, Default::default());
-            }
+            credentials: CredentialStore::new(
+                eframe::storage_dir("riiman").map(|dir| dir.join("credentials.json")),
+            ),
+            api_server_settings: Default::default(),
+            api_server: Default::default(),
         }
-
-        res
     }
 }
 
@@ -308,15 +310,27 @@ impl AppState {
         item: &Item,
         skip_save: bool,
     ) -> anyhow::Result<()> {
-        let link_res = self.update_item_links(&vault, item)?;
+        self.commit_items(vault, [item], skip_save)
+    }
+
+    /// Commits several items at once, saving the vault and any vaults they link to only once.
+    pub fn commit_items<'i>(
+        &self,
+        vault: Arc<Vault>,
+        items: impl IntoIterator<Item = &'i Item>,
+        skip_save: bool,
+    ) -> anyhow::Result<()> {
+        let mut other_vault_names = HashSet::new();
+        for item in items {
+            vault.apply_implications(item)?;
+            vault.tag_stats().update_item(&vault, item);
+            let link_res = self.update_item_links(&vault, item)?;
+            other_vault_names.extend(link_res.into_iter().map(|kind::ItemRef((n, _))| n));
+        }
         if skip_save {
             return Ok(());
         }
 
-        let other_vault_names: HashSet<_> = link_res
-            .into_iter()
-            .map(|kind::ItemRef((n, _))| n)
-            .collect();
         for other_vault_name in other_vault_names {
             self.save_vault_by_name_deferred(other_vault_name.as_str());
         }
@@ -389,10 +403,25 @@ impl AppState {
             + Send
             + Sync
             + 'static,
+    ) {
+        self.add_vault_task(self.current_vault_name(), name, task_factory);
+    }
+
+    /// Like [`Self::add_global_task`], but for a task that runs on `vault_name` rather than
+    /// the current vault.
+    pub fn add_vault_task(
+        &self,
+        vault_name: Option<String>,
+        name: impl Into<String>,
+        task_factory: impl FnOnce(AppStateRef, ProgressSenderRef) -> Promise<AsyncTaskReturn>
+            + Send
+            + Sync
+            + 'static,
     ) {
         let mut l = self.task_queue.lock().unwrap();
         l.push(TaskInfo::GlobalTask {
             name: name.into(),
+            vault_name,
             task_factory: Box::new(task_factory),
         });
     }
@@ -457,6 +486,34 @@ impl AppState {
         self.dialog_queue.lock().unwrap().drain(..).collect()
     }
 
+    pub fn request_search_text(&self, search_text: String) {
+        *self.search_text_request.lock().unwrap() = Some(search_text);
+    }
+
+    pub fn take_search_text_request(&self) -> Option<String> {
+        self.search_text_request.lock().unwrap().take()
+    }
+
+    pub fn request_selection(&self, item_ids: Vec<ItemId>) {
+        *self.selection_request.lock().unwrap() = Some(item_ids);
+    }
+
+    pub fn take_selection_request(&self) -> Option<Vec<ItemId>> {
+        self.selection_request.lock().unwrap().take()
+    }
+
+    pub fn task_log(&self) -> MutexGuard<'_, TaskLog> {
+        self.task_log.lock().unwrap()
+    }
+
+    pub fn set_task_log(&self, log: TaskLog) {
+        *self.task_log() = log;
+    }
+
+    pub fn record_task(&self, entry: TaskLogEntry) {
+        self.task_log().push(entry);
+    }
+
     pub fn try_take_request_result(&self, id: egui::Id) -> Option<AsyncTaskReturn> {
         self.results.remove(&id).map(|(_, v)| v)
     }
@@ -559,26 +616,195 @@ impl AppState {
             .collect()
     }
 
-    pub fn shortcuts(&self) -> Vec<(KeyboardShortcut, ShortcutBehaviour)> {
-        self.shortcuts
+    pub fn credentials(&self) -> &CredentialStore {
+        &self.credentials
+    }
+
+    pub fn api_server_settings(&self) -> ApiServerSettings {
+        self.api_server_settings.lock().unwrap().clone()
+    }
+
+    pub fn api_server_addr(&self) -> Option<SocketAddr> {
+        self.api_server.lock().unwrap().as_ref().map(|h| h.addr)
+    }
+
+    pub fn shortcut_profiles(&self) -> Vec<ShortcutProfileRef> {
+        let mut profiles = self
+            .shortcut_profiles
             .lock()
             .unwrap()
-            .iter()
-            .map(|(k, v)| (*k, v.clone()))
+            .keys()
+            .map(|name| ShortcutProfileRef {
+                name: name.clone(),
+                scope: ShortcutProfileScope::App,
+            })
+            .collect::<Vec<_>>();
+        if let Some(vault) = self.current_vault_opt() {
+            profiles.extend(vault.shortcut_profile_names().into_iter().map(|name| {
+                ShortcutProfileRef {
+                    name,
+                    scope: ShortcutProfileScope::Vault,
+                }
+            }));
+        }
+        profiles
+    }
+
+    pub fn get_shortcut_profile(
+        &self,
+        profile_ref: &ShortcutProfileRef,
+    ) -> Option<ShortcutProfile> {
+        match profile_ref.scope {
+            ShortcutProfileScope::App => self
+                .shortcut_profiles
+                .lock()
+                .unwrap()
+                .get(&profile_ref.name)
+                .cloned(),
+            ShortcutProfileScope::Vault => self
+                .current_vault_opt()?
+                .get_shortcut_profile(&profile_ref.name),
+        }
+    }
+
+    pub fn set_shortcut_profile(&self, scope: ShortcutProfileScope, profile: ShortcutProfile) {
+        match scope {
+            ShortcutProfileScope::App => {
+                self.shortcut_profiles
+                    .lock()
+                    .unwrap()
+                    .insert(profile.name.clone(), profile);
+            }
+            ShortcutProfileScope::Vault => {
+                if let Some(vault) = self.current_vault_opt() {
+                    vault.set_shortcut_profile(profile);
+                    self.save_vault_deferred(vault);
+                }
+            }
+        }
+    }
+
+    /// Removes a profile, unless it is the last profile stored in the app.
+    pub fn remove_shortcut_profile(&self, profile_ref: &ShortcutProfileRef) {
+        match profile_ref.scope {
+            ShortcutProfileScope::App => {
+                let mut profiles = self.shortcut_profiles.lock().unwrap();
+                if profiles.len() > 1 {
+                    profiles.shift_remove(&profile_ref.name);
+                }
+            }
+            ShortcutProfileScope::Vault => {
+                if let Some(vault) = self.current_vault_opt() {
+                    vault.remove_shortcut_profile(&profile_ref.name);
+                    self.save_vault_deferred(vault);
+                }
+            }
+        }
+    }
+
+    /// The active profile, falling back to the first of the app's profiles if the active profile
+    /// belongs to a vault that isn't current.
+    pub fn active_shortcut_profile(&self) -> ShortcutProfileRef {
+        let active = self.active_shortcut_profile.lock().unwrap().clone();
+        if self.get_shortcut_profile(&active).is_some() {
+            return active;
+        }
+        ShortcutProfileRef {
+            name: self
+                .shortcut_profiles
+                .lock()
+                .unwrap()
+                .keys()
+                .next()
+                .cloned()
+                .unwrap_or_default(),
+            scope: ShortcutProfileScope::App,
+        }
+    }
+
+    pub fn set_active_shortcut_profile(&self, profile_ref: ShortcutProfileRef) {
+        *self.active_shortcut_profile.lock().unwrap() = profile_ref;
+    }
+
+    pub fn app_shortcut_profiles(&self) -> Vec<ShortcutProfile> {
+        self.shortcut_profiles
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub fn set_app_shortcut_profiles(&self, profiles: Vec<ShortcutProfile>) {
+        if profiles.is_empty() {
+            return;
+        }
+        *self.shortcut_profiles.lock().unwrap() =
+            profiles.into_iter().map(|p| (p.name.clone(), p)).collect();
+    }
+
+    pub fn transform_presets(&self) -> Vec<TransformPreset> {
+        self.transform_presets
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
             .collect()
     }
 
+    pub fn get_transform_preset(&self, name: &str) -> Option<TransformPreset> {
+        self.transform_presets.lock().unwrap().get(name).cloned()
+    }
+
+    pub fn set_transform_preset(&self, preset: TransformPreset) {
+        self.transform_presets
+            .lock()
+            .unwrap()
+            .insert(preset.name.clone(), preset);
+    }
+
+    pub fn remove_transform_preset(&self, name: &str) {
+        self.transform_presets.lock().unwrap().shift_remove(name);
+    }
+
+    pub fn set_transform_presets(&self, presets: Vec<TransformPreset>) {
+        *self.transform_presets.lock().unwrap() = presets
+            .into_iter()
+            .map(|p| (p.name.clone(), p.upgrade()))
+            .collect();
+    }
+
+    fn update_active_shortcut_profile(&self, f: impl FnOnce(&mut ShortcutProfile)) {
+        let active = self.active_shortcut_profile();
+        if let Some(mut profile) = self.get_shortcut_profile(&active) {
+            f(&mut profile);
+            self.set_shortcut_profile(active.scope, profile);
+        }
+    }
+
+    pub fn shortcuts(&self) -> Vec<(KeyboardShortcut, ShortcutBehaviour)> {
+        self.get_shortcut_profile(&self.active_shortcut_profile())
+            .map(|p| p.shortcuts)
+            .unwrap_or_default()
+    }
+
     #[tracing::instrument]
     pub fn set_shortcut(&self, shortcut: KeyboardShortcut, behaviour: ShortcutBehaviour) {
-        self.shortcuts.lock().unwrap().insert(shortcut, behaviour);
+        self.update_active_shortcut_profile(|p| p.set(shortcut, behaviour));
+    }
+
+    #[tracing::instrument]
+    pub fn remove_shortcut(&self, shortcut: KeyboardShortcut) {
+        self.update_active_shortcut_profile(|p| p.remove(shortcut));
     }
 
     #[tracing::instrument]
     pub fn set_shortcuts(&self, shortcuts: Vec<(KeyboardShortcut, ShortcutBehaviour)>) {
-        let mut l = self.shortcuts.lock().unwrap();
-        for (shortcut, behaviour) in shortcuts {
-            l.insert(shortcut, behaviour);
-        }
+        self.update_active_shortcut_profile(|p| {
+            for (shortcut, behaviour) in shortcuts {
+                p.set(shortcut, behaviour);
+            }
+        });
     }
 
     pub fn commit_thumbnail(&self, params: ThumbnailParams, item: ThumbnailCacheItem) {
@@ -652,6 +878,42 @@ impl AppStateRef {
             || self.commit_item(vault, item, skip_save),
         )
     }
+
+    pub fn commit_items_catch<'i>(
+        &self,
+        vault: &Arc<Vault>,
+        items: impl IntoIterator<Item = &'i Item>,
+    ) -> Result<(), ()> {
+        self.catch(
+            || format!("updating items in {}", vault.name),
+            || self.commit_items(Arc::clone(vault), items, false),
+        )
+    }
+
+    /// Stores `settings` and restarts the API server, leaving it stopped if it isn't enabled.
+    pub async fn set_api_server_settings(&self, settings: ApiServerSettings) -> anyhow::Result<()> {
+        *self.api_server_settings.lock().unwrap() = settings.clone();
+        let old_handle = {
+            let mut handle = self.api_server.lock().unwrap();
+            if let Some(running) = handle.as_ref() {
+                // a new token doesn't need the server to be restarted
+                if settings.enabled && running.port() == settings.port {
+                    running.set_token(&settings.token);
+                    return Ok(());
+                }
+            }
+            handle.take()
+        };
+        // the old server must release its port before a new one can bind to it
+        if let Some(old_handle) = old_handle {
+            old_handle.stop().await;
+        }
+        if settings.enabled {
+            let handle = crate::server::start(self.clone(), &settings)?;
+            *self.api_server.lock().unwrap() = Some(handle);
+        }
+        Ok(())
+    }
 }
 
 impl Deref for AppStateRef {
@@ -1,34 +1,48 @@
 use std::collections::HashSet;
 use std::path::Path;
 
+use chrono::{DateTime, Utc};
 use eframe::egui;
 use eframe::egui::ColorImage;
 use itertools::Itertools;
 use poll_promise::Promise;
 
+pub use progress::CancellationToken;
 use progress::ProgressReceiver;
 use progress::ProgressSenderAsync;
 pub use progress::ProgressSenderRef;
 
-use crate::data::{DebugViewportClass, ThumbnailParams};
+use crate::data::{DebugViewportClass, FieldDefinition, ThumbnailParams};
 use crate::state::AppStateRef;
+use crate::tasks::stats::VaultStats;
 pub use crate::tasks::thumb_grid::RiverParams;
 pub use crate::tasks::thumb_grid::ThumbnailGridInfo;
 use crate::tasks::transform::TransformResult;
 use crate::ui::QueryResult;
 
 pub(crate) mod choose;
+pub(crate) mod definitions;
 pub(crate) mod download;
+pub(crate) mod export;
 pub(crate) mod filter;
 mod image;
+pub(crate) mod implication;
 pub(crate) mod import;
+pub(crate) mod job;
 pub(crate) mod link;
+pub(crate) mod merge;
+pub(crate) mod palette;
+pub(crate) mod preset;
 mod progress;
+pub(crate) mod sidecar;
 pub(crate) mod sort;
+pub(crate) mod stats;
+pub(crate) mod task_log;
 pub(crate) mod thumb_grid;
 pub(crate) mod thumbnail;
 pub(crate) mod transform;
 pub(crate) mod vault;
+pub(crate) mod xmp;
 
 #[derive(Debug)]
 pub enum AsyncTaskResult {
@@ -42,6 +56,10 @@ pub enum AsyncTaskResult {
         path: Box<Path>,
         results: Vec<SingleImportResult>,
     },
+    InboxImportComplete {
+        path: Box<Path>,
+        results: Vec<SingleImportResult>,
+    },
     LinkComplete {
         other_vault_name: String,
         results: Vec<SingleImportResult>,
@@ -61,9 +79,23 @@ pub enum AsyncTaskResult {
     },
     SelectedDirectory(String),
     SelectedFile(String),
+    DefinitionsLoaded {
+        path: Box<Path>,
+        definitions: Vec<FieldDefinition>,
+    },
     QueryResult(QueryResult),
-    TransformationComplete(Vec<anyhow::Result<TransformResult>>),
+    TransformationComplete {
+        results: Vec<anyhow::Result<TransformResult>>,
+        /// The number of items left unprocessed because the task was cancelled.
+        cancelled: usize,
+    },
+    VaultStats(Box<VaultStats>),
     NextItem,
+    /// The per-item results of a task whose only outcome is a summary, e.g. writing sidecars.
+    BatchComplete {
+        title: String,
+        results: Vec<SingleImportResult>,
+    },
 }
 
 pub type SingleImportResult = anyhow::Result<Box<Path>>;
@@ -86,22 +118,34 @@ pub type TaskFactory = Box<
 struct Task {
     id: Option<egui::Id>,
     name: String,
+    vault_name: Option<String>,
+    started: DateTime<Utc>,
     promise: Promise<AsyncTaskReturn>,
     progress_rx: Option<ProgressReceiver>,
+    cancellation: CancellationToken,
 }
 
 impl Task {
     pub fn with_progress(
         id: Option<egui::Id>,
         name: String,
+        vault_name: Option<String>,
         factory: impl FnOnce(ProgressSenderRef) -> Promise<AsyncTaskReturn>,
     ) -> Task {
         let (tx, rx) = tokio::sync::watch::channel(ProgressState::NotStarted);
+        let cancellation = CancellationToken::default();
         Task {
             id,
-            promise: factory(ProgressSenderAsync::new(name.clone(), tx)),
+            promise: factory(ProgressSenderAsync::new(
+                name.clone(),
+                tx,
+                cancellation.clone(),
+            )),
             name,
+            vault_name,
+            started: Utc::now(),
             progress_rx: Some(rx),
+            cancellation,
         }
     }
 
@@ -110,18 +154,33 @@ impl Task {
             id,
             promise: Promise::from_ready(value),
             name: String::new(),
+            vault_name: None,
+            started: Utc::now(),
             progress_rx: None,
+            cancellation: CancellationToken::default(),
         }
     }
 
-    pub fn try_take_result(self) -> Result<AsyncTaskReturn, Task> {
+    pub fn try_take_result(self) -> Result<FinishedTask, Task> {
         match self.promise.try_take() {
-            Ok(result) => Ok(result),
+            Ok(result) => Ok(FinishedTask {
+                name: self.name,
+                vault_name: self.vault_name,
+                started: self.started,
+                result,
+            }),
             Err(promise) => Err(Self { promise, ..self }),
         }
     }
 }
 
+pub struct FinishedTask {
+    pub name: String,
+    pub vault_name: Option<String>,
+    pub started: DateTime<Utc>,
+    pub result: AsyncTaskReturn,
+}
+
 #[derive(Default)]
 pub(crate) struct TaskState {
     running_tasks: Vec<Task>,
@@ -132,10 +191,11 @@ impl TaskState {
     pub fn add(
         &mut self,
         name: String,
+        vault_name: Option<String>,
         factory: impl FnOnce(ProgressSenderRef) -> Promise<AsyncTaskReturn>,
     ) {
         self.running_tasks
-            .push(Task::with_progress(None, name, factory));
+            .push(Task::with_progress(None, name, vault_name, factory));
     }
 
     pub fn push_message(&mut self, result: AsyncTaskReturn) {
@@ -166,7 +226,7 @@ impl TaskState {
         self.clear_tasks_by_id(id);
         self.requests.insert(id);
         self.running_tasks
-            .push(Task::with_progress(Some(id), name, factory));
+            .push(Task::with_progress(Some(id), name, None, factory));
     }
 
     pub fn push_completed_task(&mut self, id: egui::Id, value: AsyncTaskReturn) {
@@ -179,15 +239,15 @@ impl TaskState {
         self.running_tasks.len()
     }
 
-    pub fn iter_ready(&mut self) -> (Vec<AsyncTaskReturn>, Vec<(egui::Id, AsyncTaskReturn)>) {
+    pub fn iter_ready(&mut self) -> (Vec<FinishedTask>, Vec<(egui::Id, AsyncTaskReturn)>) {
         let mut results = vec![];
         let mut request_results = vec![];
         let mut still_running_tasks = vec![];
         for task in self.running_tasks.drain(..) {
             match (task.id, task.try_take_result()) {
-                (Some(id), Ok(result)) => {
+                (Some(id), Ok(finished)) => {
                     if self.requests.remove(&id) {
-                        request_results.push((id, result));
+                        request_results.push((id, finished.result));
                     }
                 }
                 (None, Ok(result)) => results.push(result),
@@ -199,12 +259,16 @@ impl TaskState {
         (results, request_results)
     }
 
-    pub fn iter_progress(&self) -> Vec<(String, ProgressState)> {
+    pub fn iter_progress(&self) -> Vec<(String, ProgressState, CancellationToken)> {
         let mut progresses = vec![];
         for task in &self.running_tasks {
             if task.progress_rx.is_some() {
                 let rx = task.progress_rx.as_ref().unwrap();
-                progresses.push((task.name.clone(), rx.borrow().clone()));
+                progresses.push((
+                    task.name.clone(),
+                    rx.borrow().clone(),
+                    task.cancellation.clone(),
+                ));
             }
         }
         progresses
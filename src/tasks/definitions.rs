@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use tokio::task::block_in_place;
+
+use crate::data::{FieldDefinition, Vault};
+use crate::errors::AppError;
+use crate::tasks::{AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, ProgressState};
+
+const DEFINITIONS_FILTER_NAME: &str = "riiman definitions file";
+const DEFINITIONS_EXTENSION: &str = "riimandefs";
+
+#[tracing::instrument]
+pub async fn choose_and_load_definitions(progress: ProgressSenderRef) -> AsyncTaskReturn {
+    let dialog =
+        rfd::AsyncFileDialog::new().add_filter(DEFINITIONS_FILTER_NAME, &[DEFINITIONS_EXTENSION]);
+
+    let fp = dialog.pick_file().await.ok_or(AppError::UserCancelled)?;
+    let path = fp.path().to_path_buf();
+
+    progress.send(ProgressState::Determinate(0.5));
+
+    let contents = tokio::fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("while reading from definitions file at {}", path.display()))?;
+    let definitions = serde_json::from_str::<Vec<FieldDefinition>>(&contents)
+        .with_context(|| format!("while deserialising definitions file at {}", path.display()))?;
+
+    Ok(AsyncTaskResult::DefinitionsLoaded {
+        path: path.into_boxed_path(),
+        definitions,
+    })
+}
+
+#[tracing::instrument]
+pub async fn export_definitions(vault: Arc<Vault>, progress: ProgressSenderRef) -> AsyncTaskReturn {
+    let dialog = rfd::AsyncFileDialog::new()
+        .add_filter(DEFINITIONS_FILTER_NAME, &[DEFINITIONS_EXTENSION])
+        .set_file_name(format!("{}.{DEFINITIONS_EXTENSION}", vault.name));
+
+    let fp = dialog.save_file().await.ok_or(AppError::UserCancelled)?;
+    let path = fp.path().to_path_buf();
+
+    let data = block_in_place(move || {
+        let definitions: Vec<_> = vault.iter_field_defs().map(|def| def.clone()).collect();
+        serde_json::to_vec(&definitions)
+    })?;
+
+    progress.send(ProgressState::Determinate(0.5));
+
+    tokio::fs::write(&path, data)
+        .await
+        .with_context(|| format!("while writing to definitions file at {}", path.display()))?;
+
+    Ok(AsyncTaskResult::None)
+}
@@ -1,19 +1,34 @@
 use std::env::consts::EXE_EXTENSION;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
 use std::process::{ExitStatus, Stdio};
 use std::sync::Arc;
 
 use anyhow::anyhow;
+use chrono::{Local, Utc};
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use strum::{Display, EnumDiscriminants};
 use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::{ChildStdout, Command};
 use tokio::sync::Mutex;
 use tokio::task::JoinSet;
+use url::Url;
 use uuid::Uuid;
 
+use crate::data::{
+    Credential, CredentialRef, CredentialStore, FieldStore, FieldValue, SubscriptionRun, Vault,
+};
 use crate::errors::AppError;
 use crate::state::AppStateRef;
-use crate::tasks::{AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, ProgressState};
+use crate::tasks::import::{import_single_image, on_import_result_send_progress, process_many};
+use crate::tasks::link::{find_sidecar, link_single_sidecar};
+use crate::tasks::{
+    AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, ProgressState, SingleImportResult,
+};
+
+const CONCURRENT_TASKS_LIMIT: usize = 16;
 
 #[derive(
     Debug, Default, Clone, PartialEq, Eq, Display, EnumDiscriminants, Serialize, Deserialize,
@@ -24,6 +39,14 @@ pub enum GalleryDLSource {
     None,
     #[strum_discriminants(strum(to_string = "Twitter Likes"))]
     TwitterLikes { username: String },
+    #[strum_discriminants(strum(to_string = "Pixiv Bookmarks"))]
+    PixivBookmarks { user_id: String },
+    #[strum_discriminants(strum(to_string = "Danbooru Search"))]
+    DanbooruSearch { tags: String },
+    #[strum_discriminants(strum(to_string = "Reddit Saved Posts"))]
+    RedditSaved { username: String },
+    #[strum_discriminants(strum(to_string = "Bluesky Likes"))]
+    BlueskyLikes { handle: String },
     #[strum_discriminants(strum(to_string = "Custom URL"))]
     CustomURL { url: String },
 }
@@ -32,6 +55,7 @@ pub enum GalleryDLSource {
     Debug, Default, Clone, PartialEq, Eq, Display, EnumDiscriminants, Serialize, Deserialize,
 )]
 #[strum_discriminants(derive(Display))]
+#[serde(from = "GalleryDLLoginRepr")]
 pub enum GalleryDLLogin {
     #[default]
     None,
@@ -40,10 +64,85 @@ pub enum GalleryDLLogin {
     #[strum_discriminants(strum(to_string = "Chrome Cookies"))]
     ChromeCookies,
     #[strum_discriminants(strum(to_string = "Username/Password"))]
-    UsernamePassword { username: String, password: String },
+    UsernamePassword {
+        credential: Option<CredentialRef>,
+        /// A username and password persisted in plain text by older versions. It is only ever
+        /// read so that it can be moved into the credential store, and is never written back.
+        #[serde(default, skip_serializing)]
+        legacy: Option<Credential>,
+    },
+}
+
+/// Accepts both the current login format and the plain text `username`/`password` fields
+/// that older vaults stored directly in the subscription.
+#[derive(Deserialize)]
+enum GalleryDLLoginRepr {
+    None,
+    FirefoxCookies,
+    ChromeCookies,
+    UsernamePassword {
+        #[serde(default)]
+        credential: Option<CredentialRef>,
+        #[serde(default)]
+        legacy: Option<Credential>,
+        #[serde(default)]
+        username: Option<String>,
+        #[serde(default)]
+        password: Option<String>,
+    },
+}
+
+impl From<GalleryDLLoginRepr> for GalleryDLLogin {
+    fn from(value: GalleryDLLoginRepr) -> Self {
+        match value {
+            GalleryDLLoginRepr::None => Self::None,
+            GalleryDLLoginRepr::FirefoxCookies => Self::FirefoxCookies,
+            GalleryDLLoginRepr::ChromeCookies => Self::ChromeCookies,
+            GalleryDLLoginRepr::UsernamePassword {
+                credential,
+                legacy,
+                username,
+                password,
+            } => {
+                let legacy = legacy.or_else(|| {
+                    username
+                        .zip(password)
+                        .map(|(username, password)| Credential { username, password })
+                });
+                Self::UsernamePassword { credential, legacy }
+            }
+        }
+    }
+}
+
+impl GalleryDLLogin {
+    /// Moves a plain text login left by an older version into `store`, labelling it with
+    /// `context`. Returns whether there was a login to move.
+    pub fn migrate_legacy(
+        &mut self,
+        store: &CredentialStore,
+        context: &str,
+    ) -> anyhow::Result<bool> {
+        let Self::UsernamePassword {
+            credential: credential @ None,
+            legacy,
+        } = self
+        else {
+            return Ok(false);
+        };
+        let Some(legacy_credential) = legacy.as_ref() else {
+            return Ok(false);
+        };
+
+        let label = format!("{} ({context})", legacy_credential.username);
+        *credential = Some(store.save(label, legacy_credential)?);
+        *legacy = None;
+        Ok(true)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GalleryDLParams {
     pub location: Option<String>,
     pub version: Option<String>,
@@ -52,6 +151,95 @@ pub struct GalleryDLParams {
     pub cli_arguments: String,
     pub json_config: String,
     pub log_file: Option<String>,
+    pub import_downloads: bool,
+    pub tag_downloads: bool,
+}
+
+impl GalleryDLSource {
+    pub fn from_discriminant(d: GalleryDLSourceDiscriminants) -> Self {
+        match d {
+            GalleryDLSourceDiscriminants::None => Self::None,
+            GalleryDLSourceDiscriminants::TwitterLikes => Self::TwitterLikes {
+                username: String::new(),
+            },
+            GalleryDLSourceDiscriminants::PixivBookmarks => Self::PixivBookmarks {
+                user_id: String::new(),
+            },
+            GalleryDLSourceDiscriminants::DanbooruSearch => Self::DanbooruSearch {
+                tags: String::new(),
+            },
+            GalleryDLSourceDiscriminants::RedditSaved => Self::RedditSaved {
+                username: String::new(),
+            },
+            GalleryDLSourceDiscriminants::BlueskyLikes => Self::BlueskyLikes {
+                handle: String::new(),
+            },
+            GalleryDLSourceDiscriminants::CustomURL => Self::CustomURL { url: String::new() },
+        }
+    }
+
+    /// The name of the gallery-dl extractor that handles this source.
+    pub fn category(&self) -> Option<&'static str> {
+        match self {
+            Self::None | Self::CustomURL { .. } => None,
+            Self::TwitterLikes { .. } => Some("twitter"),
+            Self::PixivBookmarks { .. } => Some("pixiv"),
+            Self::DanbooruSearch { .. } => Some("danbooru"),
+            Self::RedditSaved { .. } => Some("reddit"),
+            Self::BlueskyLikes { .. } => Some("bluesky"),
+        }
+    }
+
+    pub fn filename_template(&self) -> Option<&'static str> {
+        match self {
+            Self::None | Self::CustomURL { .. } => None,
+            Self::TwitterLikes { .. } => Some("twitter_{author[id]}_{tweet_id}_{num}.{extension}"),
+            Self::PixivBookmarks { .. } => Some("pixiv_{user[id]}_{id}_{num}.{extension}"),
+            Self::DanbooruSearch { .. } => Some("danbooru_{id}.{extension}"),
+            Self::RedditSaved { .. } => Some("reddit_{id}_{num}.{extension}"),
+            Self::BlueskyLikes { .. } => {
+                Some("bluesky_{author[handle]}_{post_id}_{num}.{extension}")
+            }
+        }
+    }
+
+    /// Returns the default gallery-dl configuration for this source, which sets its filename
+    /// template along with any options needed to get useful metadata.
+    pub fn default_config(&self) -> String {
+        let (Some(category), Some(filename)) = (self.category(), self.filename_template()) else {
+            return "{}".to_string();
+        };
+        let mut extractor = json!({ "filename": filename });
+        let options = match self {
+            Self::TwitterLikes { .. } => json!({
+                "users": "https://x.com/{legacy[screen_name]}",
+                "text-tweets": true,
+                "quoted": true,
+                "retweets": true,
+                "logout": true,
+                "replies": "self",
+                "parent-directory": true,
+                "postprocessors": [
+                    {
+                        "name": "metadata",
+                        "event": "post",
+                        "filename": "twitter_{author[id]}_{tweet_id}_main.json"
+                    }
+                ]
+            }),
+            Self::PixivBookmarks { .. } => json!({ "tags": "translated" }),
+            Self::DanbooruSearch { .. } => json!({ "metadata": true }),
+            Self::RedditSaved { .. } => json!({ "comments": 0, "recursion": 0 }),
+            Self::BlueskyLikes { .. } => json!({ "metadata": ["facets"] }),
+            Self::None | Self::CustomURL { .. } => json!({}),
+        };
+        if let (Some(extractor), Some(options)) = (extractor.as_object_mut(), options.as_object()) {
+            extractor.extend(options.clone());
+        }
+
+        serde_json::to_string_pretty(&json!({ "extractor": { category: extractor } }))
+            .unwrap_or_default()
+    }
 }
 
 impl Default for GalleryDLParams {
@@ -62,30 +250,13 @@ impl Default for GalleryDLParams {
             source: GalleryDLSource::None,
             login: GalleryDLLogin::None,
             cli_arguments: "--write-metadata -o skip=true".to_string(),
-            //language=json
-            json_config: r#"{
-    "extractor": {
-        "twitter": {
-            "users": "https://x.com/{legacy[screen_name]}",
-            "text-tweets": true,
-            "quoted": true,
-            "retweets": true,
-            "logout": true,
-            "replies": "self",
-            "filename": "twitter_{author[id]}_{tweet_id}_{num}.{extension}",
-            "parent-directory": true,
-            "postprocessors": [
-                {
-                    "name": "metadata",
-                    "event": "post",
-                    "filename": "twitter_{author[id]}_{tweet_id}_main.json"
-                }
-            ]
-        }
-    }
-}"#
-            .to_string(),
+            json_config: GalleryDLSource::TwitterLikes {
+                username: String::new(),
+            }
+            .default_config(),
             log_file: None,
+            import_downloads: true,
+            tag_downloads: false,
         }
     }
 }
@@ -97,18 +268,53 @@ impl GalleryDLParams {
             GalleryDLSource::TwitterLikes { username } => {
                 format!("Downloading Twitter likes of @{username} using gallery-dl")
             }
+            GalleryDLSource::PixivBookmarks { user_id } => {
+                format!("Downloading Pixiv bookmarks of user {user_id} using gallery-dl")
+            }
+            GalleryDLSource::DanbooruSearch { tags } => {
+                format!("Downloading Danbooru posts tagged {tags} using gallery-dl")
+            }
+            GalleryDLSource::RedditSaved { username } => {
+                format!("Downloading Reddit saved posts of u/{username} using gallery-dl")
+            }
+            GalleryDLSource::BlueskyLikes { handle } => {
+                format!("Downloading Bluesky likes of @{handle} using gallery-dl")
+            }
             GalleryDLSource::CustomURL { url } => {
                 format!("Downloading from {url} using gallery-dl")
             }
         }
     }
 
+    /// The tag applied to newly downloaded items when `tag_downloads` is set.
+    pub fn download_tag(&self) -> String {
+        format!(
+            "downloaded/{}/{}",
+            self.source.category().unwrap_or("custom"),
+            Local::now().format("%Y-%m-%d")
+        )
+    }
+
     pub fn source_url(&self) -> String {
         match &self.source {
             GalleryDLSource::None => String::new(),
             GalleryDLSource::TwitterLikes { username } => {
                 format!("https://twitter.com/{username}/likes")
             }
+            GalleryDLSource::PixivBookmarks { user_id } => {
+                format!("https://www.pixiv.net/en/users/{user_id}/bookmarks/artworks")
+            }
+            GalleryDLSource::DanbooruSearch { tags } => {
+                Url::parse_with_params("https://danbooru.donmai.us/posts", &[("tags", tags)])
+                    .map(|url| url.to_string())
+                    .unwrap_or_default()
+            }
+            GalleryDLSource::RedditSaved { username } => {
+                format!("https://www.reddit.com/user/{username}/saved")
+            }
+            GalleryDLSource::BlueskyLikes { handle } => {
+                format!("https://bsky.app/profile/{handle}/likes")
+            }
             GalleryDLSource::CustomURL { url } => url.to_string(),
         }
     }
@@ -213,12 +419,34 @@ async fn async_tee(
     Ok(())
 }
 
-#[allow(clippy::module_name_repetitions)]
-pub async fn perform_gallery_dl_download(
-    state: AppStateRef,
-    progress: ProgressSenderRef,
+/// Sets the login for every extractor in a gallery-dl config, which must be a JSON object.
+fn set_config_credential(
+    config: &mut serde_json::Value,
+    credential: Credential,
+) -> anyhow::Result<()> {
+    let extractor = config
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("the gallery-dl config must be a JSON object"))?
+        .entry("extractor")
+        .or_insert_with(|| json!({}))
+        .as_object_mut()
+        .ok_or_else(|| {
+            anyhow!("the \"extractor\" gallery-dl config value must be a JSON object")
+        })?;
+    extractor.insert("username".to_string(), credential.username.into());
+    extractor.insert("password".to_string(), credential.password.into());
+    Ok(())
+}
+
+/// Runs gallery-dl into the root directory of `vault`, returning the paths of the files it
+/// downloaded.
+async fn run_gallery_dl(
+    vault: &Vault,
+    progress: &ProgressSenderRef,
     params: GalleryDLParams,
-) -> AsyncTaskReturn {
+    credential: Option<Credential>,
+    archive_path: Option<PathBuf>,
+) -> anyhow::Result<Vec<PathBuf>> {
     let dl_progress = progress.sub_task("Download", 0.5);
     dl_progress.send(ProgressState::Determinate(0.0));
 
@@ -230,28 +458,32 @@ pub async fn perform_gallery_dl_download(
 
     let mut cmd = Command::new(prog.as_str());
     cmd.arg(params.source_url());
-    cmd.arg("--directory")
-        .arg(state.current_vault()?.root_dir()?);
+    cmd.arg("--directory").arg(vault.root_dir()?);
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
 
     match &params.login {
-        GalleryDLLogin::None => &mut cmd,
+        GalleryDLLogin::None | GalleryDLLogin::UsernamePassword { .. } => &mut cmd,
         GalleryDLLogin::FirefoxCookies => cmd.arg("--cookies-from-browser").arg("firefox"),
         GalleryDLLogin::ChromeCookies => cmd.arg("--cookies-from-browser").arg("chrome"),
-        GalleryDLLogin::UsernamePassword { username, password } => cmd
-            .arg("--username")
-            .arg(username)
-            .arg("--password")
-            .arg(password),
     };
 
-    let mut tmp_config_path = std::env::temp_dir();
-    tmp_config_path.push(format!("{}.json", Uuid::new_v4()));
-    tokio::fs::write(tmp_config_path.clone(), params.json_config).await?;
+    if let Some(archive_path) = archive_path {
+        cmd.arg("--download-archive").arg(archive_path);
+    }
+
+    // secrets are passed through the config file rather than the command line, which is
+    // logged and visible to other processes; the file is only readable by the current user and
+    // is removed once it goes out of scope
+    let mut config: serde_json::Value = serde_json::from_str(&params.json_config)?;
+    if let Some(credential) = credential {
+        set_config_credential(&mut config, credential)?;
+    }
+    let tmp_config = tempfile::Builder::new().suffix(".json").tempfile()?;
+    tokio::fs::write(tmp_config.path(), serde_json::to_vec(&config)?).await?;
     cmd.arg("--config-ignore")
         .arg("--config")
-        .arg(tmp_config_path);
+        .arg(tmp_config.path());
 
     cmd.raw_arg(params.cli_arguments);
 
@@ -265,9 +497,19 @@ pub async fn perform_gallery_dl_download(
     let stdout_tee: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
     let stderr_tee: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
 
-    join_set.spawn(async move { Ok(Some(child_process.wait().await?)) });
+    let cancellation = progress.cancellation();
     join_set.spawn(async move {
-        produce_lines_as_progress(stdout, dl_progress, stdout_tee).await?;
+        tokio::select! {
+            status = child_process.wait() => Ok(Some(status?)),
+            () = cancellation.cancelled() => {
+                child_process.kill().await?;
+                Ok(Some(child_process.wait().await?))
+            }
+        }
+    });
+    let stdout_lines = Arc::clone(&stdout_tee);
+    join_set.spawn(async move {
+        produce_lines_as_progress(stdout, dl_progress, stdout_lines).await?;
         Ok(None)
     });
     join_set.spawn(async move {
@@ -281,7 +523,9 @@ pub async fn perform_gallery_dl_download(
             Some(status) => break status,
         }
     };
-    join_set.shutdown().await;
+    // let the output readers finish so that no lines are lost
+    while join_set.join_next().await.is_some() {}
+    progress.cancellation().check()?;
 
     if !status.success() {
         return Err(anyhow!(AppError::CommandError {
@@ -295,5 +539,292 @@ pub async fn perform_gallery_dl_download(
         }));
     }
 
-    Ok(AsyncTaskResult::None)
+    let lines = stdout_tee.lock().await;
+    Ok(downloaded_paths(&lines))
+}
+
+/// gallery-dl prints the path of each downloaded file, and prefixes skipped files with `#`.
+fn downloaded_paths(lines: &[String]) -> Vec<PathBuf> {
+    lines
+        .iter()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'))
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Imports the files that were just downloaded and links their sidecars, without rescanning
+/// the rest of the vault.
+async fn import_downloaded(
+    state: &AppStateRef,
+    vault: &Arc<Vault>,
+    paths: Vec<PathBuf>,
+    tag_path: Option<String>,
+    progress: &ProgressSenderRef,
+) -> anyhow::Result<Vec<SingleImportResult>> {
+    let json_ext = OsStr::new("json");
+    let entries = paths
+        .into_iter()
+        .filter(|path| path.extension() != Some(json_ext))
+        .map(|path| {
+            let last_modified = path
+                .metadata()
+                .and_then(|m| m.modified())
+                .map_or(Utc::now(), Into::into);
+            (path.into_boxed_path(), last_modified)
+        })
+        .collect_vec();
+
+    let results = process_many(
+        entries,
+        progress.sub_task("Import", 0.4),
+        |(path, last_modified)| import_single_image(Arc::clone(vault), path, last_modified),
+        on_import_result_send_progress,
+        CONCURRENT_TASKS_LIMIT,
+    )
+    .await?;
+
+    let sidecars = results
+        .iter()
+        .flatten()
+        .filter_map(|path| {
+            let (sidecar_path, sidecar_date) = find_sidecar(path)?;
+            Some((path.to_path_buf(), sidecar_path, sidecar_date))
+        })
+        .collect_vec();
+
+    process_many(
+        sidecars,
+        progress.sub_task("Link sidecars", 0.1),
+        |(path, sc, sc_date)| {
+            link_single_sidecar(state.clone(), Arc::clone(vault), path, sc, sc_date, true)
+        },
+        on_import_result_send_progress,
+        CONCURRENT_TASKS_LIMIT,
+    )
+    .await?;
+
+    if let Some(tag_id) = tag_path.and_then(|t| vault.get_or_create_tag_path(&t)) {
+        for path in results.iter().flatten() {
+            let item = vault.get_item(path)?;
+            item.set_field_value(tag_id, FieldValue::Tag);
+            state.commit_item(Arc::clone(vault), &item, true)?;
+        }
+    }
+
+    Ok(results)
+}
+
+/// Runs gallery-dl and then, if requested, imports whatever it downloaded. Returns the number of
+/// files downloaded along with the import results.
+async fn download_and_import(
+    state: &AppStateRef,
+    vault: &Arc<Vault>,
+    progress: &ProgressSenderRef,
+    params: GalleryDLParams,
+    archive_path: Option<PathBuf>,
+) -> anyhow::Result<(usize, Option<Vec<SingleImportResult>>)> {
+    let import = params.import_downloads;
+    let tag_path = params.tag_downloads.then(|| params.download_tag());
+
+    let credential = match &params.login {
+        GalleryDLLogin::UsernamePassword {
+            credential: Some(credential_ref),
+            ..
+        } => Some(state.credentials().get(credential_ref)?),
+        GalleryDLLogin::UsernamePassword {
+            credential: None,
+            legacy: Some(_),
+        } => {
+            return Err(anyhow!(
+                "the login for this download is stored in plain text and must be re-entered"
+            ))
+        }
+        GalleryDLLogin::UsernamePassword {
+            credential: None,
+            legacy: None,
+        } => return Err(anyhow!("no credential has been saved for this download")),
+        _ => None,
+    };
+
+    let paths = run_gallery_dl(vault, progress, params, credential, archive_path).await?;
+    let count = paths.len();
+    if !import {
+        return Ok((count, None));
+    }
+
+    let results = import_downloaded(state, vault, paths, tag_path, progress).await?;
+    Ok((count, Some(results)))
+}
+
+#[allow(clippy::module_name_repetitions)]
+pub async fn perform_gallery_dl_download(
+    state: AppStateRef,
+    progress: ProgressSenderRef,
+    params: GalleryDLParams,
+) -> AsyncTaskReturn {
+    let vault = state.current_vault()?;
+    let (_, results) = download_and_import(&state, &vault, &progress, params, None).await?;
+    let Some(results) = results else {
+        return Ok(AsyncTaskResult::None);
+    };
+
+    state.save_vault_deferred(Arc::clone(&vault));
+    Ok(AsyncTaskResult::ImportComplete {
+        path: vault.root_dir()?.into(),
+        results,
+    })
+}
+
+async fn locate_gallery_dl(params: &mut GalleryDLParams) -> anyhow::Result<()> {
+    if params
+        .location
+        .as_ref()
+        .is_some_and(|l| Path::new(l).exists())
+    {
+        return Ok(());
+    }
+
+    #[cfg(windows)]
+    let path = get_first_line(Command::new("where.exe").arg("gallery-dl")).await?;
+    #[cfg(not(windows))]
+    let path = get_first_line(Command::new("which").arg("gallery-dl")).await?;
+
+    params.location = Some(path);
+    Ok(())
+}
+
+/// Runs each of the given subscriptions of `vault_name` in turn, recording a run in each
+/// subscription's history whether or not it succeeds.
+pub async fn run_subscriptions(
+    state: AppStateRef,
+    vault_name: String,
+    ids: Vec<Uuid>,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    let vault = state.get_vault(&vault_name)?;
+    let root_dir = vault.root_dir()?;
+    #[allow(clippy::cast_precision_loss)]
+    let weight = 1.0 / ids.len() as f32;
+    let mut errors = vec![];
+    let mut import_results: Option<Vec<SingleImportResult>> = None;
+
+    for id in ids {
+        let Some(mut subscription) = vault.get_subscription(&id) else {
+            continue;
+        };
+
+        let started = Utc::now();
+        let mut params = subscription.params.clone();
+        let result = match locate_gallery_dl(&mut params).await {
+            Ok(()) => {
+                download_and_import(
+                    &state,
+                    &vault,
+                    &progress.sub_task(&subscription.name, weight),
+                    params,
+                    Some(subscription.archive_path(&root_dir)),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+
+        let (new_files, error) = match result {
+            Ok((count, results)) => {
+                if let Some(results) = results {
+                    import_results.get_or_insert_with(Vec::new).extend(results);
+                }
+                (count, None)
+            }
+            Err(e) => {
+                errors.push(format!("{}: {e}", subscription.name));
+                (0, Some(e.to_string()))
+            }
+        };
+        subscription.record_run(SubscriptionRun {
+            started,
+            finished: Utc::now(),
+            new_files,
+            error,
+        });
+        vault.set_subscription(subscription);
+    }
+
+    state.save_vault_deferred(vault);
+
+    if !errors.is_empty() {
+        return Err(anyhow!(errors.join("\n")).context("while running subscriptions"));
+    }
+
+    Ok(match import_results {
+        Some(results) => AsyncTaskResult::ImportComplete {
+            path: root_dir.into(),
+            results,
+        },
+        None => AsyncTaskResult::None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_downloaded_paths() {
+        let lines = [
+            "/vault/twitter_1_2_1.jpg".to_string(),
+            "# /vault/twitter_1_2_2.jpg".to_string(),
+            String::new(),
+            "/vault/twitter_1_3_1.png".to_string(),
+        ];
+        assert_eq!(
+            downloaded_paths(&lines),
+            vec![
+                PathBuf::from("/vault/twitter_1_2_1.jpg"),
+                PathBuf::from("/vault/twitter_1_3_1.png")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_legacy_login() {
+        let login: GalleryDLLogin = serde_json::from_str(
+            r#"{"UsernamePassword": {"username": "user", "password": "hunter2"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            login,
+            GalleryDLLogin::UsernamePassword {
+                credential: None,
+                legacy: Some(Credential {
+                    username: "user".to_string(),
+                    password: "hunter2".to_string(),
+                }),
+            }
+        );
+
+        let json = serde_json::to_string(&login).unwrap();
+        assert_eq!(json, r#"{"UsernamePassword":{"credential":null}}"#);
+        assert_eq!(
+            serde_json::from_str::<GalleryDLLogin>(&json).unwrap(),
+            GalleryDLLogin::UsernamePassword {
+                credential: None,
+                legacy: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_set_config_credential() {
+        let credential = Credential {
+            username: "user".to_string(),
+            password: "hunter2".to_string(),
+        };
+        let mut config = json!({});
+        set_config_credential(&mut config, credential.clone()).unwrap();
+        assert_eq!(config["extractor"]["username"], "user");
+
+        assert!(set_config_credential(&mut json!([]), credential.clone()).is_err());
+        assert!(set_config_credential(&mut json!({"extractor": 1}), credential).is_err());
+    }
 }
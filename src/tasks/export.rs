@@ -0,0 +1,529 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use itertools::Itertools;
+use rusqlite::types::Value as SqlValue;
+use serde::{Deserialize, Serialize};
+use tokio::task::block_in_place;
+use uuid::Uuid;
+
+use crate::data::{FieldStore, FieldType, FieldValue, Item, Vault};
+use crate::errors::AppError;
+use crate::fields;
+use crate::tasks::transform::PathContext;
+use crate::tasks::xmp;
+use crate::tasks::{
+    AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, ProgressState, SingleImportResult,
+};
+
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, derive_more::Display,
+)]
+pub enum ExportFormat {
+    #[default]
+    #[display("CSV")]
+    Csv,
+    #[display("JSON Lines")]
+    JsonLines,
+    #[display("SQLite database")]
+    Sqlite,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::JsonLines => "jsonl",
+            Self::Sqlite => "sqlite",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportParams {
+    pub format: ExportFormat,
+    pub field_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub write_xmp_ratings: bool,
+}
+
+const PATH_COLUMN: &str = "path";
+const RESERVED_COLUMNS: [&str; 2] = ["id", PATH_COLUMN];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Column {
+    id: Uuid,
+    name: String,
+    field_type: FieldType,
+}
+
+impl Column {
+    fn is_tag(&self) -> bool {
+        matches!(self.field_type, FieldType::Tag | FieldType::Container)
+    }
+
+    fn sql_type(&self) -> &'static str {
+        match self.field_type {
+            FieldType::Tag | FieldType::Container | FieldType::Boolean | FieldType::Int => {
+                "INTEGER"
+            }
+            FieldType::Float => "REAL",
+            _ => "TEXT",
+        }
+    }
+}
+
+/// Resolves the selected fields into columns, named after their definitions. Names that are
+/// used more than once or clash with the built-in columns have the field ID appended.
+fn columns_of(vault: &Vault, field_ids: &[Uuid]) -> Vec<Column> {
+    let defs = field_ids
+        .iter()
+        .filter_map(|id| vault.get_definition(id).map(|d| d.clone()))
+        .collect_vec();
+    let name_counts = defs.iter().map(|d| d.name.to_lowercase()).counts();
+    defs.into_iter()
+        .map(|def| {
+            let name = if name_counts[&def.name.to_lowercase()] > 1
+                || RESERVED_COLUMNS
+                    .iter()
+                    .any(|c| def.name.eq_ignore_ascii_case(c))
+            {
+                format!("{} ({})", def.name, def.id)
+            } else {
+                def.name.to_string()
+            };
+            Column {
+                id: def.id,
+                name,
+                field_type: def.field_type,
+            }
+        })
+        .collect()
+}
+
+fn format_colour(value: &FieldValue) -> Option<String> {
+    let FieldValue::Colour(c) = value else {
+        return None;
+    };
+    Some(format!("#{:02x}{:02x}{:02x}", c.r(), c.g(), c.b()))
+}
+
+/// Encodes a value as JSON. Item references are written as `vault:path`, and colours as hex
+/// strings.
+pub(crate) fn value_to_json(value: &FieldValue) -> serde_json::Value {
+    match value {
+        FieldValue::Tag | FieldValue::Container => true.into(),
+        FieldValue::Boolean(b) => (*b).into(),
+        FieldValue::Int(i) => (*i).into(),
+        FieldValue::Float(f) => f.into_inner().into(),
+        FieldValue::String(s) => s.as_str().into(),
+        FieldValue::ItemRef((v, p)) => format!("{v}:{p}").into(),
+        FieldValue::Colour(_) => format_colour(value).into(),
+        FieldValue::DateTime(dt) => dt.to_rfc3339().into(),
+        FieldValue::List(list) => list.iter().map(value_to_json).collect(),
+        FieldValue::Dictionary(dict) => dict
+            .iter()
+            .map(|(k, v)| (k.to_string(), value_to_json(v)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+    }
+}
+
+/// Encodes a value as text for a single cell. Lists and dictionaries are written as JSON.
+fn value_to_text(value: &FieldValue) -> String {
+    match value_to_json(value) {
+        serde_json::Value::String(s) => s,
+        v => v.to_string(),
+    }
+}
+
+fn value_to_sql(value: &FieldValue) -> SqlValue {
+    match value {
+        FieldValue::Tag | FieldValue::Container => SqlValue::Integer(1),
+        FieldValue::Boolean(b) => SqlValue::Integer((*b).into()),
+        FieldValue::Int(i) => SqlValue::Integer(*i),
+        FieldValue::Float(f) => SqlValue::Real(f.into_inner()),
+        _ => SqlValue::Text(value_to_text(value)),
+    }
+}
+
+/// Returns the value of the column for the item. Tag columns are set if the item has the tag or
+/// any of its descendants.
+fn cell(vault: &Vault, item: &Item, column: &Column) -> Option<FieldValue> {
+    if column.is_tag() {
+        return item
+            .has_tag(vault, &column.id)
+            .is_ok_and(|b| b)
+            .then_some(FieldValue::Tag);
+    }
+    item.get_field_value(&column.id).map(|v| v.clone())
+}
+
+fn send_item_progress(progress: &ProgressSenderRef, i: usize, total: usize) {
+    #[allow(clippy::cast_precision_loss)]
+    progress.send(ProgressState::DeterminateWithMessage(
+        i as f32 / total as f32,
+        format!("{i}/{total} items"),
+    ));
+}
+
+fn write_csv(
+    path: &Path,
+    vault: &Vault,
+    items: &[Arc<Item>],
+    columns: &[Column],
+    progress: &ProgressSenderRef,
+) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(
+        [PATH_COLUMN]
+            .into_iter()
+            .chain(columns.iter().map(|c| c.name.as_str())),
+    )?;
+    for (i, item) in items.iter().enumerate() {
+        send_item_progress(progress, i, items.len());
+        let row = columns.iter().map(|c| {
+            cell(vault, item, c)
+                .map(|v| value_to_text(&v))
+                .unwrap_or_default()
+        });
+        writer.write_record([item.path().to_string()].into_iter().chain(row))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_json_lines(
+    path: &Path,
+    vault: &Vault,
+    items: &[Arc<Item>],
+    columns: &[Column],
+    progress: &ProgressSenderRef,
+) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for (i, item) in items.iter().enumerate() {
+        send_item_progress(progress, i, items.len());
+        let mut row = serde_json::Map::new();
+        row.insert(PATH_COLUMN.to_string(), item.path().into());
+        for column in columns {
+            if let Some(value) = cell(vault, item, column) {
+                row.insert(column.name.clone(), value_to_json(&value));
+            }
+        }
+        serde_json::to_writer(&mut writer, &row)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Writes a database with the tables `definitions`, `definition_parents`, `items` (with a column
+/// per selected non-tag field) and `item_tags` (the selected tags each item has directly).
+fn write_sqlite(
+    path: &Path,
+    vault: &Vault,
+    items: &[Arc<Item>],
+    columns: &[Column],
+    progress: &ProgressSenderRef,
+) -> anyhow::Result<()> {
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    let mut conn = rusqlite::Connection::open(path)?;
+    let tx = conn.transaction()?;
+
+    let (tag_columns, value_columns): (Vec<_>, Vec<_>) = columns.iter().partition(|c| c.is_tag());
+
+    tx.execute_batch(
+        "CREATE TABLE definitions (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            field_type TEXT NOT NULL
+        );
+        CREATE TABLE definition_parents (
+            id TEXT NOT NULL REFERENCES definitions(id),
+            parent_id TEXT NOT NULL REFERENCES definitions(id),
+            PRIMARY KEY (id, parent_id)
+        );
+        CREATE TABLE item_tags (
+            item_id INTEGER NOT NULL REFERENCES items(id),
+            tag_id TEXT NOT NULL REFERENCES definitions(id),
+            PRIMARY KEY (item_id, tag_id)
+        );",
+    )?;
+    let item_columns = value_columns
+        .iter()
+        .map(|c| format!(", {} {}", quote_ident(&c.name), c.sql_type()))
+        .join("");
+    tx.execute(
+        &format!(
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, {PATH_COLUMN} TEXT UNIQUE NOT NULL{item_columns})"
+        ),
+        [],
+    )?;
+
+    {
+        let defs = vault.iter_field_defs().map(|d| d.clone()).collect_vec();
+        let mut insert_def = tx.prepare("INSERT INTO definitions VALUES (?1, ?2, ?3)")?;
+        let mut insert_parent = tx.prepare("INSERT INTO definition_parents VALUES (?1, ?2)")?;
+        for def in &defs {
+            insert_def.execute((
+                def.id.to_string(),
+                def.name.as_str(),
+                def.field_type.to_string(),
+            ))?;
+        }
+        for def in &defs {
+            for parent_id in def.iter_parent_ids() {
+                insert_parent.execute((def.id.to_string(), parent_id.to_string()))?;
+            }
+        }
+
+        let placeholders = (2..value_columns.len() + 2)
+            .map(|i| format!(", ?{i}"))
+            .join("");
+        let mut insert_item = tx.prepare(&format!(
+            "INSERT INTO items ({PATH_COLUMN}{}) VALUES (?1{placeholders})",
+            value_columns
+                .iter()
+                .map(|c| format!(", {}", quote_ident(&c.name)))
+                .join("")
+        ))?;
+        let mut insert_tag = tx.prepare("INSERT INTO item_tags VALUES (?1, ?2)")?;
+
+        for (i, item) in items.iter().enumerate() {
+            send_item_progress(progress, i, items.len());
+            let values = [SqlValue::Text(item.path().to_string())]
+                .into_iter()
+                .chain(value_columns.iter().map(|c| {
+                    item.get_field_value(&c.id)
+                        .map_or(SqlValue::Null, |v| value_to_sql(&v))
+                }))
+                .collect_vec();
+            insert_item.execute(rusqlite::params_from_iter(values))?;
+            let item_id = tx.last_insert_rowid();
+            for column in &tag_columns {
+                if cell(vault, item, column).is_some() {
+                    insert_tag.execute((item_id, column.id.to_string()))?;
+                }
+            }
+        }
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Writes the rating of each item into its XMP sidecar. Items without a rating are only
+/// written as unrated if they already have a sidecar.
+fn write_xmp_ratings(
+    vault: &Vault,
+    items: &[Arc<Item>],
+    progress: &ProgressSenderRef,
+) -> Vec<SingleImportResult> {
+    let mut results = vec![];
+    for (i, item) in items.iter().enumerate() {
+        let path: Box<Path> = Path::new(item.path()).into();
+        let res = (|| {
+            let abs_path = vault.resolve_abs_path(&path)?;
+            let rating = item.get_known_field_value(fields::general::RATING)?;
+            if rating.is_some() || xmp::has_sidecar(&abs_path) {
+                xmp::write_rating(&abs_path, rating.unwrap_or(0))?;
+                return Ok(true);
+            }
+            anyhow::Ok(false)
+        })();
+        match res {
+            Ok(true) => results.push(Ok(path)),
+            Ok(false) => {}
+            Err(e) => results.push(Err(e.context(PathContext(path.to_path_buf())))),
+        }
+        send_item_progress(progress, i, items.len());
+    }
+    results
+}
+
+/// Writes the given items of the vault into a table, with one row per item and one column per
+/// selected field.
+#[tracing::instrument]
+pub async fn export_items(
+    vault: Arc<Vault>,
+    items: Vec<Arc<Item>>,
+    params: ExportParams,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    let format = params.format;
+    let dialog = rfd::AsyncFileDialog::new()
+        .add_filter(format.to_string(), &[format.extension()])
+        .set_file_name(format!("{}.{}", vault.name, format.extension()));
+
+    let fp = dialog.save_file().await.ok_or(AppError::UserCancelled)?;
+    let path = fp.path().to_path_buf();
+
+    block_in_place(|| {
+        let field_ids = params.field_ids.iter().unique().copied().collect_vec();
+        let columns = columns_of(&vault, &field_ids);
+        match format {
+            ExportFormat::Csv => write_csv(&path, &vault, &items, &columns, &progress),
+            ExportFormat::JsonLines => write_json_lines(&path, &vault, &items, &columns, &progress),
+            ExportFormat::Sqlite => write_sqlite(&path, &vault, &items, &columns, &progress),
+        }
+    })
+    .with_context(|| format!("while exporting items to {}", path.display()))?;
+
+    if !params.write_xmp_ratings {
+        return Ok(AsyncTaskResult::None);
+    }
+
+    let results = block_in_place(|| write_xmp_ratings(&vault, &items, &progress));
+    Ok(AsyncTaskResult::BatchComplete {
+        title: "XMP ratings written".to_string(),
+        results,
+    })
+}
+
+/// Returns the IDs of all fields that are set on at least one of the items, besides the
+/// internal ones that are not meant to be linked between items.
+pub fn used_field_ids(vault: &Vault, items: &[Arc<Item>]) -> HashSet<Uuid> {
+    items
+        .iter()
+        .flat_map(|item| item.iter_fields().map(|f| *f.key()).collect_vec())
+        .filter(|id| {
+            vault
+                .get_definition(id)
+                .is_some_and(|def| !def.has_field(&crate::fields::meta::NO_LINK.id))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::FieldDefinition;
+    use crate::tasks::{CancellationToken, ProgressSenderAsync};
+
+    #[test]
+    fn test_columns_and_values() {
+        let vault = Vault::new("test".to_string());
+        let a = FieldDefinition::tag(Uuid::new_v4(), "Series".into());
+        let b = FieldDefinition::tag(Uuid::new_v4(), "series".into());
+        let c = FieldDefinition::tag(Uuid::new_v4(), "Character".into());
+        let ids = [a.id, b.id, c.id];
+        vault.set_definition(a);
+        vault.set_definition(b);
+        vault.set_definition(c);
+
+        let names = columns_of(&vault, &ids)
+            .into_iter()
+            .map(|c| c.name)
+            .collect_vec();
+        assert_eq!(
+            names,
+            vec![
+                format!("Series ({})", ids[0]),
+                format!("series ({})", ids[1]),
+                "Character".to_string()
+            ]
+        );
+
+        let list = FieldValue::list(vec![
+            FieldValue::int(1),
+            FieldValue::string("two".into()),
+            FieldValue::itemref(("v".into(), "a/b.png".into())),
+        ]);
+        assert_eq!(value_to_text(&list), r#"[1,"two","v:a/b.png"]"#);
+        assert_eq!(value_to_text(&FieldValue::string("x".into())), "x");
+    }
+
+    #[test]
+    fn test_write_sqlite() {
+        let vault = Vault::new("test".to_string());
+        let parent = FieldDefinition::tag(Uuid::new_v4(), "parent".into());
+        let tag = FieldDefinition::tag(Uuid::new_v4(), "tagged".into()).with_parent(parent.id);
+        let (parent_id, tag_id) = (parent.id, tag.id);
+        vault.set_definition(parent);
+        vault.set_definition(tag);
+        let item = vault.get_item_or_init(Path::new("a.png")).unwrap();
+        item.set_field_value(tag_id, FieldValue::Tag);
+        item.set_known_field_value(crate::fields::image::WIDTH, 640);
+        vault.get_item_or_init(Path::new("b.png")).unwrap();
+        let items = vault.iter_items().map(|i| Arc::clone(&i)).collect_vec();
+        let columns = columns_of(&vault, &[parent_id, tag_id, crate::fields::image::WIDTH.id]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.sqlite");
+        let (tx, _rx) = tokio::sync::watch::channel(ProgressState::NotStarted);
+        let progress: ProgressSenderRef =
+            ProgressSenderAsync::new("test".to_string(), tx, CancellationToken::default());
+        write_sqlite(&path, &vault, &items, &columns, &progress).unwrap();
+
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        let width: i64 = conn
+            .query_row(
+                "SELECT width FROM items JOIN item_tags ON items.id = item_tags.item_id \
+                 WHERE item_tags.tag_id = ?1",
+                [tag_id.to_string()],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(width, 640);
+
+        // the parent tag is inherited from the child
+        let tagged: Vec<String> = conn
+            .prepare("SELECT tag_id FROM item_tags ORDER BY tag_id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            tagged,
+            [parent_id.to_string(), tag_id.to_string()]
+                .into_iter()
+                .sorted()
+                .collect_vec()
+        );
+    }
+
+    #[test]
+    fn test_write_xmp_ratings() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = Vault::new("test".to_string()).with_file_path(&dir.path().join("test.json"));
+        for (path, rating) in [("a.png", Some(3)), ("b.png", Some(2)), ("c.png", None)] {
+            let item = vault.get_item_or_init(Path::new(path)).unwrap();
+            if let Some(rating) = rating {
+                item.set_known_field_value(fields::general::RATING, rating);
+            }
+        }
+        std::fs::write(dir.path().join("b.xmp"), [0xff, 0xfe, 0x00]).unwrap();
+        let items = vault
+            .iter_items()
+            .map(|i| Arc::clone(&i))
+            .sorted_by(|a, b| a.path().cmp(b.path()))
+            .collect_vec();
+
+        let (tx, _rx) = tokio::sync::watch::channel(ProgressState::NotStarted);
+        let progress: ProgressSenderRef =
+            ProgressSenderAsync::new("test".to_string(), tx, CancellationToken::default());
+        let results = write_xmp_ratings(&vault, &items, &progress);
+
+        // the unreadable sidecar of b.png doesn't stop a.png from being written
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_deref().unwrap(), Path::new("a.png"));
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<PathContext>().map(|p| p.0.as_path()),
+            Some(Path::new("b.png"))
+        );
+        assert!(dir.path().join("a.png.xmp").is_file());
+        assert!(!dir.path().join("c.xmp").exists() && !dir.path().join("c.png.xmp").exists());
+    }
+}
@@ -36,6 +36,7 @@ fn evaluate_match_expression_string(
         ValueMatchExpression::StartsWith(x) => value.starts_with(x.as_str()?),
         ValueMatchExpression::EndsWith(x) => value.ends_with(x.as_str()?),
         ValueMatchExpression::Regex(x) => x.is_match(value),
+        ValueMatchExpression::Near(..) => false,
     })
 }
 
@@ -66,6 +67,7 @@ where
         ValueMatchExpression::StartsWith(x) => value.to_string().starts_with(x.as_str()?),
         ValueMatchExpression::EndsWith(x) => value.to_string().ends_with(x.as_str()?),
         ValueMatchExpression::Regex(x) => x.is_match(&value.to_string()),
+        ValueMatchExpression::Near(..) => false,
     })
 }
 
@@ -107,10 +109,51 @@ fn evaluate_match_expression_dictionary(
     })
 }
 
+/// How far apart two colours can be in CIELAB space while still being considered near each other.
+const NEAR_COLOUR_DISTANCE: f64 = 25.0;
+
+fn is_near_colour(value: SerialColour, target: &FieldValue) -> anyhow::Result<bool> {
+    let target = *kind::Colour::try_from(target.clone())?;
+    Ok(value.perceptual_distance(target) <= NEAR_COLOUR_DISTANCE)
+}
+
+/// The share of the image taken up by palette colours near the target, using the palette weights.
+fn near_palette_share(item: &Item, target: &FieldValue) -> anyhow::Result<f64> {
+    let palette = item
+        .get_known_field_value(fields::image::PALETTE)?
+        .unwrap_or_default();
+    let weights = item
+        .get_known_field_value(fields::image::PALETTE_WEIGHTS)?
+        .unwrap_or_default();
+
+    let mut share = 0.0;
+    for (colour, weight) in palette.iter().zip(weights.iter()) {
+        if is_near_colour(*colour.as_colour()?, target)? {
+            share += weight.as_float()?.0;
+        }
+    }
+    Ok(share)
+}
+
 fn evaluate_match_expression(
     value: &FieldValue,
     expr: &ValueMatchExpression,
 ) -> anyhow::Result<bool> {
+    if let ValueMatchExpression::Near(target, _) = expr {
+        return Ok(match value {
+            FieldValue::Colour(v) => is_near_colour(*v, target)?,
+            FieldValue::List(list) => {
+                for v in list {
+                    if evaluate_match_expression(v, expr)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+            _ => false,
+        });
+    }
+
     Ok(match value {
         FieldValue::Tag => true,
         FieldValue::Container => false,
@@ -195,6 +238,11 @@ pub fn evaluate_filter(
         }
         FilterExpression::FolderMatch(x) => Path::new(item.path()).starts_with(x),
         FilterExpression::TagMatch(id) => item.has_tag(vault, id).is_ok_and(|b| b),
+        FilterExpression::FieldMatch(id, ValueMatchExpression::Near(target, min_share))
+            if *id == fields::image::PALETTE.id =>
+        {
+            near_palette_share(item, target)? >= min_share.0
+        }
         FilterExpression::FieldMatch(id, expr) => {
             if let Some(v) = item.get_field_value(id) {
                 return evaluate_match_expression(&v, expr);
@@ -202,6 +250,7 @@ pub fn evaluate_filter(
 
             false
         }
+        FilterExpression::Untagged => item.iter_fields().all(|f| fields::is_known(f.key())),
         FilterExpression::Not(a) => !evaluate_filter(item, vault, a)?,
         FilterExpression::Or(a, b) => {
             evaluate_filter(item, vault, a)? || evaluate_filter(item, vault, b)?
@@ -490,4 +539,30 @@ mod test {
         assert!(query.matches(&Utf32CachedString::from("CRÈME BRÛLÉE👌👌👌")));
         assert!(query.matches(&Utf32CachedString::from("👌👌👌CRÈME BRÛLÉE")));
     }
+
+    #[test]
+    fn test_near_colour() {
+        use super::{evaluate_match_expression, near_palette_share};
+        use crate::data::{FieldValue, Item, ValueMatchExpression};
+        use crate::tasks::palette::set_item_palette;
+
+        let teal = FieldValue::colour([0, 128, 128].into());
+        let near_teal = ValueMatchExpression::Near(teal.clone(), 0.5.into());
+        let dark_teal = FieldValue::colour([10, 115, 120].into());
+        let orange = FieldValue::colour([255, 140, 0].into());
+        assert!(evaluate_match_expression(&dark_teal, &near_teal).unwrap());
+        assert!(!evaluate_match_expression(&orange, &near_teal).unwrap());
+
+        let item = Item::new("image.png".into());
+        set_item_palette(
+            &item,
+            &[
+                ([5, 120, 125].into(), 0.6),
+                ([255, 140, 0].into(), 0.3),
+                ([0, 135, 130].into(), 0.1),
+            ],
+        );
+        let share = near_palette_share(&item, &teal).unwrap();
+        assert!((share - 0.7).abs() < 1e-9, "{share}");
+    }
 }
@@ -3,7 +3,7 @@ use anyhow::{anyhow, Context};
 use chrono::{DateTime, Utc};
 use eframe::egui;
 use eframe::egui::{vec2, Vec2};
-use magick_rust::{FilterType, MagickWand};
+use magick_rust::{FilterType, MagickWand, OrientationType};
 use std::path::Path;
 
 pub fn read_image(path: impl AsRef<Path>) -> anyhow::Result<MagickWand> {
@@ -29,6 +29,22 @@ pub fn write_image(wand: &MagickWand, path: impl AsRef<Path>) -> anyhow::Result<
     Ok(())
 }
 
+/// The orientation of the image as recorded in its metadata, as an EXIF orientation value from 1
+/// to 8, or [`None`] if it has no orientation.
+pub fn get_exif_orientation(wand: &MagickWand) -> Option<i64> {
+    match wand.get_image_orientation() {
+        OrientationType::Undefined => None,
+        OrientationType::TopLeft => Some(1),
+        OrientationType::TopRight => Some(2),
+        OrientationType::BottomRight => Some(3),
+        OrientationType::BottomLeft => Some(4),
+        OrientationType::LeftTop => Some(5),
+        OrientationType::RightTop => Some(6),
+        OrientationType::RightBottom => Some(7),
+        OrientationType::LeftBottom => Some(8),
+    }
+}
+
 pub async fn get_last_modified(path: impl AsRef<Path>) -> DateTime<Utc> {
     tokio::fs::metadata(path.as_ref())
         .await
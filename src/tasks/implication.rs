@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use crate::data::Vault;
+use crate::state::AppStateRef;
+use crate::tasks::vault::save_vault_and_links;
+use crate::tasks::{AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, ProgressState};
+
+#[tracing::instrument]
+pub async fn apply_implications_to_vault(
+    state: AppStateRef,
+    vault: Arc<Vault>,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    let items = vault
+        .iter_items()
+        .map(|i| Arc::clone(&i))
+        .collect::<Vec<_>>();
+    let total = items.len();
+
+    let sub_task = progress.sub_task("Apply", 0.9);
+    #[allow(clippy::cast_precision_loss)]
+    for (i, item) in items.into_iter().enumerate() {
+        // make sure to skip saving as it should only happen once afterwards
+        state.commit_item(Arc::clone(&vault), &item, true)?;
+        sub_task.send(ProgressState::DeterminateWithMessage(
+            i as f32 / total as f32,
+            item.path().to_string(),
+        ));
+    }
+    sub_task.send(ProgressState::Completed);
+
+    save_vault_and_links(state, vault, progress.sub_task("Save", 0.1)).await?;
+
+    Ok(AsyncTaskResult::None)
+}
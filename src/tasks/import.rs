@@ -1,20 +1,26 @@
-use crate::data::{FieldStore, ThumbnailParams, Vault};
+use crate::data::{FieldStore, Item, Job, JobKind, ThumbnailParams, Vault};
 use anyhow::{anyhow, Context};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, Utc};
 use magick_rust::MagickWand;
 use std::fs::Metadata;
 use std::future::Future;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 use tokio::fs::DirEntry;
-use tokio::task::JoinSet;
-use tracing::info;
+use tokio::task::{block_in_place, JoinSet};
+use tracing::{info, warn};
+use url::Url;
 
 use crate::errors::AppError;
 use crate::fields;
-use crate::state::THUMBNAIL_LOW_QUALITY_HEIGHT;
-use crate::tasks::thumbnail::commit_thumbnail_to_fs;
+use crate::state::{AppStateRef, THUMBNAIL_LOW_QUALITY_HEIGHT};
+use crate::tasks::image::get_exif_orientation;
+use crate::tasks::job::queue_job;
+use crate::tasks::palette::{extract_palette, set_item_palette};
+use crate::tasks::thumbnail::{commit_thumbnail_to_fs, read_cached_thumbnail};
+use crate::tasks::transform::{Discriminator, PathContext};
 use crate::tasks::vault::save_vault;
+use crate::tasks::xmp;
 use crate::tasks::{
     AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, ProgressState, SingleImportResult,
 };
@@ -25,6 +31,17 @@ pub async fn import_single_image(
     vault: Arc<Vault>,
     path: Box<Path>,
     last_modified: DateTime<Utc>,
+) -> SingleImportResult {
+    let context = PathContext(path.to_path_buf());
+    import_image(vault, path, last_modified)
+        .await
+        .with_context(|| context)
+}
+
+async fn import_image(
+    vault: Arc<Vault>,
+    path: Box<Path>,
+    last_modified: DateTime<Utc>,
 ) -> SingleImportResult {
     let item = vault.get_item_or_init(&path)?;
 
@@ -55,17 +72,11 @@ pub async fn import_single_image(
 
     item.set_known_field_value(fields::general::LAST_MODIFIED, last_modified);
 
-    let rel_path = vault.resolve_rel_path(&path)?;
-    let abs_path = vault.resolve_abs_path(&path)?;
-    commit_thumbnail_to_fs(&ThumbnailParams {
-        rel_path: rel_path.to_string(),
-        abs_path,
-        last_modified: Some(last_modified),
-        height: THUMBNAIL_LOW_QUALITY_HEIGHT,
-        transform_params: None,
-    })
-    .await
-    .map_err(|e| anyhow!(e))?;
+    // the palette is only used for searching by colour, so failing to extract it shouldn't
+    // prevent the image from being imported
+    if let Err(e) = import_palette(&vault, &item, &path, last_modified).await {
+        warn!("{e:#}");
+    }
 
     #[allow(clippy::cast_possible_wrap)]
     {
@@ -77,6 +88,23 @@ pub async fn import_single_image(
         let height = wand.get_image_height() as i64;
         item.set_known_field_value(fields::image::HEIGHT, height);
         item.set_known_field_value(fields::image::WIDTH, width);
+        match get_exif_orientation(&wand) {
+            Some(orientation) => {
+                item.set_known_field_value(fields::image::ORIENTATION, orientation);
+            }
+            None => {
+                item.remove_field(&fields::image::ORIENTATION.id);
+            }
+        }
+    }
+
+    // likewise, an unreadable sidecar only means the image is imported without a rating
+    if !item.has_field(&fields::general::RATING.id) {
+        match xmp::read_rating(&path) {
+            Ok(Some(rating)) => item.set_known_field_value(fields::general::RATING, rating),
+            Ok(None) => {}
+            Err(e) => warn!("{e:#}"),
+        }
     }
 
     vault.set_last_updated();
@@ -84,6 +112,32 @@ pub async fn import_single_image(
     Ok(path)
 }
 
+async fn import_palette(
+    vault: &Vault,
+    item: &Item,
+    path: &Path,
+    last_modified: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let thumb_params = ThumbnailParams {
+        rel_path: vault.resolve_rel_path(path)?.to_string(),
+        abs_path: vault.resolve_abs_path(path)?,
+        last_modified: Some(last_modified),
+        height: THUMBNAIL_LOW_QUALITY_HEIGHT,
+        transform_params: None,
+    };
+    commit_thumbnail_to_fs(&thumb_params)
+        .await
+        .map_err(|e| anyhow!(e))
+        .with_context(|| format!("while creating thumbnail of {}", path.display()))?;
+
+    block_in_place(|| -> anyhow::Result<()> {
+        let wand = read_cached_thumbnail(&thumb_params)?;
+        set_item_palette(item, &extract_palette(&wand)?);
+        Ok(())
+    })
+    .with_context(|| format!("while extracting colour palette of {}", path.display()))
+}
+
 #[tracing::instrument]
 pub async fn select_and_import_one(
     vault: Arc<Vault>,
@@ -123,6 +177,36 @@ pub async fn select_and_import_one(
     })
 }
 
+#[tracing::instrument]
+pub async fn import_path(
+    vault: Arc<Vault>,
+    path: PathBuf,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    let last_modified = tokio::fs::metadata(&path)
+        .await
+        .and_then(|m| m.modified())
+        .map_or(Utc::now(), |m| m.into());
+
+    progress.send(ProgressState::DeterminateWithMessage(
+        0.0,
+        path.display().to_string(),
+    ));
+    let res = import_single_image(
+        Arc::clone(&vault),
+        path.clone().into_boxed_path(),
+        last_modified,
+    )
+    .await;
+
+    save_vault(vault, progress.sub_task("Save", 0.1)).await?;
+
+    Ok(AsyncTaskResult::ImportComplete {
+        path: path.into(),
+        results: vec![res],
+    })
+}
+
 pub async fn scan_recursively<T>(
     root_dir: &Path,
     progress: ProgressSenderRef,
@@ -134,6 +218,7 @@ pub async fn scan_recursively<T>(
     let mut dir_queue: Vec<PathBuf> = vec![root_dir.to_owned()];
 
     while let Some(dir_path) = dir_queue.pop() {
+        progress.cancellation().check()?;
         let mut read_dir = tokio::fs::read_dir(dir_path)
             .await
             .with_context(|| format!("reading from directory {}", root_dir.display()))?;
@@ -178,9 +263,10 @@ pub async fn process_many<
     concurrency_limit: usize,
 ) -> anyhow::Result<Vec<ResultT>> {
     let total = entries.len();
+    let cancellation = progress.cancellation();
     let mut join_set = JoinSet::new();
 
-    while join_set.len() < concurrency_limit {
+    while join_set.len() < concurrency_limit && !cancellation.is_cancelled() {
         if let Some(entry) = entries.pop() {
             join_set.spawn(task_factory(entry));
         } else {
@@ -198,6 +284,11 @@ pub async fn process_many<
 
         results.push(task_res);
 
+        // once cancelled, the tasks in flight are allowed to finish so that each item is
+        // either fully processed or left untouched
+        if cancellation.is_cancelled() {
+            continue;
+        }
         if let Some(entry) = entries.pop() {
             join_set.spawn(task_factory(entry));
         }
@@ -217,8 +308,11 @@ pub fn on_import_result_send_progress(
     }
 }
 
+/// Scans the vault's root directory and queues a job to import every file in it, so that a large
+/// import can be resumed if the app is closed part way through.
 #[tracing::instrument]
 pub async fn import_images_recursively(
+    state: AppStateRef,
     vault: Arc<Vault>,
     progress: ProgressSenderRef,
 ) -> AsyncTaskReturn {
@@ -230,25 +324,115 @@ pub async fn import_images_recursively(
 
     let root_dir = vault.root_dir()?;
 
-    let entries = scan_recursively(
-        root_dir.as_path(),
-        progress.sub_task("Scan", 0.05),
-        |item, metadata| {
-            Some((
-                item.path().into_boxed_path(),
-                metadata
-                    .modified()
-                    .map(|m| -> DateTime<Utc> { m.into() })
-                    .unwrap_or(Utc::now()),
-            ))
-        },
-    )
+    let paths = scan_recursively(root_dir.as_path(), progress, |item, _| {
+        let path = item.path();
+        let rel_path = path.strip_prefix(&root_dir).ok()?;
+        Some(rel_path.to_str()?.to_string())
+    })
     .await?;
 
+    let job = Job::new("Import all files".to_string(), JobKind::Import, paths);
+    queue_job(&state, vault, job);
+
+    Ok(AsyncTaskResult::None)
+}
+
+/// The folder, relative to the vault root, that dropped and pasted files are copied into when no
+/// other folder has been configured.
+pub const DEFAULT_INBOX_DIR: &str = "inbox";
+
+fn inbox_path(root_dir: &Path, inbox_dir: &str) -> anyhow::Result<PathBuf> {
+    let inbox_dir = Path::new(inbox_dir);
+    if inbox_dir.as_os_str().is_empty() {
+        return Ok(root_dir.join(DEFAULT_INBOX_DIR));
+    }
+    if !inbox_dir
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(AppError::InvalidInboxFolder {
+            path: inbox_dir.display().to_string(),
+        }
+        .into());
+    }
+    Ok(root_dir.join(inbox_dir))
+}
+
+fn unused_path(path: &Path) -> anyhow::Result<PathBuf> {
+    Discriminator::from_path(path)
+        .and_then(|d| d.into_unique_path(|p| Some(!p.exists())))
+        .ok_or_else(|| anyhow!("invalid destination path {}", path.display()))
+}
+
+async fn copy_and_import(
+    vault: Arc<Vault>,
+    src_path: PathBuf,
+    dest_path: PathBuf,
+) -> SingleImportResult {
+    let dest_path = if src_path == dest_path {
+        dest_path
+    } else {
+        if let Some(parent) = dest_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let dest_path = unused_path(&dest_path)?;
+        tokio::fs::copy(&src_path, &dest_path)
+            .await
+            .with_context(|| format!("while copying {}", src_path.display()))?;
+        dest_path
+    };
+
+    let last_modified = tokio::fs::metadata(&dest_path)
+        .await
+        .and_then(|m| m.modified())
+        .map_or(Utc::now(), |m| m.into());
+    import_single_image(vault, dest_path.into_boxed_path(), last_modified).await
+}
+
+/// Imports files and folders from outside the vault by copying them into the inbox folder.
+/// Files that are already inside the vault are imported where they are.
+#[tracing::instrument]
+pub async fn import_into_inbox(
+    vault: Arc<Vault>,
+    paths: Vec<PathBuf>,
+    inbox_dir: String,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    let root_dir = vault.root_dir()?;
+    let inbox = inbox_path(&root_dir, &inbox_dir)?;
+
+    let mut entries = vec![];
+    for path in paths {
+        if path.starts_with(&root_dir) {
+            if path.is_dir() {
+                let files = scan_recursively(&path, progress.sub_task("Scan", 0.05), |item, _| {
+                    Some(item.path())
+                })
+                .await?;
+                entries.extend(files.into_iter().map(|f| (f.clone(), f)));
+            } else {
+                entries.push((path.clone(), path));
+            }
+        } else if path.is_dir() {
+            let dest_dir = inbox.join(path.file_name().unwrap_or_default());
+            let files = scan_recursively(&path, progress.sub_task("Scan", 0.05), |item, _| {
+                Some(item.path())
+            })
+            .await?;
+            for file in files {
+                let dest_path = dest_dir.join(file.strip_prefix(&path)?);
+                entries.push((file, dest_path));
+            }
+        } else if let Some(file_name) = path.file_name() {
+            let dest_path = inbox.join(file_name);
+            entries.push((path, dest_path));
+        }
+    }
+
     let results = process_many(
         entries,
-        progress.sub_task("Import", 0.90),
-        |(path, last_modified)| import_single_image(Arc::clone(&vault), path, last_modified),
+        progress.sub_task("Import", 0.9),
+        |(src_path, dest_path)| copy_and_import(Arc::clone(&vault), src_path, dest_path),
         on_import_result_send_progress,
         CONCURRENT_TASKS_LIMIT,
     )
@@ -256,8 +440,108 @@ pub async fn import_images_recursively(
 
     save_vault(vault, progress.sub_task("Save", 0.05)).await?;
 
-    Ok(AsyncTaskResult::ImportComplete {
-        path: root_dir.into(),
+    Ok(AsyncTaskResult::InboxImportComplete {
+        path: inbox.into(),
         results,
     })
 }
+
+fn paths_from_text(text: &str) -> Vec<PathBuf> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| match Url::parse(l) {
+            Ok(url) if url.scheme() == "file" => url.to_file_path().ok(),
+            Ok(_) => None,
+            Err(_) => Some(PathBuf::from(l)),
+        })
+        .filter(|p| p.is_absolute() && p.exists())
+        .collect()
+}
+
+/// Imports the image on the clipboard, saving it as a PNG in the inbox folder. If there is no
+/// image but the clipboard contains paths to files or folders, those are imported instead.
+#[tracing::instrument]
+pub async fn import_from_clipboard(
+    vault: Arc<Vault>,
+    inbox_dir: String,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    progress.send(ProgressState::Indeterminate);
+
+    let root_dir = vault.root_dir()?;
+    let inbox = inbox_path(&root_dir, &inbox_dir)?;
+
+    let paths = block_in_place(|| -> anyhow::Result<Vec<PathBuf>> {
+        let mut clipboard = arboard::Clipboard::new()?;
+        let image = match clipboard.get_image() {
+            Ok(image) => image,
+            Err(arboard::Error::ContentNotAvailable) => {
+                let text = clipboard.get_text().unwrap_or_default();
+                let paths = paths_from_text(&text);
+                return if paths.is_empty() {
+                    Err(AppError::NothingToPaste.into())
+                } else {
+                    Ok(paths)
+                };
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        #[allow(clippy::cast_possible_truncation)]
+        let buffer =
+            image::RgbaImage::from_raw(image.width as u32, image.height as u32, image.bytes.into())
+                .ok_or_else(|| anyhow!("clipboard image has an unexpected size"))?;
+
+        std::fs::create_dir_all(&inbox)?;
+        let path = unused_path(&inbox.join(format!(
+            "pasted-{}.png",
+            Local::now().format("%Y%m%d-%H%M%S")
+        )))?;
+        buffer
+            .save(&path)
+            .with_context(|| format!("while writing {}", path.display()))?;
+        Ok(vec![path])
+    })?;
+
+    import_into_inbox(vault, paths, inbox_dir, progress).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_inbox_path() {
+        let root = Path::new("/vaults/test");
+        assert_eq!(inbox_path(root, "").unwrap(), root.join("inbox"));
+        assert_eq!(
+            inbox_path(root, "incoming/new").unwrap(),
+            root.join("incoming/new")
+        );
+        assert!(inbox_path(root, "../outside").is_err());
+        assert!(inbox_path(root, "/tmp").is_err());
+    }
+
+    #[test]
+    fn test_cancel_process_many() {
+        use crate::tasks::{CancellationToken, ProgressSenderAsync};
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (tx, _rx) = tokio::sync::watch::channel(ProgressState::NotStarted);
+        let cancellation = CancellationToken::default();
+        let progress = ProgressSenderAsync::new("test".to_string(), tx, cancellation.clone());
+
+        let results = rt
+            .block_on(process_many(
+                (0..100).collect(),
+                progress,
+                |i: usize| async move { i },
+                |_, _, _| cancellation.cancel(),
+                4,
+            ))
+            .unwrap();
+        assert_eq!(results.len(), 4);
+        assert!(cancellation.check().is_err());
+    }
+}
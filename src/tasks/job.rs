@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::Utc;
+use itertools::Itertools;
+use poll_promise::Promise;
+use tracing::info;
+
+use crate::data::{ItemId, Job, JobItemState, JobKind, JobQueue, JobStatus, Vault};
+use crate::state::AppStateRef;
+use crate::tasks::import::{import_single_image, process_many};
+use crate::tasks::transform::{
+    apply_image_transformation_wrap, apply_path_transformation_wrap, save_transformed_vaults,
+    TransformResult, CONCURRENT_TASKS_LIMIT,
+};
+use crate::tasks::vault::save_vault;
+use crate::tasks::{AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, ProgressState};
+
+/// The number of items processed between each save of the vault and the job queue.
+const JOB_BATCH_SIZE: usize = 256;
+
+/// Loads the job queue saved beside `vault`. A jobs file that can't be read is moved aside to
+/// `<vault>.jobs.json.bak`, so that it neither prevents the vault from loading nor gets
+/// overwritten by the next save.
+pub async fn load_jobs(vault: &Vault) -> anyhow::Result<()> {
+    let Some(vault_path) = vault.file_path.as_deref() else {
+        return Ok(());
+    };
+    let path = JobQueue::path_for_vault(vault_path);
+    if !tokio::fs::try_exists(&path).await? {
+        return Ok(());
+    }
+
+    let res = async {
+        let contents = tokio::fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("while reading from jobs file at {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("while deserialising jobs file at {}", path.display()))
+    }
+    .await;
+
+    match res {
+        Ok(jobs) => {
+            vault.jobs().set_loaded(jobs);
+            Ok(())
+        }
+        Err(e) => {
+            let backup_path = path.with_extension("json.bak");
+            tokio::fs::rename(&path, &backup_path)
+                .await
+                .with_context(|| format!("while moving jobs file to {}", backup_path.display()))?;
+            tracing::warn!("moved unreadable jobs file to {}", backup_path.display());
+            Err(e.context(format!(
+                "the jobs file was moved to {}",
+                backup_path.display()
+            )))
+        }
+    }
+}
+
+pub async fn save_jobs(vault: &Vault) -> anyhow::Result<()> {
+    let Some(vault_path) = vault.file_path.as_deref() else {
+        return Ok(());
+    };
+    let path = JobQueue::path_for_vault(vault_path);
+
+    let data = {
+        let jobs = vault.jobs().lock();
+        if jobs.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_vec(&*jobs)?)
+        }
+    };
+
+    match data {
+        Some(data) => tokio::fs::write(&path, data).await,
+        None if tokio::fs::try_exists(&path).await? => tokio::fs::remove_file(&path).await,
+        None => Ok(()),
+    }
+    .with_context(|| format!("while writing to jobs file at {}", path.display()))
+}
+
+pub fn save_jobs_deferred(state: &AppStateRef, vault: Arc<Vault>) {
+    state.add_global_task(format!("Save jobs of {}", vault.name), |_, _| {
+        Promise::spawn_async(async move {
+            save_jobs(&vault).await?;
+            Ok(AsyncTaskResult::None)
+        })
+    });
+}
+
+/// Adds `job` to the queue of `vault` and starts processing the queue if it isn't already.
+pub fn queue_job(state: &AppStateRef, vault: Arc<Vault>, job: Job) {
+    vault.jobs().push(job);
+    start_job_runner(state, vault);
+}
+
+pub fn start_job_runner(state: &AppStateRef, vault: Arc<Vault>) {
+    if !vault.jobs().try_start_runner() {
+        return;
+    }
+    let name = format!("Run jobs of {}", vault.name);
+    state.add_vault_task(Some(vault.name.clone()), name, |s, p| {
+        Promise::spawn_async(run_jobs(s, vault, p))
+    });
+}
+
+async fn process_job_item(
+    state: AppStateRef,
+    vault: Arc<Vault>,
+    kind: Arc<JobKind>,
+    path: String,
+) -> (String, anyhow::Result<TransformResult>) {
+    let res = async {
+        let item_id = || -> anyhow::Result<ItemId> {
+            let item = vault.get_item(Path::new(&path))?;
+            Ok(ItemId::from_item(&vault, &item))
+        };
+        match kind.as_ref() {
+            JobKind::TransformImages { bulk, params } => {
+                apply_image_transformation_wrap(
+                    state,
+                    Arc::clone(&vault),
+                    item_id()?,
+                    Arc::new(bulk.clone()),
+                    Arc::new(params.clone()),
+                )
+                .await
+            }
+            JobKind::TransformPaths { bulk, params } => {
+                apply_path_transformation_wrap(
+                    state,
+                    Arc::clone(&vault),
+                    item_id()?,
+                    Arc::new(bulk.clone()),
+                    Arc::new(params.clone()),
+                )
+                .await
+            }
+            JobKind::Import => {
+                let abs_path = vault.resolve_abs_path(Path::new(&path))?;
+                let last_modified = tokio::fs::metadata(&abs_path)
+                    .await
+                    .and_then(|m| m.modified())
+                    .map_or(Utc::now(), Into::into);
+                let imported = import_single_image(
+                    Arc::clone(&vault),
+                    abs_path.into_boxed_path(),
+                    last_modified,
+                )
+                .await?;
+                Ok(TransformResult::Imported(imported.into()))
+            }
+        }
+    }
+    .await;
+    (path, res)
+}
+
+async fn run_job(
+    state: &AppStateRef,
+    vault: &Arc<Vault>,
+    job: Job,
+    progress: &ProgressSenderRef,
+) -> anyhow::Result<Vec<anyhow::Result<TransformResult>>> {
+    let cancellation = progress.cancellation().child();
+    vault.jobs().start(job.id, cancellation.clone());
+    save_jobs(vault).await?;
+
+    let job_progress = progress.sub_task_with_cancellation("Job", 1.0, cancellation.clone());
+    let kind = Arc::new(job.kind.clone());
+    let total = job.items.len();
+    let done = AtomicUsize::new(total - job.len_pending());
+    let pending = job.pending_paths().map(str::to_string).collect_vec();
+
+    let mut results = vec![];
+    for batch in pending.chunks(JOB_BATCH_SIZE) {
+        if cancellation.is_cancelled() {
+            break;
+        }
+
+        let batch_results = process_many(
+            batch.to_vec(),
+            job_progress.sub_task("Items", 1.0),
+            |path| process_job_item(state.clone(), Arc::clone(vault), Arc::clone(&kind), path),
+            |(path, _), progress, _| {
+                let done = done.fetch_add(1, Ordering::SeqCst) + 1;
+                #[allow(clippy::cast_precision_loss)]
+                let p = done as f32 / total as f32;
+                progress.send(ProgressState::DeterminateWithMessage(
+                    p,
+                    format!("{}: {path}", job.name),
+                ));
+            },
+            CONCURRENT_TASKS_LIMIT,
+        )
+        .await?;
+
+        let states = batch_results
+            .iter()
+            .map(|(path, res)| {
+                let state = match res {
+                    Ok(_) => JobItemState::Done,
+                    Err(e) => JobItemState::Failed(format!("{e:#}")),
+                };
+                (path.clone(), state)
+            })
+            .collect::<HashMap<_, _>>();
+        vault
+            .jobs()
+            .update(&job.id, |job| job.set_item_states(states));
+
+        // the vault is saved before the job so that items are never recorded as done when the
+        // vault doesn't reflect it
+        let save_progress = job_progress.sub_task("Save", 0.0);
+        match kind.bulk_params() {
+            Some(bulk) => {
+                save_transformed_vaults(state.clone(), Arc::clone(vault), bulk, save_progress)
+                    .await?;
+            }
+            None => {
+                save_vault(Arc::clone(vault), save_progress).await?;
+            }
+        }
+        save_jobs(vault).await?;
+
+        results.extend(batch_results.into_iter().map(|(_, res)| res));
+    }
+
+    vault.jobs().update(&job.id, |job| {
+        job.status = match job.status {
+            JobStatus::Running if cancellation.is_cancelled() => JobStatus::Paused,
+            JobStatus::Running => JobStatus::Completed,
+            status => status,
+        }
+    });
+    vault.jobs().finish(&job.id);
+    save_jobs(vault).await?;
+
+    Ok(results)
+}
+
+/// Runs the queued jobs of `vault` one at a time in order of priority, until none are left or
+/// the task is cancelled.
+#[tracing::instrument]
+pub async fn run_jobs(
+    state: AppStateRef,
+    vault: Arc<Vault>,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    let mut results = vec![];
+    let mut cancelled = 0;
+
+    let res = async {
+        loop {
+            while let Some(job) = vault.jobs().next_queued() {
+                if progress.is_cancelled() {
+                    break;
+                }
+                info!("running job {} ({})", job.name, job.id);
+                let id = job.id;
+                let job_results = run_job(&state, &vault, job, &progress).await;
+                if job_results.is_err() {
+                    vault.jobs().pause(&id);
+                }
+                results.extend(job_results?);
+                cancelled += vault.jobs().get(&id).map_or(0, |job| job.len_pending());
+            }
+
+            // a job may have been queued just as the last one finished
+            vault.jobs().stop_runner();
+            if progress.is_cancelled()
+                || vault.jobs().next_queued().is_none()
+                || !vault.jobs().try_start_runner()
+            {
+                return Ok::<_, anyhow::Error>(());
+            }
+        }
+    }
+    .await;
+
+    if res.is_err() {
+        vault.jobs().stop_runner();
+    }
+    res?;
+
+    if results.is_empty() {
+        return Ok(AsyncTaskResult::None);
+    }
+    Ok(AsyncTaskResult::TransformationComplete { results, cancelled })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_load_corrupt_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault_path = dir.path().join("test.riiman");
+        let vault = Vault::new("test".to_string()).with_file_path(&vault_path);
+        let jobs_path = JobQueue::path_for_vault(&vault_path);
+        std::fs::write(&jobs_path, "not json").unwrap();
+
+        assert!(load_jobs(&vault).await.is_err());
+        assert!(!jobs_path.exists());
+        assert!(dir.path().join("test.jobs.json.bak").exists());
+        assert!(vault.jobs().lock().is_empty());
+
+        // the next load starts with an empty queue
+        load_jobs(&vault).await.unwrap();
+    }
+}
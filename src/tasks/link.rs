@@ -1,32 +1,33 @@
 use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::anyhow;
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, Utc};
 use itertools::Itertools;
 use tokio::task::spawn_blocking;
 
-use crate::data::{FieldStore, FieldValue, Vault};
+use crate::data::{FieldStore, Vault};
 use crate::errors::AppError;
 use crate::fields;
 use crate::state::AppStateRef;
 use crate::tasks::import::{on_import_result_send_progress, process_many, scan_recursively};
+use crate::tasks::sidecar::SidecarProfile;
 use crate::tasks::vault::{save_current_and_linked_vaults, save_vault};
 use crate::tasks::{AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, SingleImportResult};
 
 const CONCURRENT_TASKS_LIMIT: usize = 16;
 
-async fn link_single_sidecar(
+pub(crate) async fn link_single_sidecar(
     state: AppStateRef,
+    vault: Arc<Vault>,
     path: PathBuf,
     sidecar_path: PathBuf,
     sidecar_date: DateTime<Utc>,
     skip_save: bool,
 ) -> SingleImportResult {
     let sc_path_string = sidecar_path.to_string_lossy().to_string();
-    let vault = state.current_vault()?;
     let item = vault.get_item(&path)?;
 
     if let Some(item_updated) = item.get_known_field_value(fields::general::SIDECAR_LAST_UPDATED)? {
@@ -51,62 +52,8 @@ async fn link_single_sidecar(
             error: Some("not an object".into())
         }))?;
 
-    if let Some(tweet_id) = dom.get("tweet_id").and_then(|tid| tid.as_i64()) {
-        item.set_known_field_value(fields::tweet::ID, tweet_id);
-    }
-
-    if let Some(content) = dom.get("content").and_then(|c| c.as_str()) {
-        item.set_known_field_value(fields::tweet::CONTENT, content.to_string().into());
-    }
-
-    if let Some(num) = dom.get("num").and_then(|c| c.as_i64()) {
-        item.set_known_field_value(fields::tweet::IMAGE_NUMBER, num);
-    }
-
-    if let Some(hashtags) = dom.get("hashtags").and_then(|c| c.as_array()).map(|a| {
-        a.iter()
-            .filter_map(|ht| {
-                ht.as_str()
-                    .map(|s| FieldValue::string(s.to_string().into()))
-            })
-            .collect_vec()
-    }) {
-        item.set_known_field_value(fields::tweet::HASHTAGS, hashtags);
-    }
-
-    let author = dom.get("author").and_then(|a| a.as_object());
-
-    if let Some(author_id) = author.and_then(|a| a.get("id")).and_then(|id| id.as_i64()) {
-        item.set_known_field_value(fields::tweet::AUTHOR_ID, author_id);
-    }
-
-    if let Some(author_handle) = author.and_then(|a| a.get("name")).and_then(|n| n.as_str()) {
-        item.set_known_field_value(
-            fields::tweet::AUTHOR_HANDLE,
-            author_handle.to_string().into(),
-        );
-    }
-
-    if let Some(author_name) = author.and_then(|a| a.get("nick")).and_then(|n| n.as_str()) {
-        item.set_known_field_value(fields::tweet::AUTHOR_NAME, author_name.to_string().into());
-    }
-
-    if let Some(tweet_date) = dom
-        .get("date")
-        .and_then(|d| d.as_str())
-        .and_then(|d| NaiveDateTime::parse_from_str(d, "%Y-%m-%d %H:%M:%S").ok())
-        .map(|d| d.and_utc())
-    {
-        item.set_known_field_value(fields::tweet::POST_DATE, tweet_date);
-    }
-
-    if let Some(liked_date) = dom
-        .get("date_liked")
-        .and_then(|d| d.as_str())
-        .and_then(|d| NaiveDateTime::parse_from_str(d, "%Y-%m-%d %H:%M:%S").ok())
-        .map(|d| d.and_utc())
-    {
-        item.set_known_field_value(fields::tweet::LIKED_DATE, liked_date);
+    if let Some(profile) = SidecarProfile::detect(dom) {
+        profile.apply(&item, dom);
     }
 
     // make sure to skip saving as it should only happen once afterwards
@@ -115,8 +62,24 @@ async fn link_single_sidecar(
     Ok(path.into_boxed_path())
 }
 
+/// Returns the sidecar written by gallery-dl alongside the file at `path`, if one exists.
+pub(crate) fn find_sidecar(path: &Path) -> Option<(PathBuf, DateTime<Utc>)> {
+    let extension = match path.extension() {
+        None => OsString::from("json"),
+        Some(ext) => format!("{}.json", ext.to_str()?).into(),
+    };
+
+    [path.with_extension("json"), path.with_extension(extension)]
+        .into_iter()
+        .find_map(|sidecar_path| {
+            let modified = sidecar_path.metadata().ok()?.modified().ok()?;
+            Some((sidecar_path, modified.into()))
+        })
+}
+
 pub async fn link_sidecars(state: AppStateRef, progress: ProgressSenderRef) -> AsyncTaskReturn {
-    let root_dir = state.current_vault()?.root_dir()?;
+    let vault = state.current_vault()?;
+    let root_dir = vault.root_dir()?;
 
     let entries = scan_recursively(
         root_dir.as_path(),
@@ -171,7 +134,9 @@ pub async fn link_sidecars(state: AppStateRef, progress: ProgressSenderRef) -> A
     process_many(
         entries_with_sidecars,
         progress.sub_task("Import", 0.90),
-        |(path, sc, sc_date)| link_single_sidecar(state.clone(), path, sc, sc_date, true),
+        |(path, sc, sc_date)| {
+            link_single_sidecar(state.clone(), Arc::clone(&vault), path, sc, sc_date, true)
+        },
         on_import_result_send_progress,
         CONCURRENT_TASKS_LIMIT,
     )
@@ -0,0 +1,438 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context;
+use itertools::Itertools;
+use tokio::task::block_in_place;
+use uuid::Uuid;
+
+use crate::data::transform::DestinationExistingBehaviour;
+use crate::data::{
+    DefinitionImport, FieldConflictPolicy, FieldDefinition, FieldStore, FilterExpression, Item,
+    Vault,
+};
+use crate::errors::AppError;
+use crate::state::AppStateRef;
+use crate::tasks::filter::evaluate_filter;
+use crate::tasks::transform::{Discriminator, PathContext, TransformResult};
+use crate::tasks::vault::save_vault;
+use crate::tasks::{AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, ProgressState};
+
+const COMPARE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Maps the `(vault, path)` of each moved item to its new location.
+type MovedItems = HashMap<(String, String), (String, String)>;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MergeParams {
+    pub source_vault_name: String,
+    pub existing_behaviour: DestinationExistingBehaviour,
+    pub conflict_policy: FieldConflictPolicy,
+}
+
+fn definitions_of(vault: &Vault) -> HashMap<Uuid, FieldDefinition> {
+    vault
+        .iter_field_defs()
+        .map(|def| (def.id, def.clone()))
+        .collect()
+}
+
+async fn move_file(from: &Path, to: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = to.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    // renaming does not work across file systems
+    if tokio::fs::rename(from, to).await.is_err() {
+        tokio::fs::copy(from, to).await?;
+        tokio::fs::remove_file(from).await?;
+    }
+    Ok(())
+}
+
+/// Whether the two files have exactly the same contents.
+fn same_contents(a: &Path, b: &Path) -> std::io::Result<bool> {
+    if std::fs::metadata(a)?.len() != std::fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+    let (mut a, mut b) = (File::open(a)?, File::open(b)?);
+    let (mut buf_a, mut buf_b) = (vec![0; COMPARE_CHUNK_SIZE], vec![0; COMPARE_CHUNK_SIZE]);
+    loop {
+        let n = a.read(&mut buf_a)?;
+        if n == 0 {
+            return Ok(true);
+        }
+        b.read_exact(&mut buf_b[..n])?;
+        if buf_a[..n] != buf_b[..n] {
+            return Ok(false);
+        }
+    }
+}
+
+fn record_move(
+    moved: &mut MovedItems,
+    source: &Vault,
+    item: &Item,
+    target: &Vault,
+    new_item: &Item,
+) {
+    moved.insert(
+        (source.name.clone(), item.path().to_string()),
+        (target.name.clone(), new_item.path().to_string()),
+    );
+}
+
+async fn merge_item(
+    source: &Vault,
+    target: &Vault,
+    item: &Item,
+    params: &MergeParams,
+    moved: &mut MovedItems,
+) -> anyhow::Result<TransformResult> {
+    let old_abs_path = source.resolve_abs_path(Path::new(item.path()))?;
+    let mut new_path = PathBuf::from(item.path());
+    let mut new_abs_path = target.resolve_abs_path(&new_path)?;
+    // both vaults may share a root directory, in which case only the metadata is merged
+    let same_file = new_abs_path == old_abs_path;
+
+    // an item that already exists in the target is merged into, unless its file is a different
+    // one, in which case the incoming item is given a path of its own
+    let files_differ = !same_file
+        && tokio::fs::try_exists(&new_abs_path).await?
+        && !block_in_place(|| same_contents(&old_abs_path, &new_abs_path))?;
+
+    if files_differ
+        && params.existing_behaviour == DestinationExistingBehaviour::AppendDiscriminator
+    {
+        let Some(disc_path) = Discriminator::from_path(&new_path).and_then(|d| {
+            d.into_unique_path(|p| {
+                let abs_path = target.resolve_abs_path(p).ok()?;
+                Some(target.get_item(p).is_err() && !abs_path.exists())
+            })
+        }) else {
+            return Ok(TransformResult::NoTransform(old_abs_path));
+        };
+        new_path = disc_path;
+        new_abs_path = target.resolve_abs_path(&new_path)?;
+    }
+
+    let result = if same_file {
+        TransformResult::InPlaceTransform(new_abs_path, None)
+    } else if tokio::fs::try_exists(&new_abs_path).await? {
+        match params.existing_behaviour {
+            DestinationExistingBehaviour::Skip => {
+                return Ok(TransformResult::NoTransform(old_abs_path));
+            }
+            // for AppendDiscriminator, the file can only exist here if it is identical
+            DestinationExistingBehaviour::Remove
+            | DestinationExistingBehaviour::AppendDiscriminator => {
+                tokio::fs::remove_file(&old_abs_path).await?;
+                TransformResult::RemovedWithoutTransform(old_abs_path)
+            }
+            DestinationExistingBehaviour::Overwrite => {
+                move_file(&old_abs_path, &new_abs_path).await?;
+                TransformResult::MoveSuccess {
+                    removed: old_abs_path,
+                    created: new_abs_path,
+                    encoded: None,
+                }
+            }
+        }
+    } else {
+        move_file(&old_abs_path, &new_abs_path).await?;
+        TransformResult::MoveSuccess {
+            removed: old_abs_path,
+            created: new_abs_path,
+            encoded: None,
+        }
+    };
+
+    let new_item = target.get_item_or_init(&new_path)?;
+    new_item.merge(item, params.conflict_policy);
+    source.remove_item(Path::new(item.path()))?;
+    record_move(moved, source, item, target, &new_item);
+
+    Ok(result)
+}
+
+async fn split_item(
+    source: &Vault,
+    target: &Vault,
+    item: &Item,
+    moved: &mut MovedItems,
+) -> anyhow::Result<TransformResult> {
+    let old_abs_path = source.resolve_abs_path(Path::new(item.path()))?;
+    let root_dir = target.root_dir()?;
+
+    let (new_abs_path, result) = if old_abs_path.starts_with(&root_dir) {
+        (
+            old_abs_path.clone(),
+            TransformResult::InPlaceTransform(old_abs_path, None),
+        )
+    } else {
+        let new_abs_path = root_dir.join(item.path());
+        if tokio::fs::try_exists(&new_abs_path).await? {
+            return Ok(TransformResult::NoTransform(old_abs_path));
+        }
+        move_file(&old_abs_path, &new_abs_path).await?;
+        (
+            new_abs_path.clone(),
+            TransformResult::MoveSuccess {
+                removed: old_abs_path,
+                created: new_abs_path,
+                encoded: None,
+            },
+        )
+    };
+
+    let new_item = target.get_item_or_init(&new_abs_path)?;
+    new_item.update(item);
+    source.remove_item(Path::new(item.path()))?;
+    record_move(moved, source, item, target, &new_item);
+
+    Ok(result)
+}
+
+/// Rewrites references to the moved items in every loaded vault, then saves the source, the
+/// target and any other vault that was changed.
+async fn finish_move(
+    state: &AppStateRef,
+    source: Arc<Vault>,
+    target: Arc<Vault>,
+    moved: &MovedItems,
+    progress: ProgressSenderRef,
+) -> anyhow::Result<()> {
+    let mut changed_vaults = vec![];
+    for name in state.valid_vault_names() {
+        let Ok(vault) = state.get_vault(&name) else {
+            continue;
+        };
+        if vault.rewrite_item_refs(moved) > 0 && name != source.name && name != target.name {
+            changed_vaults.push(vault);
+        }
+    }
+
+    for (_, new_path) in moved.values() {
+        let item = target.get_item(Path::new(new_path))?;
+        target.apply_implications(&item)?;
+        target.tag_stats().update_item(&target, &item);
+    }
+
+    let vaults = [source, target]
+        .into_iter()
+        .chain(changed_vaults)
+        .collect_vec();
+    #[allow(clippy::cast_precision_loss)]
+    let weight = 1.0 / vaults.len() as f32;
+    for vault in vaults {
+        let task_name = format!("Save {} vault", vault.name);
+        save_vault(vault, progress.sub_task(&task_name, weight)).await?;
+    }
+    state.refresh_unresolved_vaults();
+
+    Ok(())
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn send_item_progress(progress: &ProgressSenderRef, i: usize, total: usize, path: &Path) {
+    progress.send(ProgressState::DeterminateWithMessage(
+        i as f32 / total as f32,
+        path.display().to_string(),
+    ));
+}
+
+/// Moves all items and their files from the source vault into `target`, combining definitions
+/// by UUID and merging the fields of items that exist in both.
+#[tracing::instrument]
+pub async fn merge_vaults(
+    state: AppStateRef,
+    target: Arc<Vault>,
+    params: MergeParams,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    let source = state.get_vault(&params.source_vault_name)?;
+    if source.name == target.name {
+        return Err(AppError::CannotMergeVaultIntoItself {
+            name: source.name.clone(),
+        }
+        .into());
+    }
+
+    let definitions = definitions_of(&source);
+    let ids = definitions.keys().copied().collect();
+    DefinitionImport::plan_ids(&definitions, ids, &target, false)?.apply(&target);
+
+    let items = source
+        .iter_items()
+        .map(|item| Arc::clone(item.value()))
+        .collect_vec();
+    let mut moved = MovedItems::new();
+    let mut results = Vec::with_capacity(items.len());
+    let item_progress = progress.sub_task("Merge items", 0.9);
+    for (i, item) in items.iter().enumerate() {
+        if item_progress.is_cancelled() {
+            break;
+        }
+        let path = source.resolve_abs_path(Path::new(item.path()))?;
+        send_item_progress(&item_progress, i, items.len(), &path);
+        results.push(
+            merge_item(&source, &target, item, &params, &mut moved)
+                .await
+                .with_context(|| PathContext(path)),
+        );
+    }
+
+    finish_move(
+        &state,
+        source,
+        target,
+        &moved,
+        progress.sub_task("Save", 0.1),
+    )
+    .await?;
+
+    Ok(AsyncTaskResult::TransformationComplete {
+        cancelled: items.len() - results.len(),
+        results,
+    })
+}
+
+/// Returns the IDs of the fields used by `items`, along with all of their ancestors.
+fn used_definition_ids(vault: &Vault, items: &[Arc<Item>]) -> HashSet<Uuid> {
+    let field_ids: HashSet<Uuid> = items
+        .iter()
+        .flat_map(|item| item.iter_fields().map(|f| *f.key()).collect_vec())
+        .collect();
+    field_ids
+        .iter()
+        .flat_map(|id| vault.iter_field_ancestor_paths(id))
+        .flatten()
+        .collect()
+}
+
+/// Moves the items of `source` that match `filter` into a new vault, along with the definitions
+/// they need. Files outside the new vault's directory are moved into it.
+#[tracing::instrument]
+pub async fn split_vault(
+    state: AppStateRef,
+    source: Arc<Vault>,
+    filter: FilterExpression,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    let dialog = rfd::AsyncFileDialog::new()
+        .add_filter("riiman vault file", &["riiman"])
+        .set_file_name(format!("{}-split.riiman", source.name));
+
+    let fp = dialog.save_file().await.ok_or(AppError::UserCancelled)?;
+    let target = Vault::new(String::new()).with_file_path(fp.path());
+    if state.get_vault(&target.name).is_ok() {
+        return Err(AppError::VaultAlreadyExists { name: target.name }.into());
+    }
+
+    let items = block_in_place(|| -> anyhow::Result<Vec<_>> {
+        let mut items = vec![];
+        for item in source.iter_items() {
+            if evaluate_filter(&item, &source, &filter)? {
+                items.push(Arc::clone(item.value()));
+            }
+        }
+        Ok(items)
+    })?;
+
+    let ids = used_definition_ids(&source, &items);
+    DefinitionImport::plan_ids(&definitions_of(&source), ids, &target, false)?.apply(&target);
+
+    let name = target.name.clone();
+    state.load_vault(target, false);
+    let target = state.get_vault(&name)?;
+
+    let mut moved = MovedItems::new();
+    let mut results = Vec::with_capacity(items.len());
+    let item_progress = progress.sub_task("Split items", 0.9);
+    for (i, item) in items.iter().enumerate() {
+        if item_progress.is_cancelled() {
+            break;
+        }
+        let path = source.resolve_abs_path(Path::new(item.path()))?;
+        send_item_progress(&item_progress, i, items.len(), &path);
+        results.push(
+            split_item(&source, &target, item, &mut moved)
+                .await
+                .with_context(|| PathContext(path)),
+        );
+    }
+
+    finish_move(
+        &state,
+        source,
+        target,
+        &moved,
+        progress.sub_task("Save", 0.1),
+    )
+    .await?;
+
+    Ok(AsyncTaskResult::TransformationComplete {
+        cancelled: items.len() - results.len(),
+        results,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::FieldValue;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_merge_item_collisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let (source_dir, target_dir) = (dir.path().join("source"), dir.path().join("target"));
+        std::fs::create_dir_all(&source_dir).unwrap();
+        std::fs::create_dir_all(&target_dir).unwrap();
+        let source = Vault::new("source".to_string()).with_file_path(&source_dir.join("s.riiman"));
+        let target = Vault::new("target".to_string()).with_file_path(&target_dir.join("t.riiman"));
+
+        let (old_tag, new_tag) = (Uuid::new_v4(), Uuid::new_v4());
+        for (path, source_contents, target_contents) in
+            [("same.png", "same", "same"), ("other.png", "old", "new")]
+        {
+            std::fs::write(source_dir.join(path), source_contents).unwrap();
+            std::fs::write(target_dir.join(path), target_contents).unwrap();
+            let item = source.get_item_or_init(Path::new(path)).unwrap();
+            item.set_field_value(new_tag, FieldValue::Tag);
+            let existing = target.get_item_or_init(Path::new(path)).unwrap();
+            existing.set_field_value(old_tag, FieldValue::Tag);
+        }
+
+        let params = MergeParams {
+            source_vault_name: source.name.clone(),
+            existing_behaviour: DestinationExistingBehaviour::AppendDiscriminator,
+            conflict_policy: FieldConflictPolicy::KeepTarget,
+        };
+        let mut moved = MovedItems::new();
+        for path in ["same.png", "other.png"] {
+            let item = source.get_item(Path::new(path)).unwrap();
+            merge_item(&source, &target, &item, &params, &mut moved)
+                .await
+                .unwrap();
+        }
+
+        // identical files are merged into the existing item
+        let same = target.get_item(Path::new("same.png")).unwrap();
+        assert!(same.has_field(&old_tag) && same.has_field(&new_tag));
+        assert!(!source_dir.join("same.png").exists());
+        assert!(target.get_item(Path::new("same (2).png")).is_err());
+
+        // different files are kept side by side
+        let other = target.get_item(Path::new("other.png")).unwrap();
+        assert!(!other.has_field(&new_tag));
+        let incoming = target.get_item(Path::new("other (2).png")).unwrap();
+        assert!(incoming.has_field(&new_tag) && !incoming.has_field(&old_tag));
+        assert_eq!(
+            std::fs::read_to_string(target_dir.join("other (2).png")).unwrap(),
+            "old"
+        );
+        assert_eq!(source.len_items(), 0);
+    }
+}
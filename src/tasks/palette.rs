@@ -0,0 +1,134 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context};
+use magick_rust::{ColorspaceType, DitherMethod, MagickWand, PixelWand};
+use tokio::task::block_in_place;
+
+use crate::data::{FieldStore, FieldValue, Item, SerialColour, ThumbnailParams, Vault};
+use crate::fields;
+use crate::state::THUMBNAIL_LOW_QUALITY_HEIGHT;
+use crate::tasks::import::{on_import_result_send_progress, process_many};
+use crate::tasks::thumbnail::read_cached_thumbnail;
+use crate::tasks::transform::PathContext;
+use crate::tasks::vault::save_vault;
+use crate::tasks::{AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef};
+
+const PALETTE_SIZE: usize = 5;
+const CONCURRENT_TASKS_LIMIT: usize = 16;
+
+/// Reduces the image to a few colours and returns them from most to least common, along with the
+/// share of the image that each one covers.
+#[allow(clippy::cast_precision_loss)]
+#[allow(clippy::cast_sign_loss)]
+#[allow(clippy::cast_possible_truncation)]
+pub fn extract_palette(wand: &MagickWand) -> anyhow::Result<Vec<(SerialColour, f64)>> {
+    let wand = MagickWand::new_from_image(&wand.get_image()?)?;
+    wand.quantize_image(
+        PALETTE_SIZE,
+        ColorspaceType::Lab,
+        0,
+        DitherMethod::No,
+        false,
+    )?;
+    let histogram = wand
+        .get_image_histogram()
+        .ok_or_else(|| anyhow!("failed to get colour histogram"))?;
+
+    let total = histogram
+        .iter()
+        .map(PixelWand::get_color_count)
+        .sum::<usize>()
+        .max(1) as f64;
+    let channel = |c: f64| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    let mut palette: Vec<_> = histogram
+        .iter()
+        .map(|px| {
+            let colour = [
+                channel(px.get_red()),
+                channel(px.get_green()),
+                channel(px.get_blue()),
+            ];
+            (colour.into(), px.get_color_count() as f64 / total)
+        })
+        .collect();
+    palette.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    Ok(palette)
+}
+
+pub fn set_item_palette(item: &Item, palette: &[(SerialColour, f64)]) {
+    item.set_known_field_value(
+        fields::image::PALETTE,
+        palette
+            .iter()
+            .map(|(colour, _)| FieldValue::colour(*colour))
+            .collect(),
+    );
+    item.set_known_field_value(
+        fields::image::PALETTE_WEIGHTS,
+        palette
+            .iter()
+            .map(|(_, weight)| FieldValue::float((*weight).into()))
+            .collect(),
+    );
+}
+
+/// Extracts the palette of an item from its low quality thumbnail.
+pub fn update_item_palette(vault: &Vault, item: &Item) -> anyhow::Result<()> {
+    let params = ThumbnailParams {
+        abs_path: vault.resolve_abs_path(Path::new(item.path()))?,
+        rel_path: item.path().to_string(),
+        last_modified: item.get_known_field_value(fields::general::LAST_MODIFIED)?,
+        height: THUMBNAIL_LOW_QUALITY_HEIGHT,
+        transform_params: None,
+    };
+    let wand = read_cached_thumbnail(&params)?;
+    set_item_palette(item, &extract_palette(&wand)?);
+    Ok(())
+}
+
+/// Extracts the palettes of images that were imported before palettes were introduced.
+#[tracing::instrument]
+pub async fn extract_missing_palettes(
+    vault: Arc<Vault>,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    let paths: Vec<Box<Path>> = vault
+        .iter_items()
+        .filter(|item| !item.has_field(&fields::image::PALETTE.id))
+        .filter(|item| {
+            item.get_known_field_value(fields::general::MEDIA_TYPE)
+                .ok()
+                .flatten()
+                .is_some_and(|t| t.starts_with("image/"))
+        })
+        .map(|item| Path::new(item.path()).into())
+        .collect();
+
+    let results = process_many(
+        paths,
+        progress.sub_task("Extract", 0.95),
+        |path| {
+            let vault = Arc::clone(&vault);
+            async move {
+                let context = PathContext(path.to_path_buf());
+                vault
+                    .get_item(&path)
+                    .and_then(|item| block_in_place(|| update_item_palette(&vault, &item)))
+                    .map(|()| path)
+                    .with_context(|| context)
+            }
+        },
+        on_import_result_send_progress,
+        CONCURRENT_TASKS_LIMIT,
+    )
+    .await?;
+
+    save_vault(vault, progress.sub_task("Save", 0.05)).await?;
+
+    Ok(AsyncTaskResult::BatchComplete {
+        title: "Colour palettes extracted".to_string(),
+        results,
+    })
+}
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use poll_promise::Promise;
+
+use crate::data::transform::{PresetParams, SourceKind};
+use crate::data::{ItemId, TransformPreset, Vault};
+use crate::errors::AppError;
+use crate::state::AppStateRef;
+use crate::tasks::transform::{apply_image_transformations, apply_path_transformations};
+use crate::tasks::{AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, ProgressState};
+
+const PRESETS_FILTER_NAME: &str = "riiman transform presets";
+const PRESETS_EXTENSION: &str = "riimanpresets";
+
+/// The items a preset applies to when it is run by itself, according to its source options.
+pub fn preset_source_ids(
+    state: &AppStateRef,
+    vault: &Vault,
+    preset: &TransformPreset,
+) -> Vec<ItemId> {
+    match preset.params.bulk().source.kind {
+        SourceKind::Selection => state.selected_item_ids(),
+        SourceKind::Filtered => state.item_list_ids(),
+        SourceKind::All => vault
+            .iter_items()
+            .map(|item| ItemId::from_item(vault, &item))
+            .collect(),
+    }
+}
+
+/// Runs a preset on the given items without going through the transform window.
+pub fn run_preset(
+    state: &AppStateRef,
+    vault: Arc<Vault>,
+    item_ids: Vec<ItemId>,
+    preset: TransformPreset,
+) {
+    let name = format!("Run preset {}", preset.name);
+    let vault_name = Some(vault.name.clone());
+    match preset.params {
+        PresetParams::Image { bulk, params } => {
+            state.add_vault_task(vault_name, name, move |s, p| {
+                Promise::spawn_async(apply_image_transformations(
+                    s, vault, item_ids, bulk, params, p,
+                ))
+            });
+        }
+        PresetParams::Path { bulk, params } => {
+            state.add_vault_task(vault_name, name, move |s, p| {
+                Promise::spawn_async(apply_path_transformations(
+                    s, vault, item_ids, bulk, params, p,
+                ))
+            });
+        }
+    }
+}
+
+#[tracing::instrument]
+pub async fn choose_and_import_presets(
+    state: AppStateRef,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    let dialog = rfd::AsyncFileDialog::new().add_filter(PRESETS_FILTER_NAME, &[PRESETS_EXTENSION]);
+
+    let fp = dialog.pick_file().await.ok_or(AppError::UserCancelled)?;
+    let path = fp.path().to_path_buf();
+
+    progress.send(ProgressState::Determinate(0.5));
+
+    let contents = tokio::fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("while reading from presets file at {}", path.display()))?;
+    let presets = serde_json::from_str(&contents)
+        .map(TransformPreset::from_values)
+        .with_context(|| format!("while deserialising presets file at {}", path.display()))?;
+
+    for preset in presets {
+        state.set_transform_preset(preset.upgrade());
+    }
+
+    Ok(AsyncTaskResult::None)
+}
+
+#[tracing::instrument]
+pub async fn export_presets(
+    presets: Vec<TransformPreset>,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    let dialog = rfd::AsyncFileDialog::new()
+        .add_filter(PRESETS_FILTER_NAME, &[PRESETS_EXTENSION])
+        .set_file_name(format!("presets.{PRESETS_EXTENSION}"));
+
+    let fp = dialog.save_file().await.ok_or(AppError::UserCancelled)?;
+    let path = fp.path().to_path_buf();
+
+    let data = serde_json::to_vec_pretty(&presets)?;
+
+    progress.send(ProgressState::Determinate(0.5));
+
+    tokio::fs::write(&path, data)
+        .await
+        .with_context(|| format!("while writing to presets file at {}", path.display()))?;
+
+    Ok(AsyncTaskResult::None)
+}
@@ -1,14 +1,88 @@
+use crate::errors::AppError;
 use crate::tasks::ProgressState;
 use dashmap::DashMap;
 use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use tokio::sync::Notify;
 
 pub type ProgressReceiver = tokio::sync::watch::Receiver<ProgressState>;
 
+/// Allows the user to request that a running task stops. Tasks check the token between units
+/// of work so that they can stop at a point where the vault is left consistent.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+    parent: Option<Arc<CancellationToken>>,
+}
+
+impl CancellationToken {
+    /// Creates a token that is cancelled along with this one, but can also be cancelled on
+    /// its own without affecting this one.
+    pub fn child(&self) -> Self {
+        Self {
+            parent: Some(Arc::new(self.clone())),
+            ..Default::default()
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+            || self.parent.as_ref().is_some_and(|p| p.is_cancelled())
+    }
+
+    /// Returns an error if the task has been cancelled.
+    pub fn check(&self) -> Result<(), AppError> {
+        if self.is_cancelled() {
+            Err(AppError::UserCancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Completes once the task has been cancelled.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            match &self.parent {
+                Some(parent) => {
+                    tokio::select! {
+                        () = notified => {}
+                        () = Box::pin(parent.cancelled()) => {}
+                    }
+                }
+                None => notified.await,
+            }
+        }
+    }
+}
+
 pub trait ProgressSender: Send + Sync {
     fn send(&self, state: ProgressState);
     fn sub_task(&self, name: &str, weight: f32) -> ProgressSenderRef;
     fn receive_sub_task_progress(&self, name: &str, state: ProgressState);
+    fn cancellation(&self) -> CancellationToken;
+    /// Like [`ProgressSender::sub_task`], but the sub-task and its descendants observe
+    /// `cancellation` instead of the token of this task.
+    fn sub_task_with_cancellation(
+        &self,
+        name: &str,
+        weight: f32,
+        cancellation: CancellationToken,
+    ) -> ProgressSenderRef;
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation().is_cancelled()
+    }
 }
 
 pub type ProgressSenderRef = Box<dyn ProgressSender>;
@@ -28,6 +102,7 @@ pub struct ProgressSenderAsync {
     name: String,
     tx: tokio::sync::watch::Sender<ProgressState>,
     sub_tasks: DashMap<String, SubTaskProgress>,
+    cancellation: CancellationToken,
 }
 
 fn compute_progress(sub_tasks: &DashMap<String, SubTaskProgress>) -> ProgressState {
@@ -55,11 +130,16 @@ fn compute_progress(sub_tasks: &DashMap<String, SubTaskProgress>) -> ProgressSta
 #[allow(clippy::unnecessary_box_returns)]
 #[allow(clippy::redundant_allocation)]
 impl ProgressSenderAsync {
-    pub fn new(name: String, tx: tokio::sync::watch::Sender<ProgressState>) -> Box<Arc<Self>> {
+    pub fn new(
+        name: String,
+        tx: tokio::sync::watch::Sender<ProgressState>,
+        cancellation: CancellationToken,
+    ) -> Box<Arc<Self>> {
         Arc::new(Self {
             name,
             tx,
             sub_tasks: DashMap::new(),
+            cancellation,
         })
         .into()
     }
@@ -83,16 +163,37 @@ impl ProgressSender for Arc<ProgressSenderAsync> {
             name,
         )))
     }
+    fn sub_task_with_cancellation(
+        &self,
+        name: &str,
+        weight: f32,
+        cancellation: CancellationToken,
+    ) -> ProgressSenderRef {
+        self.sub_tasks.insert(
+            name.to_string(),
+            SubTaskProgress {
+                weight,
+                state: ProgressState::NotStarted,
+            },
+        );
+        let mut sub_task = ProgressSenderSubTask::new(Box::new(self.clone()), name);
+        sub_task.cancellation = Some(cancellation);
+        Box::new(Arc::new(sub_task))
+    }
     fn receive_sub_task_progress(&self, name: &str, state: ProgressState) {
         self.sub_tasks.get_mut(name).unwrap().state = state;
         self.send(compute_progress(&self.sub_tasks));
     }
+    fn cancellation(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
 }
 
 struct ProgressSenderSubTask {
     parent: Box<dyn ProgressSender>,
     name: String,
     sub_tasks: DashMap<String, SubTaskProgress>,
+    cancellation: Option<CancellationToken>,
 }
 
 impl ProgressSenderSubTask {
@@ -101,6 +202,7 @@ impl ProgressSenderSubTask {
             parent,
             name: name.to_string(),
             sub_tasks: DashMap::new(),
+            cancellation: None,
         }
     }
 }
@@ -124,10 +226,32 @@ impl ProgressSender for Arc<ProgressSenderSubTask> {
             name,
         )))
     }
+    fn sub_task_with_cancellation(
+        &self,
+        name: &str,
+        weight: f32,
+        cancellation: CancellationToken,
+    ) -> ProgressSenderRef {
+        self.sub_tasks.insert(
+            name.to_string(),
+            SubTaskProgress {
+                weight,
+                state: ProgressState::NotStarted,
+            },
+        );
+        let mut sub_task = ProgressSenderSubTask::new(Box::new(self.clone()), name);
+        sub_task.cancellation = Some(cancellation);
+        Box::new(Arc::new(sub_task))
+    }
     fn receive_sub_task_progress(&self, name: &str, state: ProgressState) {
         self.sub_tasks.get_mut(name).unwrap().state = state;
         self.send(compute_progress(&self.sub_tasks));
     }
+    fn cancellation(&self) -> CancellationToken {
+        self.cancellation
+            .clone()
+            .unwrap_or_else(|| self.parent.cancellation())
+    }
 }
 
 pub struct DummyProgressSender;
@@ -146,4 +270,15 @@ impl ProgressSender for Arc<DummyProgressSender> {
         Box::new(self.clone())
     }
     fn receive_sub_task_progress(&self, _name: &str, _state: ProgressState) {}
+    fn cancellation(&self) -> CancellationToken {
+        CancellationToken::default()
+    }
+    fn sub_task_with_cancellation(
+        &self,
+        _name: &str,
+        _weight: f32,
+        _cancellation: CancellationToken,
+    ) -> ProgressSenderRef {
+        Box::new(self.clone())
+    }
 }
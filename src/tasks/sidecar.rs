@@ -0,0 +1,256 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use itertools::Itertools;
+use serde_json::{Map, Value};
+use strum::Display;
+
+use crate::data::{kind, FieldStore, FieldValue, Item, KnownField};
+use crate::fields;
+
+type Dom = Map<String, Value>;
+
+/// Describes how the metadata that gallery-dl writes for a site is mapped onto item fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum SidecarProfile {
+    Twitter,
+    Pixiv,
+    Danbooru,
+    Reddit,
+    Bluesky,
+}
+
+fn get<'a>(dom: &'a Dom, path: &[&str]) -> Option<&'a Value> {
+    let (first, rest) = path.split_first()?;
+    rest.iter().try_fold(dom.get(*first)?, |v, key| v.get(key))
+}
+
+/// Returns the value as a string, also accepting numbers as some sites use numeric IDs.
+fn get_string(dom: &Dom, path: &[&str]) -> Option<String> {
+    match get(dom, path)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn get_date(dom: &Dom, key: &str) -> Option<DateTime<Utc>> {
+    let date = dom.get(key)?.as_str()?;
+    NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|d| d.and_utc())
+}
+
+fn get_string_list(dom: &Dom, key: &str) -> Option<Vec<FieldValue>> {
+    let list = dom
+        .get(key)?
+        .as_array()?
+        .iter()
+        .filter_map(|v| v.as_str().or_else(|| v.get("name")?.as_str()))
+        .map(|s| FieldValue::string(s.to_string().into()))
+        .collect_vec();
+    (!list.is_empty()).then_some(list)
+}
+
+fn set_string(item: &Item, field: KnownField<kind::String>, value: Option<String>) {
+    if let Some(value) = value {
+        item.set_known_field_value(field, value.into());
+    }
+}
+
+impl SidecarProfile {
+    pub fn from_category(category: &str) -> Option<Self> {
+        match category {
+            "twitter" => Some(Self::Twitter),
+            "pixiv" => Some(Self::Pixiv),
+            "danbooru" => Some(Self::Danbooru),
+            "reddit" => Some(Self::Reddit),
+            "bluesky" => Some(Self::Bluesky),
+            _ => None,
+        }
+    }
+
+    /// Detects the profile from the extractor category recorded by gallery-dl. Sidecars written
+    /// without a category are assumed to come from Twitter if they have a tweet ID.
+    pub fn detect(dom: &Dom) -> Option<Self> {
+        dom.get("category")
+            .and_then(|c| c.as_str())
+            .and_then(Self::from_category)
+            .or_else(|| dom.contains_key("tweet_id").then_some(Self::Twitter))
+    }
+
+    /// Sets the fields shared by all non-Twitter profiles, and returns the post ID.
+    fn apply_common(self, item: &Item, dom: &Dom, id_key: &str) -> Option<String> {
+        item.set_known_field_value(fields::post::SITE, self.to_string().into());
+        if let Some(num) = dom.get("num").and_then(|n| n.as_i64()) {
+            item.set_known_field_value(fields::post::IMAGE_NUMBER, num);
+        }
+        if let Some(date) = get_date(dom, "date") {
+            item.set_known_field_value(fields::post::POST_DATE, date);
+        }
+
+        let id = get_string(dom, &[id_key]);
+        set_string(item, fields::post::ID, id.clone());
+        id
+    }
+
+    pub fn apply(self, item: &Item, dom: &Dom) {
+        match self {
+            Self::Twitter => apply_twitter(item, dom),
+            Self::Pixiv => {
+                let id = self.apply_common(item, dom, "id");
+                set_string(item, fields::post::TITLE, get_string(dom, &["title"]));
+                set_string(item, fields::post::CONTENT, get_string(dom, &["caption"]));
+                set_string(
+                    item,
+                    fields::post::AUTHOR_ID,
+                    get_string(dom, &["user", "id"]),
+                );
+                set_string(
+                    item,
+                    fields::post::AUTHOR_HANDLE,
+                    get_string(dom, &["user", "account"]),
+                );
+                set_string(
+                    item,
+                    fields::post::AUTHOR_NAME,
+                    get_string(dom, &["user", "name"]),
+                );
+                if let Some(tags) = get_string_list(dom, "tags") {
+                    item.set_known_field_value(fields::post::TAGS, tags);
+                }
+                let url = id.map(|id| format!("https://www.pixiv.net/artworks/{id}"));
+                set_string(item, fields::post::URL, url);
+            }
+            Self::Danbooru => {
+                let id = self.apply_common(item, dom, "id");
+                set_string(
+                    item,
+                    fields::post::AUTHOR_NAME,
+                    get_string(dom, &["tag_string_artist"]),
+                );
+                if let Some(tags) = get_string(dom, &["tag_string"]) {
+                    let tags = tags
+                        .split_whitespace()
+                        .map(|t| FieldValue::string(t.to_string().into()))
+                        .collect_vec();
+                    item.set_known_field_value(fields::post::TAGS, tags);
+                }
+                let url = id.map(|id| format!("https://danbooru.donmai.us/posts/{id}"));
+                set_string(item, fields::post::URL, url);
+            }
+            Self::Reddit => {
+                self.apply_common(item, dom, "id");
+                set_string(item, fields::post::TITLE, get_string(dom, &["title"]));
+                set_string(item, fields::post::CONTENT, get_string(dom, &["selftext"]));
+                set_string(
+                    item,
+                    fields::post::AUTHOR_HANDLE,
+                    get_string(dom, &["author"]),
+                );
+                let url = get_string(dom, &["permalink"])
+                    .map(|link| format!("https://www.reddit.com{link}"));
+                set_string(item, fields::post::URL, url);
+            }
+            Self::Bluesky => {
+                let id = self.apply_common(item, dom, "post_id");
+                set_string(item, fields::post::CONTENT, get_string(dom, &["text"]));
+                set_string(
+                    item,
+                    fields::post::AUTHOR_ID,
+                    get_string(dom, &["author", "did"]),
+                );
+                let handle = get_string(dom, &["author", "handle"]);
+                set_string(item, fields::post::AUTHOR_HANDLE, handle.clone());
+                set_string(
+                    item,
+                    fields::post::AUTHOR_NAME,
+                    get_string(dom, &["author", "displayName"]),
+                );
+                if let Some(tags) = get_string_list(dom, "hashtags") {
+                    item.set_known_field_value(fields::post::TAGS, tags);
+                }
+                let url = handle
+                    .zip(id)
+                    .map(|(handle, id)| format!("https://bsky.app/profile/{handle}/post/{id}"));
+                set_string(item, fields::post::URL, url);
+            }
+        }
+    }
+}
+
+fn apply_twitter(item: &Item, dom: &Dom) {
+    if let Some(tweet_id) = dom.get("tweet_id").and_then(|tid| tid.as_i64()) {
+        item.set_known_field_value(fields::tweet::ID, tweet_id);
+    }
+
+    if let Some(content) = dom.get("content").and_then(|c| c.as_str()) {
+        item.set_known_field_value(fields::tweet::CONTENT, content.to_string().into());
+    }
+
+    if let Some(num) = dom.get("num").and_then(|c| c.as_i64()) {
+        item.set_known_field_value(fields::tweet::IMAGE_NUMBER, num);
+    }
+
+    if let Some(hashtags) = get_string_list(dom, "hashtags") {
+        item.set_known_field_value(fields::tweet::HASHTAGS, hashtags);
+    }
+
+    let author = dom.get("author").and_then(|a| a.as_object());
+
+    if let Some(author_id) = author.and_then(|a| a.get("id")).and_then(|id| id.as_i64()) {
+        item.set_known_field_value(fields::tweet::AUTHOR_ID, author_id);
+    }
+
+    if let Some(author_handle) = author.and_then(|a| a.get("name")).and_then(|n| n.as_str()) {
+        item.set_known_field_value(
+            fields::tweet::AUTHOR_HANDLE,
+            author_handle.to_string().into(),
+        );
+    }
+
+    if let Some(author_name) = author.and_then(|a| a.get("nick")).and_then(|n| n.as_str()) {
+        item.set_known_field_value(fields::tweet::AUTHOR_NAME, author_name.to_string().into());
+    }
+
+    if let Some(tweet_date) = get_date(dom, "date") {
+        item.set_known_field_value(fields::tweet::POST_DATE, tweet_date);
+    }
+
+    if let Some(liked_date) = get_date(dom, "date_liked") {
+        item.set_known_field_value(fields::tweet::LIKED_DATE, liked_date);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bluesky_profile() {
+        let sidecar = serde_json::json!({
+            "category": "bluesky",
+            "post_id": "3kabc",
+            "text": "hello",
+            "author": { "did": "did:plc:xyz", "handle": "alice.bsky.social" },
+            "date": "2024-05-01 12:00:00",
+            "num": 2
+        });
+        let dom = sidecar.as_object().unwrap();
+        let item = Item::new("a.jpg".to_string());
+
+        let profile = SidecarProfile::detect(dom).unwrap();
+        assert_eq!(profile, SidecarProfile::Bluesky);
+        profile.apply(&item, dom);
+
+        assert_eq!(
+            item.get_known_field_value(fields::post::URL)
+                .unwrap()
+                .map(|s| s.to_string()),
+            Some("https://bsky.app/profile/alice.bsky.social/post/3kabc".to_string())
+        );
+        assert_eq!(
+            item.get_known_field_value(fields::post::IMAGE_NUMBER)
+                .unwrap(),
+            Some(2)
+        );
+    }
+}
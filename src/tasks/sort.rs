@@ -49,12 +49,20 @@ pub enum SortType {
     #[default]
     Path,
     Field,
+    Rating,
+    Favourite,
 }
 
 impl From<SortExpression> for SortType {
     fn from(value: SortExpression) -> Self {
         match value {
             SortExpression::Path(_) => SortType::Path,
+            SortExpression::Field(id, _) if id == crate::fields::general::RATING.id => {
+                SortType::Rating
+            }
+            SortExpression::Field(id, _) if id == crate::fields::general::FAVOURITE.id => {
+                SortType::Favourite
+            }
             SortExpression::Field(_, _) => SortType::Field,
         }
     }
@@ -0,0 +1,382 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::{DateTime, Datelike, Local, Months, NaiveDate, Utc};
+use itertools::Itertools;
+use uuid::Uuid;
+
+use crate::data::{kind, tag_stats, FieldDefinition, FieldStore, FieldType, Item, Vault};
+use crate::fields;
+use crate::tasks::{AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, ProgressState};
+
+const MAX_BUCKETS: usize = 20;
+const MAX_FILTER_TERMS: usize = 16;
+
+type Resolution = (i64, i64);
+
+/// A single bar of a chart, along with the search text that selects the items it represents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsBucket {
+    pub label: String,
+    pub value: u64,
+    pub search_text: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagCount {
+    pub id: Uuid,
+    pub direct: usize,
+    pub inherited: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VaultStats {
+    pub vault_name: String,
+    pub total_items: usize,
+    pub total_bytes: u64,
+    pub tags: Vec<TagCount>,
+    pub unused_tag_ids: Vec<Uuid>,
+    pub media_types: Vec<StatsBucket>,
+    pub resolutions: Vec<StatsBucket>,
+    pub aspect_ratios: Vec<StatsBucket>,
+    pub folder_sizes: Vec<StatsBucket>,
+    pub links: Vec<StatsBucket>,
+    pub last_modified: Vec<StatsBucket>,
+    pub post_date: Vec<StatsBucket>,
+}
+
+fn quote(s: &str) -> String {
+    format!("{s:?}")
+}
+
+fn field_equals(id: Uuid, value: impl std::fmt::Display) -> String {
+    format!("field:{id} = {value}")
+}
+
+fn resolution_search_text((width, height): Resolution) -> String {
+    format!(
+        "{} {}",
+        field_equals(fields::image::WIDTH.id, width),
+        field_equals(fields::image::HEIGHT.id, height)
+    )
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn aspect_ratio((width, height): Resolution) -> Resolution {
+    let d = gcd(width, height).max(1);
+    (width / d, height / d)
+}
+
+fn is_counted_def(def: &FieldDefinition) -> bool {
+    matches!(def.field_type, FieldType::Tag | FieldType::Container)
+        && !def.has_field(&fields::meta::NO_LINK.id)
+}
+
+fn is_counted_tag(vault: &Vault, id: &Uuid) -> bool {
+    vault
+        .get_definition(id)
+        .is_some_and(|def| is_counted_def(&def))
+}
+
+fn sorted_buckets(
+    counts: HashMap<String, u64>,
+    search_text: impl Fn(&str) -> Option<String>,
+) -> Vec<StatsBucket> {
+    counts
+        .into_iter()
+        .sorted_by(|(a_label, a), (b_label, b)| b.cmp(a).then(a_label.cmp(b_label)))
+        .take(MAX_BUCKETS)
+        .map(|(label, value)| StatsBucket {
+            search_text: search_text(&label),
+            label,
+            value,
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Timeline(BTreeMap<NaiveDate, u64>);
+
+impl Timeline {
+    fn add(&mut self, dt: DateTime<Utc>) {
+        let local = dt.with_timezone(&Local).date_naive();
+        if let Some(month) = NaiveDate::from_ymd_opt(local.year(), local.month(), 1) {
+            *self.0.entry(month).or_default() += 1;
+        }
+    }
+
+    fn into_buckets(self, field_id: Uuid) -> Vec<StatsBucket> {
+        self.0
+            .into_iter()
+            .map(|(month, value)| StatsBucket {
+                label: month.format("%Y-%m").to_string(),
+                value,
+                search_text: month.checked_add_months(Months::new(1)).map(|next| {
+                    format!(
+                        "field:{id} >= {} field:{id} < {}",
+                        month.format("%Y-%m-%d"),
+                        next.format("%Y-%m-%d"),
+                        id = field_id
+                    )
+                }),
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct Accumulator {
+    direct_tags: HashMap<Uuid, usize>,
+    inherited_tags: HashMap<Uuid, usize>,
+    media_types: HashMap<String, u64>,
+    resolutions: HashMap<Resolution, u64>,
+    folder_sizes: HashMap<String, u64>,
+    links: HashMap<String, u64>,
+    unlinked: u64,
+    last_modified: Timeline,
+    post_date: Timeline,
+    total_bytes: u64,
+}
+
+impl Accumulator {
+    fn add_item(&mut self, vault: &Vault, item: &Item, size: u64) -> anyhow::Result<()> {
+        let mut ancestors = HashSet::new();
+        for id in item.iter_fields().map(|f| *f.key()) {
+            if !is_counted_tag(vault, &id) {
+                continue;
+            }
+            *self.direct_tags.entry(id).or_default() += 1;
+            ancestors.extend(vault.iter_field_ancestor_paths(&id).into_iter().flatten());
+        }
+        for id in ancestors {
+            *self.inherited_tags.entry(id).or_default() += 1;
+        }
+
+        if let Some(media_type) = item.get_known_field_value(fields::general::MEDIA_TYPE)? {
+            *self.media_types.entry(media_type.to_string()).or_default() += 1;
+        }
+
+        if let (Some(width), Some(height)) = (
+            item.get_known_field_value(fields::image::WIDTH)?,
+            item.get_known_field_value(fields::image::HEIGHT)?,
+        ) {
+            *self.resolutions.entry((width, height)).or_default() += 1;
+        }
+
+        *self
+            .folder_sizes
+            .entry(tag_stats::folder_of(item.path()))
+            .or_default() += size;
+        self.total_bytes += size;
+
+        let links = item.links()?;
+        if links.is_empty() {
+            self.unlinked += 1;
+        }
+        for vault_name in links
+            .into_iter()
+            .map(|kind::ItemRef((n, _))| n.into_string())
+            .unique()
+        {
+            *self.links.entry(vault_name).or_default() += 1;
+        }
+
+        if let Some(dt) = item.get_known_field_value(fields::general::LAST_MODIFIED)? {
+            self.last_modified.add(dt);
+        }
+        if let Some(dt) = item.get_known_field_value(fields::tweet::POST_DATE)? {
+            self.post_date.add(dt);
+        }
+
+        Ok(())
+    }
+
+    fn finish(self, vault: &Vault, total_items: usize) -> VaultStats {
+        let tags = vault
+            .iter_field_defs()
+            .filter(|def| is_counted_def(def))
+            .map(|def| TagCount {
+                id: def.id,
+                direct: self.direct_tags.get(&def.id).copied().unwrap_or_default(),
+                inherited: self
+                    .inherited_tags
+                    .get(&def.id)
+                    .copied()
+                    .unwrap_or_default(),
+            })
+            .sorted_by(|a, b| b.inherited.cmp(&a.inherited).then(a.id.cmp(&b.id)))
+            .collect_vec();
+
+        let unused_tag_ids = tags
+            .iter()
+            .filter(|count| count.inherited == 0)
+            .map(|count| count.id)
+            .filter(|id| {
+                vault
+                    .get_definition(id)
+                    .is_some_and(|def| def.field_type == FieldType::Tag)
+            })
+            .collect();
+
+        let mut ratios: HashMap<Resolution, Vec<(Resolution, u64)>> = HashMap::new();
+        for (resolution, count) in &self.resolutions {
+            ratios
+                .entry(aspect_ratio(*resolution))
+                .or_default()
+                .push((*resolution, *count));
+        }
+        let aspect_ratios = ratios
+            .into_iter()
+            .map(|((w, h), resolutions)| {
+                let value = resolutions.iter().map(|(_, c)| c).sum();
+                let search_text = (resolutions.len() <= MAX_FILTER_TERMS).then(|| {
+                    resolutions
+                        .iter()
+                        .map(|(r, _)| format!("({})", resolution_search_text(*r)))
+                        .join(" || ")
+                });
+                StatsBucket {
+                    label: format!("{w}:{h}"),
+                    value,
+                    search_text,
+                }
+            })
+            .sorted_by(|a, b| b.value.cmp(&a.value).then(a.label.cmp(&b.label)))
+            .take(MAX_BUCKETS)
+            .collect();
+
+        let resolutions = self
+            .resolutions
+            .into_iter()
+            .sorted_by(|(a_res, a), (b_res, b)| b.cmp(a).then(a_res.cmp(b_res)))
+            .take(MAX_BUCKETS)
+            .map(|((w, h), value)| StatsBucket {
+                label: format!("{w}\u{d7}{h}"),
+                value,
+                search_text: Some(resolution_search_text((w, h))),
+            })
+            .collect();
+
+        let media_types = sorted_buckets(self.media_types, |media_type| {
+            Some(field_equals(
+                fields::general::MEDIA_TYPE.id,
+                quote(media_type),
+            ))
+        });
+
+        let folder_sizes = sorted_buckets(self.folder_sizes, |folder| {
+            Some(format!("folder:{}", quote(folder)))
+        });
+
+        let mut links = sorted_buckets(self.links, |vault_name| {
+            Some(field_equals(fields::general::LINK.id, quote(vault_name)))
+        });
+        if self.unlinked > 0 {
+            links.push(StatsBucket {
+                label: "(not linked)".to_string(),
+                value: self.unlinked,
+                search_text: Some(format!("-field:{}", fields::general::LINK.id)),
+            });
+        }
+
+        VaultStats {
+            vault_name: vault.name.clone(),
+            total_items,
+            total_bytes: self.total_bytes,
+            tags,
+            unused_tag_ids,
+            media_types,
+            resolutions,
+            aspect_ratios,
+            folder_sizes,
+            links,
+            last_modified: self
+                .last_modified
+                .into_buckets(fields::general::LAST_MODIFIED.id),
+            post_date: self.post_date.into_buckets(fields::tweet::POST_DATE.id),
+        }
+    }
+}
+
+async fn file_size(vault: &Vault, item: &Item) -> u64 {
+    let Ok(abs_path) = vault.resolve_abs_path(Path::new(item.path())) else {
+        return 0;
+    };
+    tokio::fs::metadata(abs_path)
+        .await
+        .map_or(0, |metadata| metadata.len())
+}
+
+#[tracing::instrument]
+pub async fn compute_vault_stats(
+    vault: Arc<Vault>,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    let items = vault
+        .iter_items()
+        .map(|i| Arc::clone(&i))
+        .collect::<Vec<_>>();
+    let total = items.len();
+
+    let mut acc = Accumulator::default();
+    #[allow(clippy::cast_precision_loss)]
+    for (i, item) in items.into_iter().enumerate() {
+        let size = file_size(&vault, &item).await;
+        acc.add_item(&vault, &item, size)?;
+        progress.send(ProgressState::DeterminateWithMessage(
+            i as f32 / total as f32,
+            item.path().to_string(),
+        ));
+    }
+    progress.send(ProgressState::Completed);
+
+    Ok(AsyncTaskResult::VaultStats(Box::new(
+        acc.finish(&vault, total),
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::parse::FilterExpressionParseResult;
+    use crate::data::{FieldValue, FilterExpression, ValueMatchExpression};
+
+    fn parse(s: &str) -> FilterExpression {
+        s.parse::<FilterExpressionParseResult>().unwrap().expr
+    }
+
+    #[test]
+    fn test_bucket_search_text() {
+        assert_eq!(
+            parse(&field_equals(
+                fields::general::MEDIA_TYPE.id,
+                quote("image/png")
+            )),
+            FilterExpression::FieldMatch(
+                fields::general::MEDIA_TYPE.id,
+                ValueMatchExpression::Equals(FieldValue::string("image/png".into()))
+            )
+        );
+        assert_eq!(
+            parse(&format!("folder:{}", quote("a b/c \"d\""))),
+            FilterExpression::FolderMatch(Path::new("a b/c \"d\"").into())
+        );
+        assert!(matches!(
+            parse(&format!(
+                "({}) || ({})",
+                resolution_search_text((1920, 1080)),
+                resolution_search_text((1280, 720))
+            )),
+            FilterExpression::Or(..)
+        ));
+        assert_eq!(aspect_ratio((1920, 1080)), (16, 9));
+    }
+}
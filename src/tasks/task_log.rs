@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use anyhow::Context;
+use tokio::task::block_in_place;
+
+use crate::data::{TaskLogEntry, TaskLogError};
+use crate::errors::AppError;
+use crate::tasks::{AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, ProgressState};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, derive_more::Display)]
+pub enum TaskLogFormat {
+    #[display("CSV")]
+    Csv,
+    #[display("JSON")]
+    Json,
+}
+
+impl TaskLogFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+const CSV_HEADER: [&str; 10] = [
+    "task",
+    "vault",
+    "started",
+    "finished",
+    "succeeded",
+    "failed",
+    "cancelled",
+    "path",
+    "kind",
+    "error",
+];
+
+/// Writes one row per error of each entry, or a single row for entries without any errors.
+fn write_csv(path: &Path, entries: &[TaskLogEntry]) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(CSV_HEADER)?;
+    for entry in entries {
+        let summary = [
+            entry.name.clone(),
+            entry.vault.clone().unwrap_or_default(),
+            entry.started.to_rfc3339(),
+            entry.finished.to_rfc3339(),
+            entry.succeeded.to_string(),
+            entry.failed.len().to_string(),
+            entry.cancelled.to_string(),
+        ];
+        let errors = entry
+            .error
+            .iter()
+            .map(|e| (None, e))
+            .chain(
+                entry
+                    .failed
+                    .iter()
+                    .map(|item| (item.path.as_deref(), &item.error)),
+            )
+            .collect::<Vec<(Option<&str>, &TaskLogError)>>();
+
+        if errors.is_empty() {
+            writer.write_record(summary.iter().map(String::as_str).chain(["", "", ""]))?;
+        }
+        for (item_path, error) in errors {
+            let error_text = error.to_string();
+            writer.write_record(summary.iter().map(String::as_str).chain([
+                item_path.unwrap_or_default(),
+                error.kind.as_deref().unwrap_or_default(),
+                error_text.as_str(),
+            ]))?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[tracing::instrument(skip(entries))]
+pub async fn export_task_log(
+    entries: Vec<TaskLogEntry>,
+    format: TaskLogFormat,
+    progress: ProgressSenderRef,
+) -> AsyncTaskReturn {
+    let dialog = rfd::AsyncFileDialog::new()
+        .add_filter(format.to_string(), &[format.extension()])
+        .set_file_name(format!("task_log.{}", format.extension()));
+
+    let fp = dialog.save_file().await.ok_or(AppError::UserCancelled)?;
+    let path = fp.path().to_path_buf();
+
+    progress.send(ProgressState::Determinate(0.5));
+
+    block_in_place(|| match format {
+        TaskLogFormat::Csv => write_csv(&path, &entries),
+        TaskLogFormat::Json => {
+            std::fs::write(&path, serde_json::to_vec_pretty(&entries)?).map_err(Into::into)
+        }
+    })
+    .with_context(|| format!("while exporting task log to {}", path.display()))?;
+
+    Ok(AsyncTaskResult::None)
+}
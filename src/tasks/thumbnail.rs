@@ -34,6 +34,15 @@ fn load_image_thumbnail_from_file(params: &ThumbnailParams) -> anyhow::Result<Ma
     Ok(wand)
 }
 
+/// Reads the thumbnail from the cache if it is up to date, or otherwise from the original image.
+pub fn read_cached_thumbnail(params: &ThumbnailParams) -> anyhow::Result<MagickWand> {
+    let hash_file = std::env::temp_dir().join(params.hash_path());
+    match std::fs::metadata(&hash_file) {
+        Ok(meta) if !thumbnail_needs_updating(params, &meta) => read_image(&hash_file),
+        _ => load_image_thumbnail_from_file(params),
+    }
+}
+
 pub async fn load_image_thumbnail_with_fs(
     params: ThumbnailParams,
     progress: ProgressSenderRef,
@@ -2,10 +2,12 @@
 #![allow(clippy::cast_sign_loss)]
 #![allow(clippy::cast_precision_loss)]
 #![allow(clippy::cast_possible_truncation)]
+#![allow(clippy::cast_possible_wrap)]
 
 use crate::data::transform::{
-    BulkParams, DestinationExistingBehaviour, DestinationKind, DestinationOptions, FitAlgorithm,
-    InfillOptions, InfillTechnique, ScaleAlgorithm, ScaleOptions,
+    BulkParams, CompressionFileType, CompressionMode, CompressionOptions, CropAnchor,
+    DestinationExistingBehaviour, DestinationKind, DestinationOptions, FitAlgorithm,
+    GeometryOptions, InfillOptions, InfillTechnique, Rotation, ScaleAlgorithm, ScaleOptions,
 };
 use crate::data::{
     FieldStore, Item, ItemId, TransformBulkParams, TransformImageParams, TransformPathParams, Vault,
@@ -14,7 +16,8 @@ use crate::errors::AppError;
 use crate::fields;
 use crate::state::AppStateRef;
 use crate::tasks::image::{
-    export_all_rgba, get_last_modified, read_image, wand_to_image, write_image,
+    export_all_rgba, get_exif_orientation, get_last_modified, read_image, wand_to_image,
+    write_image,
 };
 use crate::tasks::import::{import_single_image, process_many};
 use crate::tasks::vault::save_vault_and_links;
@@ -24,6 +27,7 @@ use chrono::Utc;
 use eframe::egui;
 use eframe::egui::{pos2, vec2, Color32, Pos2, Rect, Vec2, ViewportClass};
 use magick_rust::{CompositeOperator, FilterType, GravityType, MagickWand, PixelWand};
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::fmt::{Display, Formatter};
 use std::fs;
@@ -47,6 +51,264 @@ fn get_image_size(wand: &MagickWand) -> Vec2 {
     )
 }
 
+/// The part of an image of the given size that is kept when cropping it to the target aspect
+/// ratio, positioned according to the anchor.
+fn get_crop_rect(size: impl Into<Vec2>, options: &GeometryOptions, focal_point: Vec2) -> Rect {
+    let (dst_x, dst_y) = options.crop_aspect_ratio;
+    get_anchored_crop_rect(size, dst_x.0 / dst_y.0, options.crop_anchor, focal_point)
+}
+
+fn get_anchored_crop_rect(
+    size: impl Into<Vec2>,
+    dst_ratio: f32,
+    anchor: CropAnchor,
+    focal_point: Vec2,
+) -> Rect {
+    let size = size.into();
+    let src_ratio = size.x / size.y;
+
+    let offset = |full: f32, part: f32, start: bool, end: bool, focus: f32| {
+        let max = full - part;
+        match anchor {
+            CropAnchor::FocalPoint => (focus * full - part / 2.0).clamp(0.0, max),
+            _ if start => 0.0,
+            _ if end => max,
+            _ => max / 2.0,
+        }
+    };
+
+    if src_ratio > dst_ratio {
+        let width = (size.y * dst_ratio).floor();
+        let x = offset(
+            size.x,
+            width,
+            anchor == CropAnchor::Left,
+            anchor == CropAnchor::Right,
+            focal_point.x,
+        );
+        Rect::from_min_size(pos2(x.floor(), 0.0), vec2(width, size.y))
+    } else {
+        let height = (size.x / dst_ratio).floor();
+        let y = offset(
+            size.y,
+            height,
+            anchor == CropAnchor::Top,
+            anchor == CropAnchor::Bottom,
+            focal_point.y,
+        );
+        Rect::from_min_size(pos2(0.0, y.floor()), vec2(size.x, height))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum FrameStep {
+    Transpose,
+    FlipX,
+    FlipY,
+    /// Keeps the given part of the image, as a fraction of its size.
+    Crop(Rect),
+}
+
+impl FrameStep {
+    fn forward(self, p: Vec2) -> Vec2 {
+        match self {
+            Self::Transpose => vec2(p.y, p.x),
+            Self::FlipX => vec2(1.0 - p.x, p.y),
+            Self::FlipY => vec2(p.x, 1.0 - p.y),
+            Self::Crop(rect) => (p - rect.min.to_vec2()) / rect.size(),
+        }
+    }
+
+    fn backward(self, p: Vec2) -> Vec2 {
+        match self {
+            Self::Transpose | Self::FlipX | Self::FlipY => self.forward(p),
+            Self::Crop(rect) => p * rect.size() + rect.min.to_vec2(),
+        }
+    }
+}
+
+/// Follows the geometry transformations applied to an image, so that points given as a fraction
+/// of the source image's size can be found in the transformed image and vice versa.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GeometryFrame(Vec<FrameStep>);
+
+impl GeometryFrame {
+    fn orient(&mut self, exif_orientation: i64) {
+        use FrameStep::{FlipX, FlipY, Transpose};
+        self.0.extend_from_slice(match exif_orientation {
+            2 => &[FlipX],
+            3 => &[FlipX, FlipY],
+            4 => &[FlipY],
+            5 => &[Transpose],
+            6 => &[Transpose, FlipX],
+            7 => &[Transpose, FlipX, FlipY],
+            8 => &[Transpose, FlipY],
+            _ => &[],
+        });
+    }
+
+    fn rotate(&mut self, rotation: Rotation) {
+        use FrameStep::{FlipX, FlipY, Transpose};
+        // rotations are clockwise
+        self.0.extend_from_slice(match rotation {
+            Rotation::None => &[],
+            Rotation::Rotate90 => &[Transpose, FlipX],
+            Rotation::Rotate180 => &[FlipX, FlipY],
+            Rotation::Rotate270 => &[Transpose, FlipY],
+        });
+    }
+
+    fn crop(&mut self, full_size: Vec2, rect: Rect) {
+        self.0.push(FrameStep::Crop(Rect::from_min_size(
+            (rect.min.to_vec2() / full_size).to_pos2(),
+            rect.size() / full_size,
+        )));
+    }
+
+    pub fn to_output(&self, point: Vec2) -> Vec2 {
+        self.0
+            .iter()
+            .fold(point, |p, step| step.forward(p))
+            .clamp(Vec2::ZERO, Vec2::splat(1.0))
+    }
+
+    pub fn to_source(&self, point: Vec2) -> Vec2 {
+        self.0
+            .iter()
+            .rev()
+            .fold(point, |p, step| step.backward(p))
+            .clamp(Vec2::ZERO, Vec2::splat(1.0))
+    }
+}
+
+/// The focal point of the item as given in the options, which is relative to the source image.
+fn source_focal_point(options: &GeometryOptions) -> Vec2 {
+    options
+        .focal_point
+        .map_or(vec2(0.5, 0.5), |(x, y)| vec2(x.0, y.0))
+}
+
+fn orientation_swaps_sides(exif_orientation: i64) -> bool {
+    (5..=8).contains(&exif_orientation)
+}
+
+fn get_geometry_size(size: impl Into<Vec2>, options: &GeometryOptions) -> Vec2 {
+    let mut size = size.into();
+    if options.auto_orient && options.orientation.is_some_and(orientation_swaps_sides) {
+        size = vec2(size.y, size.x);
+    }
+    if options.rotation.swaps_sides() {
+        size = vec2(size.y, size.x);
+    }
+    if options.crop {
+        size = get_crop_rect(size, options, source_focal_point(options)).size();
+    }
+    size
+}
+
+fn do_geometry(wand: &mut MagickWand, options: &GeometryOptions) -> anyhow::Result<GeometryFrame> {
+    // ImageMagick is built with a 16-bit quantum depth by default
+    const QUANTUM_RANGE: f64 = 65535.0;
+
+    let mut frame = GeometryFrame::default();
+
+    if options.auto_orient {
+        let orientation = get_exif_orientation(wand);
+        if !wand.auto_orient() {
+            return Err(anyhow::anyhow!("failed to orient image using its metadata"));
+        }
+        frame.orient(orientation.unwrap_or(1));
+    }
+
+    if options.rotation.degrees() != 0.0 {
+        wand.rotate_image(&PixelWand::new(), options.rotation.degrees())?;
+        frame.rotate(options.rotation);
+    }
+    if options.flip_horizontal {
+        wand.flop_image()?;
+        frame.0.push(FrameStep::FlipX);
+    }
+    if options.flip_vertical {
+        wand.flip_image()?;
+        frame.0.push(FrameStep::FlipY);
+    }
+
+    if options.trim_borders {
+        let fuzz = f64::from(options.trim_tolerance.0.clamp(0.0, 1.0)) * QUANTUM_RANGE;
+        let full_size = get_image_size(wand);
+        wand.trim_image(fuzz)?;
+        // the offset of the trimmed image is kept in its page geometry until it is reset
+        let (_, _, x, y) = wand.get_image_page();
+        frame.crop(
+            full_size,
+            Rect::from_min_size(pos2(x as f32, y as f32), get_image_size(wand)),
+        );
+        wand.reset_image_page("")?;
+    }
+
+    if options.crop {
+        let full_size = get_image_size(wand);
+        let focal_point = frame.to_output(source_focal_point(options));
+        let rect = get_crop_rect(full_size, options, focal_point);
+        wand.crop_image(
+            rect.width() as usize,
+            rect.height() as usize,
+            rect.min.x as isize,
+            rect.min.y as isize,
+        )?;
+        wand.reset_image_page("")?;
+        frame.crop(full_size, rect);
+    }
+
+    Ok(frame)
+}
+
+/// Fills in the options that come from the item being transformed rather than the parameters.
+pub fn params_for_item(params: &TransformImageParams, item: &Item) -> TransformImageParams {
+    let mut params = params.clone();
+    let focal_x = item.get_known_field_value(fields::image::FOCAL_X);
+    let focal_y = item.get_known_field_value(fields::image::FOCAL_Y);
+    params.geometry.focal_point = match (focal_x, focal_y) {
+        (Ok(Some(x)), Ok(Some(y))) => Some(((x.0 as f32).into(), (y.0 as f32).into())),
+        _ => None,
+    };
+    params.geometry.orientation = item
+        .get_known_field_value(fields::image::ORIENTATION)
+        .ok()
+        .flatten();
+    params
+}
+
+/// Saves the focal point chosen by the smart crop on an item that doesn't have one yet, so that
+/// transforming it again gives the same result and the point can be adjusted by hand.
+fn store_smart_crop_focal_point(
+    wand: &MagickWand,
+    item: &Item,
+    params: &mut TransformImageParams,
+) -> anyhow::Result<()> {
+    if !params.infill.enabled
+        || params.infill.technique != InfillTechnique::SmartCrop
+        || params.geometry.focal_point.is_some()
+    {
+        return Ok(());
+    }
+
+    // the point is found in the image as it will be cropped, but stored relative to the source
+    // like the points chosen by hand
+    let mut copy = MagickWand::new_from_image(&wand.get_image()?)?;
+    let frame = if params.geometry.enabled {
+        do_geometry(&mut copy, &params.geometry)?
+    } else {
+        GeometryFrame::default()
+    };
+    let point = frame.to_source(find_smart_crop_focal_point(&copy, &params.infill)?);
+
+    item.set_known_field_value(fields::image::FOCAL_X, f64::from(point.x).into());
+    item.set_known_field_value(fields::image::FOCAL_Y, f64::from(point.y).into());
+    params.geometry.focal_point = Some((point.x.into(), point.y.into()));
+    Ok(())
+}
+
 fn get_scaled_size(size: impl Into<Vec2>, options: &ScaleOptions) -> Vec2 {
     let size = size.into();
     let use_width = options.use_target_width;
@@ -99,6 +361,10 @@ fn get_infill_size(size: impl Into<Vec2>, options: &InfillOptions) -> Vec2 {
     let (dst_x, dst_y) = options.target_aspect_ratio;
     let dst_ratio = dst_x.0 / dst_y.0;
 
+    if options.technique == InfillTechnique::SmartCrop {
+        return get_anchored_crop_rect(size, dst_ratio, CropAnchor::Centre, vec2(0.5, 0.5)).size();
+    }
+
     if dst_ratio > src_ratio {
         vec2(size.y * dst_ratio, size.y)
     } else {
@@ -107,35 +373,289 @@ fn get_infill_size(size: impl Into<Vec2>, options: &InfillOptions) -> Vec2 {
     .floor()
 }
 
+/// Colour statistics of an image used to choose an infill technique automatically.
+#[derive(Debug, Clone, PartialEq)]
+struct EdgeStats {
+    /// The average colour along the top or left edge that the infill extends from.
+    start_mean: [f32; 4],
+    /// The average colour along the bottom or right edge that the infill extends from.
+    end_mean: [f32; 4],
+    /// The largest average distance of an edge pixel from the mean colour of its edge.
+    spread: f32,
+    /// The number of distinct colours relative to the number of pixels, which is very low for
+    /// pixel art.
+    colour_ratio: f32,
+}
+
+fn colour_distance(a: [f32; 4], b: [f32; 4]) -> f32 {
+    ((a[0] - b[0]).abs() + (a[1] - b[1]).abs() + (a[2] - b[2]).abs()) / (3.0 * 255.0)
+}
+
+fn rgba_at(rgba: &[u8], width: usize, x: usize, y: usize) -> [f32; 4] {
+    let i = (y * width + x) * 4;
+    [
+        rgba[i] as f32,
+        rgba[i + 1] as f32,
+        rgba[i + 2] as f32,
+        rgba[i + 3] as f32,
+    ]
+}
+
+/// Returns the mean colour of the pixels and their average distance from it.
+fn get_line_stats(pixels: &[[f32; 4]]) -> ([f32; 4], f32) {
+    let count = pixels.len().max(1) as f32;
+    let mut mean = [0.0; 4];
+    for px in pixels {
+        for (m, c) in mean.iter_mut().zip(px) {
+            *m += c / count;
+        }
+    }
+    let spread = pixels
+        .iter()
+        .map(|px| colour_distance(*px, mean))
+        .sum::<f32>()
+        / count;
+    (mean, spread)
+}
+
+/// Collects the statistics of the edges the infill extends from, which are the top and bottom
+/// edges if `vertical` is set or the left and right edges otherwise.
+fn get_edge_stats(rgba: &[u8], width: usize, height: usize, vertical: bool) -> EdgeStats {
+    let (start, end): (Vec<_>, Vec<_>) = if vertical {
+        (0..width)
+            .map(|x| {
+                (
+                    rgba_at(rgba, width, x, 0),
+                    rgba_at(rgba, width, x, height - 1),
+                )
+            })
+            .unzip()
+    } else {
+        (0..height)
+            .map(|y| {
+                (
+                    rgba_at(rgba, width, 0, y),
+                    rgba_at(rgba, width, width - 1, y),
+                )
+            })
+            .unzip()
+    };
+    let (start_mean, start_spread) = get_line_stats(&start);
+    let (end_mean, end_spread) = get_line_stats(&end);
+
+    let colours: HashSet<_> = rgba.chunks_exact(4).collect();
+    EdgeStats {
+        start_mean,
+        end_mean,
+        spread: start_spread.max(end_spread),
+        colour_ratio: colours.len() as f32 / (width * height).max(1) as f32,
+    }
+}
+
+/// Rates how well each infill technique suits an image with the given edge statistics, where the
+/// colour tolerance is how far apart two colours can be while still being considered the same.
+fn score_infill_techniques(
+    stats: &EdgeStats,
+    colour_tolerance: f32,
+) -> Vec<(InfillTechnique, f32)> {
+    const PIXEL_ART_COLOUR_RATIO: f32 = 0.05;
+
+    let tolerance = colour_tolerance.max(f32::EPSILON);
+    let edges_flat = (1.0 - stats.spread / tolerance).max(0.0);
+    let edges_smooth = (1.0 - stats.spread / (4.0 * tolerance)).max(0.0);
+    let edges_match =
+        (1.0 - colour_distance(stats.start_mean, stats.end_mean) / tolerance).max(0.0);
+    // blurring pixel art gives a smeared look that doesn't match the crisp image in the middle
+    let pixel_art = if stats.colour_ratio < PIXEL_ART_COLOUR_RATIO {
+        1.0
+    } else {
+        0.0
+    };
+
+    vec![
+        (InfillTechnique::Solid, edges_flat * edges_match),
+        (
+            InfillTechnique::Gradient,
+            edges_flat * (1.0 - edges_match) * 0.9,
+        ),
+        (InfillTechnique::EdgeExtend, edges_smooth * 0.6),
+        (InfillTechnique::Mirror, 0.3 + pixel_art * 0.4),
+        (InfillTechnique::Tile, 0.1 + pixel_art * 0.4),
+        (InfillTechnique::BlurredTile, 0.2 - pixel_art * 0.2),
+        (InfillTechnique::Blur, 0.5 - pixel_art * 0.3),
+    ]
+}
+
 fn determine_infill_technique(
     wand: &MagickWand,
     target_size: impl Into<Vec2>,
-    colour_tolerance: f64,
+    colour_tolerance: f32,
 ) -> anyhow::Result<InfillTechnique> {
-    let colour_tolerance = colour_tolerance.clamp(0.0, 1.0);
+    const SAMPLE_SIZE: f32 = 256.0;
 
     let source_size = get_image_size(wand);
-    let source_ratio = source_size.x / source_size.y;
     let target_size = target_size.into();
-    let target_ratio = target_size.x / target_size.y;
-
-    let trim_wand = MagickWand::new_from_image(&wand.get_image()?)?;
-    trim_wand.trim_image(colour_tolerance)?;
-    let trim_size = get_image_size(&trim_wand);
-
-    match (
-        target_ratio < source_ratio,
-        trim_size.x < source_size.x,
-        trim_size.y < source_size.y,
-    ) {
-        (true, _, trimmed) | (false, trimmed, _) => {
-            if trimmed {
-                Ok(InfillTechnique::Solid)
+    let vertical = source_size.x / source_size.y > target_size.x / target_size.y;
+
+    // nearest neighbour sampling doesn't introduce any new colours
+    let scale = (SAMPLE_SIZE / source_size.max_elem()).min(1.0);
+    let sample_size = (source_size * scale).round().max(vec2(1.0, 1.0));
+    let sample_wand = MagickWand::new_from_image(&wand.get_image()?)?;
+    sample_wand.sample_image(sample_size.x as usize, sample_size.y as usize)?;
+
+    let (width, height) = (
+        sample_wand.get_image_width(),
+        sample_wand.get_image_height(),
+    );
+    let stats = get_edge_stats(&export_all_rgba(&sample_wand)?, width, height, vertical);
+
+    Ok(
+        score_infill_techniques(&stats, colour_tolerance.clamp(0.0, 1.0))
+            .into_iter()
+            .fold((InfillTechnique::Blur, f32::MIN), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            })
+            .0,
+    )
+}
+
+/// Whether the colour falls within a simple RGB skin tone range.
+fn is_skin_tone(r: u8, g: u8, b: u8) -> bool {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    r > 95 && g > 40 && b > 20 && max - min > 15 && r.abs_diff(g) > 15 && r > g && r > b
+}
+
+/// Scores each pixel of an RGBA buffer by its local contrast, with extra weight given to skin tones
+/// so that faces and figures are preferred over busy backgrounds.
+fn get_saliency_map(rgba: &[u8], width: usize, height: usize) -> Vec<f32> {
+    const SKIN_WEIGHT: f32 = 0.5;
+
+    let luma: Vec<f32> = rgba
+        .chunks_exact(4)
+        .map(|px| {
+            (0.299 * px[0] as f32 + 0.587 * px[1] as f32 + 0.114 * px[2] as f32) / 255.0
+                * (px[3] as f32 / 255.0)
+        })
+        .collect();
+
+    let mut energy = vec![0.0; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+            let dx =
+                luma[y * width + (x + 1).min(width - 1)] - luma[y * width + x.saturating_sub(1)];
+            let dy =
+                luma[(y + 1).min(height - 1) * width + x] - luma[y.saturating_sub(1) * width + x];
+            let px = &rgba[i * 4..i * 4 + 4];
+            let skin = if is_skin_tone(px[0], px[1], px[2]) {
+                SKIN_WEIGHT
             } else {
-                Ok(InfillTechnique::Blur)
+                0.0
+            };
+            energy[i] = dx.abs() + dy.abs() + skin;
+        }
+    }
+    energy
+}
+
+/// Finds the position of the window of the given size with the most energy, returning its centre
+/// relative to the size of the map.
+fn find_best_window(energy: &[f32], width: usize, height: usize, window: Vec2) -> Vec2 {
+    let win_width = (window.x.round() as usize).clamp(1, width);
+    let win_height = (window.y.round() as usize).clamp(1, height);
+
+    // summed area table with an extra leading row and column of zeroes
+    let stride = width + 1;
+    let mut table = vec![0.0f64; stride * (height + 1)];
+    for y in 0..height {
+        for x in 0..width {
+            table[(y + 1) * stride + x + 1] = f64::from(energy[y * width + x])
+                + table[y * stride + x + 1]
+                + table[(y + 1) * stride + x]
+                - table[y * stride + x];
+        }
+    }
+
+    let mut best = (f64::MIN, 0, 0);
+    for top in 0..=height - win_height {
+        for left in 0..=width - win_width {
+            let (right, bottom) = (left + win_width, top + win_height);
+            let sum = table[bottom * stride + right]
+                - table[top * stride + right]
+                - table[bottom * stride + left]
+                + table[top * stride + left];
+            if sum > best.0 {
+                best = (sum, left, top);
             }
         }
     }
+
+    vec2(
+        (best.1 as f32 + win_width as f32 / 2.0) / width as f32,
+        (best.2 as f32 + win_height as f32 / 2.0) / height as f32,
+    )
+}
+
+/// Picks the centre of the most interesting part of the image to keep when cropping it to the
+/// infill aspect ratio.
+pub fn find_smart_crop_focal_point(
+    wand: &MagickWand,
+    options: &InfillOptions,
+) -> anyhow::Result<Vec2> {
+    const SALIENCY_SIZE: f32 = 128.0;
+
+    let size = get_image_size(wand);
+    let scale = (SALIENCY_SIZE / size.max_elem()).min(1.0);
+    let small_size = (size * scale).round().max(vec2(1.0, 1.0));
+    let small = MagickWand::new_from_image(&wand.get_image()?)?;
+    small.resize_image(
+        small_size.x as usize,
+        small_size.y as usize,
+        FilterType::Triangle,
+    )?;
+
+    let (width, height) = (small.get_image_width(), small.get_image_height());
+    let energy = get_saliency_map(&export_all_rgba(&small)?, width, height);
+
+    let (dst_x, dst_y) = options.target_aspect_ratio;
+    let window = get_anchored_crop_rect(
+        vec2(width as f32, height as f32),
+        dst_x.0 / dst_y.0,
+        CropAnchor::Centre,
+        vec2(0.5, 0.5),
+    );
+    Ok(find_best_window(&energy, width, height, window.size()))
+}
+
+fn do_smart_crop(
+    wand: &mut MagickWand,
+    options: &InfillOptions,
+    focal_point: Option<Vec2>,
+) -> anyhow::Result<()> {
+    let focal_point = match focal_point {
+        Some(point) => point,
+        None => find_smart_crop_focal_point(wand, options)?,
+    };
+    let (dst_x, dst_y) = options.target_aspect_ratio;
+    let rect = get_anchored_crop_rect(
+        get_image_size(wand),
+        dst_x.0 / dst_y.0,
+        CropAnchor::FocalPoint,
+        focal_point,
+    );
+    wand.crop_image(
+        rect.width() as usize,
+        rect.height() as usize,
+        rect.min.x as isize,
+        rect.min.y as isize,
+    )?;
+    wand.reset_image_page("")?;
+    Ok(())
 }
 
 #[allow(clippy::needless_pass_by_value)]
@@ -217,6 +737,25 @@ fn do_blur_infill(
         crop_rect.min.y as isize,
     )?;
 
+    post_process_infill(wand, params, blur_scale)?;
+
+    wand.resize_image(
+        out_size.x as usize,
+        out_size.y as usize,
+        FilterType::Lanczos,
+    )?;
+
+    wand.compose_images_gravity(&orig_wand, CompositeOperator::Over, GravityType::Center)?;
+
+    Ok(())
+}
+
+/// Applies the blur, brightness and contrast changes to an infill background.
+fn post_process_infill(
+    wand: &mut MagickWand,
+    params: &InfillOptions,
+    blur_scale: Option<f32>,
+) -> anyhow::Result<()> {
     if params.use_gaussian {
         let mut radius = params.gaussian_radius as f64;
         if let Some(blur_scale) = blur_scale {
@@ -237,13 +776,92 @@ fn do_blur_infill(
     };
     wand.brightness_contrast_image(brightness as f64, contrast as f64)?;
 
-    wand.resize_image(
-        out_size.x as usize,
-        out_size.y as usize,
-        FilterType::Lanczos,
-    )?;
+    Ok(())
+}
 
-    wand.compose_images_gravity(&orig_wand, CompositeOperator::Over, GravityType::Center)?;
+/// Maps a coordinate outside of `0..len` back into it by reflecting it off the ends.
+fn reflect_coord(i: isize, len: usize) -> usize {
+    let len = len as isize;
+    let m = i.rem_euclid(2 * len);
+    (if m < len { m } else { 2 * len - 1 - m }) as usize
+}
+
+/// The source pixel that a pixel outside of the image takes its colour from, for the techniques
+/// that repeat the image contents.
+fn get_infill_source(
+    technique: InfillTechnique,
+    x: isize,
+    y: isize,
+    width: usize,
+    height: usize,
+) -> (usize, usize) {
+    match technique {
+        InfillTechnique::Mirror => (reflect_coord(x, width), reflect_coord(y, height)),
+        InfillTechnique::Tile | InfillTechnique::BlurredTile => (
+            x.rem_euclid(width as isize) as usize,
+            y.rem_euclid(height as isize) as usize,
+        ),
+        _ => (
+            x.clamp(0, width as isize - 1) as usize,
+            y.clamp(0, height as isize - 1) as usize,
+        ),
+    }
+}
+
+/// Fills in the background pixel by pixel for the mirror, edge extend, gradient and tile
+/// techniques.
+fn do_pixel_infill(
+    wand: &mut MagickWand,
+    params: &InfillOptions,
+    technique: InfillTechnique,
+    blur_scale: Option<f32>,
+) -> anyhow::Result<()> {
+    let size = get_image_size(wand);
+    let out_size = get_infill_size(size, params);
+    let vertical = size.x / size.y > out_size.x / out_size.y;
+
+    let (width, height) = (wand.get_image_width(), wand.get_image_height());
+    let (out_width, out_height) = (out_size.x as usize, out_size.y as usize);
+    let left = (out_width.saturating_sub(width) / 2) as isize;
+    let top = (out_height.saturating_sub(height) / 2) as isize;
+
+    let rgba = export_all_rgba(wand)?;
+    let stats = get_edge_stats(&rgba, width, height, vertical);
+
+    let mut out = Vec::with_capacity(out_width * out_height * 4);
+    for out_y in 0..out_height {
+        for out_x in 0..out_width {
+            let (x, y) = (out_x as isize - left, out_y as isize - top);
+            let inside = (0..width as isize).contains(&x) && (0..height as isize).contains(&y);
+            if technique == InfillTechnique::Gradient && !inside {
+                let t = if vertical {
+                    out_y as f32 / (out_height - 1).max(1) as f32
+                } else {
+                    out_x as f32 / (out_width - 1).max(1) as f32
+                };
+                out.extend(
+                    stats
+                        .start_mean
+                        .iter()
+                        .zip(stats.end_mean)
+                        .map(|(a, b)| (a + (b - a) * t).round() as u8),
+                );
+            } else {
+                let (src_x, src_y) = get_infill_source(technique, x, y, width, height);
+                let i = (src_y * width + src_x) * 4;
+                out.extend_from_slice(&rgba[i..i + 4]);
+            }
+        }
+    }
+
+    let orig_wand = MagickWand::new_from_image(&wand.get_image()?)?;
+    wand.extend_image(out_width, out_height, 0, 0)?;
+    wand.import_image_pixels(0, 0, out_width, out_height, &out, "RGBA")?;
+
+    if technique == InfillTechnique::BlurredTile {
+        post_process_infill(wand, params, blur_scale)?;
+        wand.compose_images(&orig_wand, CompositeOperator::Over, true, left, top)?;
+    }
 
     Ok(())
 }
@@ -251,6 +869,10 @@ fn do_blur_infill(
 pub fn get_transformed_size(size: impl Into<Vec2>, params: &TransformImageParams) -> Vec2 {
     let mut size = size.into();
 
+    if params.geometry.enabled {
+        size = get_geometry_size(size, &params.geometry);
+    }
+
     if params.scale.enabled {
         size = get_scaled_size(size, &params.scale);
     }
@@ -328,6 +950,15 @@ pub fn transform_wand(
     params: &TransformImageParams,
     full_size: Option<Vec2>,
 ) -> anyhow::Result<()> {
+    // assume x scale == y scale
+    let thumbnail_scale = full_size.map(|full_size| get_image_size(wand).x / full_size.x);
+
+    let frame = if params.geometry.enabled {
+        do_geometry(wand, &params.geometry)?
+    } else {
+        GeometryFrame::default()
+    };
+
     let orig_size = get_image_size(wand);
 
     if params.scale.enabled {
@@ -361,17 +992,30 @@ pub fn transform_wand(
     if params.infill.enabled {
         let out_size = get_infill_size(get_image_size(wand), &params.infill);
         let technique = match params.infill.technique {
-            // TODO: allow choosing infill colour tolerance?
-            InfillTechnique::Automatic => determine_infill_technique(wand, out_size, 0.02)?,
+            InfillTechnique::Automatic => {
+                determine_infill_technique(wand, out_size, params.infill.colour_tolerance.0)?
+            }
             tech => tech,
         };
 
-        // assume x scale == y scale
-        let blur_scale = full_size.map(|full_size| orig_size.x / full_size.x);
-
         match technique {
-            InfillTechnique::Blur => do_blur_infill(wand, &params.infill, blur_scale)?,
+            InfillTechnique::Blur => do_blur_infill(wand, &params.infill, thumbnail_scale)?,
             InfillTechnique::Solid => do_solid_infill(wand, &params.infill)?,
+            InfillTechnique::Mirror
+            | InfillTechnique::EdgeExtend
+            | InfillTechnique::Gradient
+            | InfillTechnique::Tile
+            | InfillTechnique::BlurredTile => {
+                do_pixel_infill(wand, &params.infill, technique, thumbnail_scale)?;
+            }
+            InfillTechnique::SmartCrop => do_smart_crop(
+                wand,
+                &params.infill,
+                params
+                    .geometry
+                    .focal_point
+                    .map(|(x, y)| frame.to_output(vec2(x.0, y.0))),
+            )?,
             InfillTechnique::Automatic => unreachable!(),
         };
     }
@@ -379,6 +1023,178 @@ pub fn transform_wand(
     Ok(())
 }
 
+/// The outcome of encoding a transformed image according to the compression options.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodedImage {
+    pub file_size: u64,
+    /// The quality the image was encoded at, which is not given for lossless formats.
+    pub quality: Option<u16>,
+    /// How much the image was scaled down to fit within the maximum file size.
+    pub scale: f32,
+}
+
+/// Finds the highest quality between `min` and `max` for which `fits` returns true, assuming
+/// that the output only gets larger as the quality increases.
+fn search_quality(
+    min: u16,
+    max: u16,
+    mut fits: impl FnMut(u16) -> anyhow::Result<bool>,
+) -> anyhow::Result<Option<u16>> {
+    let (mut low, mut high) = (min, max);
+    let mut best = None;
+    while low <= high {
+        let mid = low + (high - low) / 2;
+        if fits(mid)? {
+            best = Some(mid);
+            low = mid + 1;
+        } else if mid == 0 {
+            break;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Ok(best)
+}
+
+fn prepare_for_encoding(wand: &MagickWand, options: &CompressionOptions) -> anyhow::Result<()> {
+    if options.strip_metadata {
+        wand.strip_image()?;
+    }
+    wand.set_image_format(options.file_type.magick_format())?;
+    match options.file_type {
+        CompressionFileType::Jpeg => wand.set_option(
+            "jpeg:sampling-factor",
+            options.chroma_subsampling.sampling_factor(),
+        )?,
+        CompressionFileType::WebpLossless => wand.set_option("webp:lossless", "true")?,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn encode_with_quality(
+    wand: &MagickWand,
+    options: &CompressionOptions,
+    quality: u16,
+) -> anyhow::Result<Vec<u8>> {
+    wand.set_image_compression_quality(quality.into())?;
+    Ok(wand.write_image_blob(options.file_type.magick_format())?)
+}
+
+/// Encodes the image in the chosen format, lowering the quality and optionally the dimensions
+/// until it fits within the maximum file size if one is set.
+pub fn encode_image(
+    wand: &MagickWand,
+    options: &CompressionOptions,
+) -> anyhow::Result<(Vec<u8>, EncodedImage)> {
+    const MIN_QUALITY: u16 = 10;
+    const MAX_DOWNSCALE_STEPS: usize = 8;
+    const MAX_DOWNSCALE_FACTOR: f32 = 0.9;
+
+    let lossless = options.file_type.is_lossless();
+    let reported_quality = |quality: u16| (!lossless).then_some(quality);
+
+    let mut scaled = MagickWand::new_from_image(&wand.get_image()?)?;
+    prepare_for_encoding(&scaled, options)?;
+
+    if options.mode == CompressionMode::Quality {
+        let data = encode_with_quality(&scaled, options, options.quality)?;
+        let encoded = EncodedImage {
+            file_size: data.len() as u64,
+            quality: reported_quality(options.quality),
+            scale: 1.0,
+        };
+        return Ok((data, encoded));
+    }
+
+    let max_size = u64::from(options.max_file_size_kib) * 1024;
+    let full_size = get_image_size(wand);
+    let mut scale = 1.0;
+    let mut smallest = u64::MAX;
+
+    for _ in 0..=MAX_DOWNSCALE_STEPS {
+        let mut output = None;
+        let min_quality = if lossless {
+            options.quality
+        } else {
+            MIN_QUALITY.min(options.quality)
+        };
+        let quality = search_quality(min_quality, options.quality, |quality| {
+            let data = encode_with_quality(&scaled, options, quality)?;
+            let size = data.len() as u64;
+            smallest = smallest.min(size);
+            let fits = size <= max_size;
+            if fits {
+                output = Some(data);
+            }
+            Ok(fits)
+        })?;
+
+        if let (Some(quality), Some(data)) = (quality, output) {
+            let encoded = EncodedImage {
+                file_size: data.len() as u64,
+                quality: reported_quality(quality),
+                scale,
+            };
+            return Ok((data, encoded));
+        }
+
+        if !options.allow_downscale {
+            break;
+        }
+
+        // the file size is roughly proportional to the number of pixels
+        scale *= ((max_size as f32 / smallest as f32).sqrt()).min(MAX_DOWNSCALE_FACTOR);
+        let new_size = (full_size * scale).round().max(vec2(1.0, 1.0));
+        scaled = MagickWand::new_from_image(&wand.get_image()?)?;
+        scaled.resize_image(
+            new_size.x as usize,
+            new_size.y as usize,
+            FilterType::Lanczos,
+        )?;
+        prepare_for_encoding(&scaled, options)?;
+    }
+
+    Err(AppError::FileSizeTooLarge {
+        max_size,
+        size: smallest,
+    }
+    .into())
+}
+
+/// The path of the output file, which takes the extension of the compressed file type.
+pub fn get_output_path(path: &Path, options: &CompressionOptions) -> PathBuf {
+    if options.enabled {
+        path.with_extension(options.file_type.extension())
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Returns `path`, or `path` with a discriminator appended if an item or file already exists there.
+fn unique_output_path(vault: &Vault, path: &Path) -> Option<PathBuf> {
+    Discriminator::from_path(path)?.into_unique_path(|p| {
+        let abs_path = vault.resolve_abs_path(p).ok()?;
+        Some(vault.get_item(p).is_err() && !abs_path.exists())
+    })
+}
+
+/// Writes the transformed image, encoding it according to the compression options if enabled.
+fn write_output(
+    wand: &MagickWand,
+    params: &TransformImageParams,
+    path: &Path,
+) -> anyhow::Result<Option<EncodedImage>> {
+    if !params.compression.enabled {
+        write_image(wand, path)?;
+        return Ok(None);
+    }
+
+    let (data, encoded) = encode_image(wand, &params.compression)?;
+    fs::write(path, data).with_context(|| format!("while writing to path {}", path.display()))?;
+    Ok(Some(encoded))
+}
+
 pub fn list_destination_paths(
     dest: &DestinationOptions,
     app_state: AppStateRef,
@@ -448,15 +1264,25 @@ pub fn transform_path(item: &Item, params: &TransformPathParams) -> Option<PathB
 #[derive(Debug)]
 pub enum TransformResult {
     NoTransform(PathBuf),
-    InPlaceTransform(PathBuf),
+    Imported(PathBuf),
+    InPlaceTransform(PathBuf, Option<EncodedImage>),
     RemovedWithoutTransform(PathBuf),
-    MoveSuccess { removed: PathBuf, created: PathBuf },
-    CopySuccess { original: PathBuf, copy: PathBuf },
+    MoveSuccess {
+        removed: PathBuf,
+        created: PathBuf,
+        encoded: Option<EncodedImage>,
+    },
+    CopySuccess {
+        original: PathBuf,
+        copy: PathBuf,
+        encoded: Option<EncodedImage>,
+    },
 }
 
 pub trait TransformReturn {
     fn orig_path(&self) -> Option<&Path>;
     fn new_path(&self) -> Option<&Path>;
+    fn encoded(&self) -> Option<&EncodedImage>;
 }
 
 impl TransformReturn for anyhow::Result<TransformResult> {
@@ -464,7 +1290,8 @@ impl TransformReturn for anyhow::Result<TransformResult> {
         match self {
             Ok(
                 TransformResult::NoTransform(buf)
-                | TransformResult::InPlaceTransform(buf)
+                | TransformResult::Imported(buf)
+                | TransformResult::InPlaceTransform(buf, _)
                 | TransformResult::RemovedWithoutTransform(buf)
                 | TransformResult::MoveSuccess { removed: buf, .. }
                 | TransformResult::CopySuccess { original: buf, .. },
@@ -485,9 +1312,20 @@ impl TransformReturn for anyhow::Result<TransformResult> {
             _ => None,
         }
     }
+
+    fn encoded(&self) -> Option<&EncodedImage> {
+        match self {
+            Ok(
+                TransformResult::InPlaceTransform(_, encoded)
+                | TransformResult::MoveSuccess { encoded, .. }
+                | TransformResult::CopySuccess { encoded, .. },
+            ) => encoded.as_ref(),
+            _ => None,
+        }
+    }
 }
 
-struct Discriminator<'a> {
+pub(crate) struct Discriminator<'a> {
     parent: &'a Path,
     base_name: &'a OsStr,
     extension: Option<&'a OsStr>,
@@ -495,7 +1333,7 @@ struct Discriminator<'a> {
 }
 
 impl<'a> Discriminator<'a> {
-    fn from_path(path: &'a Path) -> Option<Self> {
+    pub(crate) fn from_path(path: &'a Path) -> Option<Self> {
         let parent = path.parent()?;
         let base_name = path.file_stem()?;
         let extension = path.extension();
@@ -523,7 +1361,10 @@ impl<'a> Discriminator<'a> {
         self.discriminator = Some(self.discriminator.map_or(2, |x| x + 1));
     }
 
-    fn into_unique_path(mut self, check_fn: impl Fn(&Path) -> Option<bool>) -> Option<PathBuf> {
+    pub(crate) fn into_unique_path(
+        mut self,
+        check_fn: impl Fn(&Path) -> Option<bool>,
+    ) -> Option<PathBuf> {
         loop {
             let path = self.get_path();
             if check_fn(&path)? {
@@ -603,12 +1444,14 @@ async fn apply_path_transformation(
                         return Ok(TransformResult::MoveSuccess {
                             removed: old_abs_path,
                             created: new_abs_path,
+                            encoded: None,
                         });
                     }
 
                     return Ok(TransformResult::CopySuccess {
                         original: old_abs_path,
                         copy: new_abs_path,
+                        encoded: None,
                     });
                 };
             }
@@ -712,12 +1555,14 @@ async fn apply_path_transformation(
                         return Ok(TransformResult::MoveSuccess {
                             removed: old_abs_path,
                             created: new_abs_path,
+                            encoded: None,
                         });
                     }
 
                     Ok(TransformResult::CopySuccess {
                         original: old_abs_path,
                         copy: new_abs_path,
+                        encoded: None,
                     })
                 }
             }
@@ -737,14 +1582,19 @@ async fn apply_image_transformation(
     let orig_item = vault.get_item_by_id(item_id)?;
     let orig_abs_path = vault.resolve_abs_path(Path::new(orig_item.path()))?;
     let rel_path = Path::new(vault.resolve_rel_path(Path::new(orig_item.path()))?);
-
-    // TODO: handle compression options
+    let mut params = params_for_item(params, &orig_item);
+    let dest_rel_path = get_output_path(rel_path, &params.compression);
 
     let mut wand = read_image(&orig_abs_path)?;
 
     let exist_behaviour = bulk.destination.item_existing_behaviour;
     let dry_run = params.dry_run;
 
+    if !dry_run {
+        store_smart_crop_focal_point(&wand, &orig_item, &mut params)?;
+    }
+    let params = &params;
+
     macro_rules! remove {
         () => {
             if !dry_run {
@@ -757,9 +1607,10 @@ async fn apply_image_transformation(
 
     macro_rules! move_or_copy_into {
         ($vault:ident, $abs_path:ident) => {
+            let mut encoded = None;
             if !dry_run {
                 transform_wand(&mut wand, params, None)?;
-                write_image(&wand, &$abs_path)?;
+                encoded = write_output(&wand, params, &$abs_path)?;
                 import_single_image(
                     Arc::clone(&$vault),
                     $abs_path.clone().into_boxed_path(),
@@ -782,12 +1633,14 @@ async fn apply_image_transformation(
                 return Ok(TransformResult::MoveSuccess {
                     removed: orig_abs_path,
                     created: $abs_path,
+                    encoded,
                 });
             }
 
             return Ok(TransformResult::CopySuccess {
                 original: orig_abs_path,
                 copy: $abs_path,
+                encoded,
             });
         };
     }
@@ -800,10 +1653,56 @@ async fn apply_image_transformation(
                 }
                 .into())
             }
+            DestinationExistingBehaviour::Overwrite if dest_rel_path != rel_path => {
+                // the file type changed, so the original is replaced by a file with a new
+                // extension, which must not clobber another file that already has that name
+                let Some(new_rel_path) = unique_output_path(&vault, &dest_rel_path) else {
+                    return Ok(TransformResult::NoTransform(orig_abs_path));
+                };
+                let new_abs_path = vault.resolve_abs_path(&new_rel_path)?;
+                let mut encoded = None;
+                if !dry_run {
+                    transform_wand(&mut wand, params, None)?;
+                    encoded = write_output(&wand, params, &new_abs_path)?;
+                    // carry over the original's fields, letting the import refresh the rest
+                    let new_item = vault.get_item_or_init(&new_abs_path)?;
+                    new_item.update(orig_item.as_ref());
+                    new_item.remove_field(&fields::general::MEDIA_TYPE.id);
+                    new_item.remove_field(&fields::general::LAST_MODIFIED.id);
+                    import_single_image(
+                        Arc::clone(&vault),
+                        new_abs_path.clone().into_boxed_path(),
+                        Utc::now(),
+                    )
+                    .await?;
+
+                    // the links of the original now belong to the new item, so they are
+                    // rewritten rather than unlinked
+                    vault.remove_item(rel_path)?;
+                    tokio::fs::remove_file(&orig_abs_path).await?;
+                    let moved = HashMap::from([(
+                        (vault.name.clone(), orig_item.path().to_string()),
+                        (vault.name.clone(), new_item.path().to_string()),
+                    )]);
+                    for name in state.valid_vault_names() {
+                        if let Ok(other_vault) = state.get_vault(&name) {
+                            other_vault.rewrite_item_refs(&moved);
+                        }
+                    }
+                    state.update_item_links(&vault, &new_item)?;
+                }
+
+                Ok(TransformResult::MoveSuccess {
+                    removed: orig_abs_path,
+                    created: new_abs_path,
+                    encoded,
+                })
+            }
             DestinationExistingBehaviour::Overwrite => {
+                let mut encoded = None;
                 if !dry_run {
                     transform_wand(&mut wand, params, None)?;
-                    write_image(&wand, &orig_abs_path)?;
+                    encoded = write_output(&wand, params, &orig_abs_path)?;
                     import_single_image(
                         Arc::clone(&vault),
                         orig_abs_path.clone().into_boxed_path(),
@@ -813,10 +1712,10 @@ async fn apply_image_transformation(
                     state.update_item_links(&vault, &orig_item)?;
                 }
 
-                Ok(TransformResult::InPlaceTransform(orig_abs_path))
+                Ok(TransformResult::InPlaceTransform(orig_abs_path, encoded))
             }
             DestinationExistingBehaviour::AppendDiscriminator => {
-                let Some(new_rel_path) = Discriminator::from_path(&rel_path)
+                let Some(new_rel_path) = Discriminator::from_path(&dest_rel_path)
                     .and_then(|d| d.into_unique_path(|p| Some(vault.get_item(p).is_err())))
                 else {
                     return Ok(TransformResult::NoTransform(orig_abs_path));
@@ -831,16 +1730,14 @@ async fn apply_image_transformation(
 
             let new_rel_path = match exist_behaviour {
                 DestinationExistingBehaviour::AppendDiscriminator => {
-                    let Some(disc_path) =
-                        Discriminator::from_path(Path::new(&rel_path)).and_then(|d| {
-                            d.into_unique_path(|p| Some(other_vault.get_item(p).is_err()))
-                        })
-                    else {
+                    let Some(disc_path) = Discriminator::from_path(&dest_rel_path).and_then(|d| {
+                        d.into_unique_path(|p| Some(other_vault.get_item(p).is_err()))
+                    }) else {
                         return Ok(TransformResult::NoTransform(orig_abs_path));
                     };
                     disc_path
                 }
-                _ => rel_path.into(),
+                _ => dest_rel_path.clone(),
             };
 
             let other_item_exists = other_vault.get_item(&new_rel_path).is_ok();
@@ -899,8 +1796,7 @@ async fn apply_image_transformation(
             }
         }
         DestinationKind::Directory => {
-            let mut new_abs_path =
-                Path::new(&bulk.destination.directory_path).join(Path::new(&rel_path));
+            let mut new_abs_path = Path::new(&bulk.destination.directory_path).join(&dest_rel_path);
 
             if exist_behaviour == DestinationExistingBehaviour::AppendDiscriminator {
                 let Some(disc_path) = Discriminator::from_path(&new_abs_path).and_then(|d| {
@@ -932,20 +1828,24 @@ async fn apply_image_transformation(
                     DestinationExistingBehaviour::Overwrite
                     | DestinationExistingBehaviour::AppendDiscriminator,
                 ) => {
+                    let mut encoded = None;
                     if !dry_run {
-                        write_image(&wand, &new_abs_path)?;
+                        transform_wand(&mut wand, params, None)?;
+                        encoded = write_output(&wand, params, &new_abs_path)?;
                     }
                     if bulk.source.delete_source {
                         remove!();
                         return Ok(TransformResult::MoveSuccess {
                             removed: orig_abs_path,
                             created: new_abs_path,
+                            encoded,
                         });
                     }
 
                     Ok(TransformResult::CopySuccess {
                         original: orig_abs_path,
                         copy: new_abs_path,
+                        encoded,
                     })
                 }
             }
@@ -963,7 +1863,7 @@ impl Display for PathContext {
     }
 }
 
-async fn apply_path_transformation_wrap(
+pub(crate) async fn apply_path_transformation_wrap(
     state: AppStateRef,
     vault: Arc<Vault>,
     item_id: ItemId,
@@ -976,7 +1876,7 @@ async fn apply_path_transformation_wrap(
         .with_context(|| PathContext(path))
 }
 
-async fn apply_image_transformation_wrap(
+pub(crate) async fn apply_image_transformation_wrap(
     state: AppStateRef,
     vault: Arc<Vault>,
     item_id: ItemId,
@@ -989,7 +1889,23 @@ async fn apply_image_transformation_wrap(
         .with_context(|| PathContext(path))
 }
 
-const CONCURRENT_TASKS_LIMIT: usize = 16;
+pub(crate) const CONCURRENT_TASKS_LIMIT: usize = 16;
+
+/// Saves the source vault and, if different, the destination vault of a transformation.
+pub(crate) async fn save_transformed_vaults(
+    state: AppStateRef,
+    vault: Arc<Vault>,
+    bulk: &TransformBulkParams,
+    progress: ProgressSenderRef,
+) -> anyhow::Result<()> {
+    save_vault_and_links(state.clone(), vault, progress.sub_task("Source", 0.5)).await?;
+    if bulk.destination.kind == DestinationKind::OtherVault {
+        if let Ok(other_vault) = state.get_vault(&bulk.destination.other_vault_name) {
+            save_vault_and_links(state, other_vault, progress.sub_task("Destination", 0.5)).await?;
+        }
+    }
+    Ok(())
+}
 
 #[tracing::instrument]
 pub async fn apply_path_transformations(
@@ -1002,6 +1918,7 @@ pub async fn apply_path_transformations(
 ) -> AsyncTaskReturn {
     let bulk = Arc::new(bulk);
     let params = Arc::new(params);
+    let total = item_ids.len();
     let results = process_many(
         item_ids,
         progress.sub_task("Transform", 0.90),
@@ -1024,14 +1941,12 @@ pub async fn apply_path_transformations(
     )
     .await?;
 
-    save_vault_and_links(state.clone(), vault, progress.sub_task("Save", 0.025)).await?;
-    if bulk.destination.kind == DestinationKind::OtherVault {
-        if let Ok(other_vault) = state.get_vault(&bulk.destination.other_vault_name) {
-            save_vault_and_links(state, other_vault, progress.sub_task("Save", 0.025)).await?;
-        }
-    }
+    save_transformed_vaults(state, vault, &bulk, progress.sub_task("Save", 0.05)).await?;
 
-    Ok(AsyncTaskResult::TransformationComplete(results))
+    Ok(AsyncTaskResult::TransformationComplete {
+        cancelled: total - results.len(),
+        results,
+    })
 }
 
 #[tracing::instrument]
@@ -1045,6 +1960,7 @@ pub async fn apply_image_transformations(
 ) -> AsyncTaskReturn {
     let bulk = Arc::new(bulk);
     let params = Arc::new(params);
+    let total = item_ids.len();
     let results = process_many(
         item_ids,
         progress.sub_task("Transform", 0.90),
@@ -1067,14 +1983,12 @@ pub async fn apply_image_transformations(
     )
     .await?;
 
-    save_vault_and_links(state.clone(), vault, progress.sub_task("Save", 0.025)).await?;
-    if bulk.destination.kind == DestinationKind::OtherVault {
-        if let Ok(other_vault) = state.get_vault(&bulk.destination.other_vault_name) {
-            save_vault_and_links(state, other_vault, progress.sub_task("Save", 0.025)).await?;
-        }
-    }
+    save_transformed_vaults(state, vault, &bulk, progress.sub_task("Save", 0.05)).await?;
 
-    Ok(AsyncTaskResult::TransformationComplete(results))
+    Ok(AsyncTaskResult::TransformationComplete {
+        cancelled: total - results.len(),
+        results,
+    })
 }
 
 #[cfg(test)]
@@ -1259,4 +2173,213 @@ mod test {
         test(&p, (1600.0, 200.0), (1600.0, 800.0));
         test(&p, (2000.0, 1000.0), (2000.0, 1000.0));
     }
+
+    #[test]
+    fn test_crop() {
+        let mut options = GeometryOptions {
+            enabled: true,
+            crop: true,
+            crop_aspect_ratio: (1.0.into(), 1.0.into()),
+            ..Default::default()
+        };
+        let crop = |o: &GeometryOptions, size: (f32, f32)| {
+            let rect = get_crop_rect(size, o, source_focal_point(o));
+            (rect.min.x, rect.min.y, rect.width(), rect.height())
+        };
+
+        assert_eq!(crop(&options, (400.0, 200.0)), (100.0, 0.0, 200.0, 200.0));
+        assert_eq!(crop(&options, (200.0, 400.0)), (0.0, 100.0, 200.0, 200.0));
+
+        options.crop_anchor = CropAnchor::Right;
+        assert_eq!(crop(&options, (400.0, 200.0)), (200.0, 0.0, 200.0, 200.0));
+        assert_eq!(crop(&options, (200.0, 400.0)), (0.0, 100.0, 200.0, 200.0));
+
+        options.crop_anchor = CropAnchor::FocalPoint;
+        options.focal_point = Some((0.1.into(), 0.5.into()));
+        assert_eq!(crop(&options, (400.0, 200.0)), (0.0, 0.0, 200.0, 200.0));
+        options.focal_point = Some((0.6.into(), 0.5.into()));
+        assert_eq!(crop(&options, (400.0, 200.0)), (140.0, 0.0, 200.0, 200.0));
+
+        options.rotation = Rotation::Rotate90;
+        options.crop_aspect_ratio = (16.0.into(), 9.0.into());
+        let p = TransformImageParams {
+            geometry: options,
+            ..Default::default()
+        };
+        test(&p, (900.0, 1600.0), (1600.0, 900.0));
+        test(&p, (1000.0, 1000.0), (1000.0, 562.0));
+    }
+
+    #[test]
+    fn test_geometry_frame() {
+        let point = vec2(0.2, 0.1);
+        let close = |a: Vec2, b: Vec2| (a - b).length() < 1e-5;
+
+        let mut frame = GeometryFrame::default();
+        frame.rotate(Rotation::Rotate90);
+        assert!(close(frame.to_output(point), vec2(0.9, 0.2)));
+
+        // EXIF orientation 6 is turned the same way as rotating clockwise
+        let mut oriented = GeometryFrame::default();
+        oriented.orient(6);
+        assert_eq!(oriented, frame);
+
+        frame.0.push(FrameStep::FlipY);
+        frame.crop(
+            vec2(200.0, 100.0),
+            Rect::from_min_size(pos2(100.0, 0.0), vec2(100.0, 100.0)),
+        );
+        let output = frame.to_output(point);
+        assert!(close(output, vec2(0.8, 0.8)), "{output:?}");
+        assert!(close(frame.to_source(output), point));
+
+        let p = TransformImageParams {
+            geometry: GeometryOptions {
+                enabled: true,
+                orientation: Some(8),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        test(&p, (900.0, 1600.0), (1600.0, 900.0));
+    }
+
+    #[test]
+    fn test_smart_crop() {
+        let p = infill_params(|i| {
+            i.target_aspect_ratio = (1.0.into(), 1.0.into());
+            i.technique = InfillTechnique::SmartCrop;
+        });
+        test(&p, (400.0, 200.0), (200.0, 200.0));
+        test(&p, (300.0, 500.0), (300.0, 300.0));
+
+        // a single bright spot in the right half of a wide image
+        let (width, height) = (8, 2);
+        let mut energy = vec![0.0; width * height];
+        energy[6] = 1.0;
+        let centre = find_best_window(&energy, width, height, vec2(2.0, 2.0));
+        assert!(centre.x > 0.5, "{centre:?}");
+        assert!((centre.y - 0.5).abs() < f32::EPSILON);
+
+        let rgba = [[200, 120, 90, 255], [0, 0, 0, 255]].concat();
+        let saliency = get_saliency_map(&rgba, 2, 1);
+        assert!(
+            saliency[0] > saliency[1],
+            "skin tones should be weighted higher"
+        );
+    }
+
+    #[test]
+    fn test_infill_source() {
+        let coords: Vec<_> = (-3..6).map(|i| reflect_coord(i, 3)).collect();
+        assert_eq!(coords, vec![2, 1, 0, 0, 1, 2, 2, 1, 0]);
+
+        assert_eq!(
+            get_infill_source(InfillTechnique::Tile, -1, 4, 3, 3),
+            (2, 1)
+        );
+        assert_eq!(
+            get_infill_source(InfillTechnique::EdgeExtend, -5, 7, 3, 3),
+            (0, 2)
+        );
+    }
+
+    #[test]
+    fn test_automatic_infill() {
+        let best = |stats: &EdgeStats| {
+            score_infill_techniques(stats, 0.02)
+                .into_iter()
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap()
+                .0
+        };
+        let mut stats = EdgeStats {
+            start_mean: [20.0, 20.0, 20.0, 255.0],
+            end_mean: [20.0, 20.0, 20.0, 255.0],
+            spread: 0.0,
+            colour_ratio: 0.5,
+        };
+        assert_eq!(best(&stats), InfillTechnique::Solid);
+
+        stats.end_mean = [200.0, 100.0, 20.0, 255.0];
+        assert_eq!(best(&stats), InfillTechnique::Gradient);
+
+        stats.spread = 0.2;
+        assert_eq!(best(&stats), InfillTechnique::Blur);
+
+        stats.colour_ratio = 0.001;
+        assert_eq!(best(&stats), InfillTechnique::Mirror);
+
+        let rgba = [
+            [0, 0, 0, 255],
+            [0, 0, 0, 255],
+            [255, 255, 255, 255],
+            [255, 255, 255, 255],
+        ]
+        .concat();
+        let stats = get_edge_stats(&rgba, 2, 2, true);
+        assert!(colour_distance(stats.start_mean, [0.0, 0.0, 0.0, 255.0]) < f32::EPSILON);
+        assert!(colour_distance(stats.end_mean, [255.0, 255.0, 255.0, 255.0]) < f32::EPSILON);
+        assert!(stats.spread.abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_search_quality() {
+        let search = |limit: u16| {
+            let mut attempts = 0;
+            let res = search_quality(10, 90, |q| {
+                attempts += 1;
+                Ok(q <= limit)
+            })
+            .unwrap();
+            assert!(attempts <= 7);
+            res
+        };
+        assert_eq!(search(55), Some(55));
+        assert_eq!(search(90), Some(90));
+        assert_eq!(search(100), Some(90));
+        assert_eq!(search(10), Some(10));
+        assert_eq!(search(5), None);
+        assert_eq!(search_quality(0, 10, |_| Ok(false)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_output_path() {
+        let mut options = CompressionOptions::default();
+        let path = Path::new("photos/image.png");
+        assert_eq!(get_output_path(path, &options), path);
+
+        options.enabled = true;
+        options.file_type = CompressionFileType::JpegXl;
+        assert_eq!(
+            get_output_path(path, &options),
+            Path::new("photos/image.jxl")
+        );
+        options.file_type = CompressionFileType::WebpLossless;
+        assert_eq!(
+            get_output_path(path, &options),
+            Path::new("photos/image.webp")
+        );
+    }
+
+    #[test]
+    fn test_output_path_exists() {
+        let dir = tempfile::tempdir().unwrap();
+        let vault = Vault::new("test".to_string()).with_file_path(&dir.path().join("test.riiman"));
+        fs::write(dir.path().join("a.jpg"), []).unwrap();
+        vault.get_item_or_init(Path::new("b.jpg")).unwrap();
+
+        assert_eq!(
+            unique_output_path(&vault, Path::new("a.jpg")).unwrap(),
+            Path::new("a (2).jpg")
+        );
+        assert_eq!(
+            unique_output_path(&vault, Path::new("b.jpg")).unwrap(),
+            Path::new("b (2).jpg")
+        );
+        assert_eq!(
+            unique_output_path(&vault, Path::new("c.jpg")).unwrap(),
+            Path::new("c.jpg")
+        );
+    }
 }
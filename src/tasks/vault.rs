@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
 
@@ -5,7 +6,7 @@ use anyhow::Context;
 use itertools::Itertools;
 use tokio::task::block_in_place;
 
-use crate::data::Vault;
+use crate::data::{DefinitionImport, Vault};
 use crate::errors::AppError;
 use crate::state::AppStateRef;
 use crate::tasks::{AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, ProgressState};
@@ -50,6 +51,36 @@ pub async fn choose_and_load_vault(
     }
 }
 
+/// Moves usernames and passwords that older versions stored in plain text in a subscription
+/// into the credential store. Returns whether any were moved, in which case the vault should be
+/// saved so that the plain text copies are removed from disk. Logins that can't be moved (e.g.
+/// because the credential file is locked) are never written back and will have to be re-entered.
+fn migrate_legacy_logins(state: &AppStateRef, vault: &Vault) -> bool {
+    let subscriptions = vault
+        .iter_subscriptions()
+        .map(|s| s.value().clone())
+        .collect_vec();
+    let mut migrated = false;
+    for mut subscription in subscriptions {
+        match subscription
+            .params
+            .login
+            .migrate_legacy(state.credentials(), &subscription.name)
+        {
+            Ok(true) => {
+                vault.set_subscription(subscription);
+                migrated = true;
+            }
+            Ok(false) => {}
+            Err(e) => tracing::warn!(
+                "could not migrate the login for subscription {}: {e:#}",
+                subscription.name
+            ),
+        }
+    }
+    migrated
+}
+
 #[tracing::instrument]
 pub async fn load_vault_from_path(
     path: String,
@@ -67,10 +98,20 @@ pub async fn load_vault_from_path(
         .with_context(|| format!("while deserialising vault file at {path}"))?
         .with_file_path(Path::new(&path))
         .with_id_lookup()
-        .with_standard_defs();
+        .with_standard_defs()
+        .with_tag_stats();
+    let jobs_res = crate::tasks::job::load_jobs(&vault).await;
+    let _ = state.catch(
+        || format!("loading the jobs of {}", vault.name),
+        || jobs_res,
+    );
+    let migrated_logins = migrate_legacy_logins(&state, &vault);
 
     let name = vault.name.clone();
     state.load_vault(vault, set_as_current);
+    if migrated_logins {
+        state.save_vault_by_name_deferred(&name);
+    }
 
     Ok(AsyncTaskResult::VaultLoaded {
         name,
@@ -114,6 +155,7 @@ pub async fn save_vault(vault: Arc<Vault>, progress: ProgressSenderRef) -> Async
 pub async fn save_new_vault(
     state: AppStateRef,
     name: String,
+    template: Option<Arc<Vault>>,
     progress: ProgressSenderRef,
 ) -> AsyncTaskReturn {
     let dialog = rfd::AsyncFileDialog::new()
@@ -125,6 +167,15 @@ pub async fn save_new_vault(
     let mut vault = Vault::new(name);
     vault.set_file_path(path);
 
+    if let Some(template) = template {
+        let source: HashMap<_, _> = template
+            .iter_field_defs()
+            .map(|def| (def.id, def.clone()))
+            .collect();
+        let root_ids = source.keys().copied().collect_vec();
+        DefinitionImport::plan(&source, root_ids, &vault)?.apply(&vault);
+    }
+
     let vault = Arc::new(vault);
     save_vault(vault.clone(), progress).await?;
 
@@ -0,0 +1,174 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Context};
+use regex::bytes;
+use regex::Regex;
+
+/// Embedded XMP packets are near the start of most image formats, so only this much of an image
+/// is searched for one.
+const EMBEDDED_SCAN_LIMIT: u64 = 1024 * 1024;
+
+const XMP_NAMESPACE: &str = "http://ns.adobe.com/xap/1.0/";
+
+const RATING_PATTERN: &str =
+    r#"xmp:Rating\s*=\s*["'](-?[0-9.]+)["']|<xmp:Rating>\s*(-?[0-9.]+)\s*</xmp:Rating>"#;
+
+fn rating_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(RATING_PATTERN).unwrap())
+}
+
+fn rating_bytes_regex() -> &'static bytes::Regex {
+    static RE: OnceLock<bytes::Regex> = OnceLock::new();
+    RE.get_or_init(|| bytes::Regex::new(RATING_PATTERN).unwrap())
+}
+
+/// Both `image.xmp` and `image.jpg.xmp` are in common use.
+fn sidecar_paths(path: &Path) -> [PathBuf; 2] {
+    let extension = match path.extension().and_then(|ext| ext.to_str()) {
+        None => "xmp".to_string(),
+        Some(ext) => format!("{ext}.xmp"),
+    };
+    [path.with_extension("xmp"), path.with_extension(extension)]
+}
+
+fn find_sidecar(path: &Path) -> Option<PathBuf> {
+    sidecar_paths(path).into_iter().find(|p| p.is_file())
+}
+
+/// Unrated (0) and rejected (-1) items are treated as having no rating.
+#[allow(clippy::cast_possible_truncation)]
+fn rating_from_str(s: &str) -> Option<i64> {
+    let rating = s.parse::<f64>().ok()?.round() as i64;
+    (rating > 0).then_some(rating.min(5))
+}
+
+fn parse_rating(packet: &[u8]) -> Option<i64> {
+    let captures = rating_bytes_regex().captures(packet)?;
+    let value = captures.get(1).or_else(|| captures.get(2))?;
+    rating_from_str(std::str::from_utf8(value.as_bytes()).ok()?)
+}
+
+fn read_embedded_rating(path: &Path) -> anyhow::Result<Option<i64>> {
+    let mut buf = vec![];
+    std::fs::File::open(path)?
+        .take(EMBEDDED_SCAN_LIMIT)
+        .read_to_end(&mut buf)?;
+
+    let Some(start) = find_bytes(&buf, b"<x:xmpmeta") else {
+        return Ok(None);
+    };
+    let end = find_bytes(&buf[start..], b"</x:xmpmeta>").map_or(buf.len(), |i| start + i);
+    Ok(parse_rating(&buf[start..end]))
+}
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Reads the `xmp:Rating` of an image from its XMP sidecar if it has one, or otherwise from the
+/// XMP packet embedded in the image.
+pub fn read_rating(path: &Path) -> anyhow::Result<Option<i64>> {
+    match find_sidecar(path) {
+        Some(sidecar_path) => {
+            let packet = std::fs::read(&sidecar_path)
+                .with_context(|| format!("while reading {}", sidecar_path.display()))?;
+            Ok(parse_rating(&packet))
+        }
+        None => read_embedded_rating(path)
+            .with_context(|| format!("while reading XMP metadata of {}", path.display())),
+    }
+}
+
+fn new_packet(rating: i64) -> String {
+    format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n \
+         <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n  \
+         <rdf:Description rdf:about=\"\"\n    \
+         xmlns:xmp=\"{XMP_NAMESPACE}\"\n    \
+         xmp:Rating=\"{rating}\"/>\n \
+         </rdf:RDF>\n\
+         </x:xmpmeta>\n\
+         <?xpacket end=\"w\"?>\n"
+    )
+}
+
+/// Sets the rating in an existing packet, keeping everything else as it is.
+fn set_rating_in_packet(packet: &str, rating: i64) -> Option<String> {
+    const DESCRIPTION: &str = "<rdf:Description";
+
+    if let Some(captures) = rating_regex().captures(packet) {
+        let value = captures.get(1).or_else(|| captures.get(2))?;
+        return Some(format!(
+            "{}{rating}{}",
+            &packet[..value.start()],
+            &packet[value.end()..]
+        ));
+    }
+
+    let insert_at = packet.find(DESCRIPTION)? + DESCRIPTION.len();
+    let namespace = if packet.contains("xmlns:xmp=") {
+        String::new()
+    } else {
+        format!(" xmlns:xmp=\"{XMP_NAMESPACE}\"")
+    };
+    Some(format!(
+        "{}{namespace} xmp:Rating=\"{rating}\"{}",
+        &packet[..insert_at],
+        &packet[insert_at..]
+    ))
+}
+
+/// Writes the `xmp:Rating` of an image into its XMP sidecar, creating one next to the image if
+/// it doesn't have one yet.
+pub fn write_rating(path: &Path, rating: i64) -> anyhow::Result<()> {
+    let (sidecar_path, contents) = if let Some(sidecar_path) = find_sidecar(path) {
+        let packet = std::fs::read_to_string(&sidecar_path)
+            .with_context(|| format!("while reading {}", sidecar_path.display()))?;
+        let contents = set_rating_in_packet(&packet, rating)
+            .ok_or_else(|| anyhow!("{} is not an XMP packet", sidecar_path.display()))?;
+        (sidecar_path, contents)
+    } else {
+        let [_, sidecar_path] = sidecar_paths(path);
+        (sidecar_path, new_packet(rating))
+    };
+
+    std::fs::write(&sidecar_path, contents)
+        .with_context(|| format!("while writing {}", sidecar_path.display()))
+}
+
+/// Whether the image has an XMP sidecar that [`write_rating`] would update.
+pub fn has_sidecar(path: &Path) -> bool {
+    find_sidecar(path).is_some()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rating_packets() {
+        let packet = new_packet(4);
+        assert_eq!(parse_rating(packet.as_bytes()), Some(4));
+        assert_eq!(parse_rating(b"<xmp:Rating>2.0</xmp:Rating>"), Some(2));
+        assert_eq!(parse_rating(b"xmp:Rating='-1'"), None);
+        assert_eq!(parse_rating(b"<x:xmpmeta></x:xmpmeta>"), None);
+
+        let updated = set_rating_in_packet(&packet, 1).unwrap();
+        assert_eq!(parse_rating(updated.as_bytes()), Some(1));
+        assert_eq!(updated.len(), packet.len());
+
+        let no_rating = "<rdf:RDF><rdf:Description rdf:about=\"\"/></rdf:RDF>";
+        let updated = set_rating_in_packet(no_rating, 3).unwrap();
+        assert_eq!(parse_rating(updated.as_bytes()), Some(3));
+        assert!(updated.contains(XMP_NAMESPACE));
+        assert_eq!(set_rating_in_packet("<html/>", 3), None);
+
+        let [plain, with_ext] = sidecar_paths(Path::new("dir/image.jpg"));
+        assert_eq!(plain, Path::new("dir/image.xmp"));
+        assert_eq!(with_ext, Path::new("dir/image.jpg.xmp"));
+    }
+}
@@ -1,9 +1,18 @@
 use crate::data::parse::FilterExpressionParseResult;
 use crate::data::transform::DestinationExistingBehaviour;
-use crate::data::{FilterExpression, ShortcutBehaviour, ThumbnailCacheItem};
+use crate::data::{
+    FilterExpression, ItemId, ShortcutBehaviour, ShortcutProfile, ShortcutProfileRef, TaskLog,
+    TaskLogEntry, ThumbnailCacheItem, TransformPreset,
+};
 use crate::errors::AppError;
+use crate::fields;
+use crate::server::ApiServerSettings;
 use crate::state::{AppState, AppStateRef, TaskInfo};
-use crate::tasks::{AsyncTaskResult, AsyncTaskReturn, ProgressSenderRef, ProgressState, TaskState};
+use crate::tasks::{
+    AsyncTaskResult, AsyncTaskReturn, FinishedTask, ProgressSenderRef, ProgressState,
+    SingleImportResult, TaskState,
+};
+use chrono::Utc;
 use eframe::egui;
 use eframe::egui::{vec2, FontData, FontDefinitions, KeyboardShortcut};
 use eframe::epaint::FontFamily;
@@ -15,6 +24,7 @@ use std::sync::{Arc, OnceLock};
 use tracing::{error, info};
 use uuid::Uuid;
 
+use crate::tasks::preset::{preset_source_ids, run_preset};
 use crate::tasks::sort::{SortDirection, SortExpression, SortType};
 use crate::tasks::transform::load_image_preview;
 use crate::time;
@@ -101,6 +111,7 @@ pub(crate) struct App {
     sort_field_id: Option<Uuid>,
     sort_direction: SortDirection,
     search_text: String,
+    inbox_dir: String,
 
     expand_right_panel: bool,
 }
@@ -114,7 +125,16 @@ struct AppStorage {
     sorts: Vec<SortExpression>,
     filter: FilterExpression,
     search_text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     shortcuts2: Vec<(KeyboardShortcut, ShortcutBehaviour)>,
+    shortcut_profiles: Vec<ShortcutProfile>,
+    active_shortcut_profile: ShortcutProfileRef,
+    #[serde(deserialize_with = "TransformPreset::deserialize_list_lenient")]
+    transform_presets: Vec<TransformPreset>,
+    api_server: ApiServerSettings,
+    inbox_dir: String,
+    #[serde(deserialize_with = "TaskLog::deserialize_lenient")]
+    task_log: TaskLog,
 }
 
 impl AppStorage {
@@ -127,11 +147,12 @@ impl App {
             state: AppStateRef::new(AppState::default()),
             tasks: Default::default(),
             modal_dialogs: Default::default(),
-            thumbnail_grid: ThumbnailGrid::new("main_thumbnail_grid"),
+            thumbnail_grid: ThumbnailGrid::new("main_thumbnail_grid").with_context_menu(),
             sort_type: Default::default(),
             sort_field_id: None,
             sort_direction: Default::default(),
             search_text: String::new(),
+            inbox_dir: String::new(),
             expand_right_panel: false,
         }
     }
@@ -152,8 +173,12 @@ impl App {
         for info in self.state.drain_tasks(capacity) {
             let s = self.state.clone();
             match info {
-                TaskInfo::GlobalTask { name, task_factory } => {
-                    self.tasks.add(name, |tx| task_factory(s, tx));
+                TaskInfo::GlobalTask {
+                    name,
+                    vault_name,
+                    task_factory,
+                } => {
+                    self.tasks.add(name, vault_name, |tx| task_factory(s, tx));
                 }
                 TaskInfo::GlobalMessage { result } => {
                     self.tasks.push_message(result);
@@ -179,6 +204,25 @@ impl App {
         self.add_modal_dialog(modals::Message::error(message));
     }
 
+    fn offer_overdue_subscriptions(&mut self, vault_name: String) {
+        let Ok(vault) = self.state.get_vault(&vault_name) else {
+            return;
+        };
+        let ids = vault.overdue_subscription_ids(Utc::now());
+        if !ids.is_empty() {
+            self.add_modal_dialog(modals::Subscriptions::overdue(vault_name, ids));
+        }
+    }
+
+    fn offer_unfinished_jobs(&mut self, vault_name: String) {
+        let Ok(vault) = self.state.get_vault(&vault_name) else {
+            return;
+        };
+        if vault.jobs().len_unfinished() > 0 {
+            self.add_modal_dialog(modals::Jobs::resume(vault_name));
+        }
+    }
+
     fn success(&mut self, title: String, message: String) {
         self.add_modal_dialog(modals::Message::success(message).with_title(title));
     }
@@ -209,12 +253,35 @@ impl App {
         }
 
         self.search_text = stored_state.search_text;
+        self.inbox_dir = stored_state.inbox_dir;
 
         self.thumbnail_grid.params.init_row_height = stored_state.thumbnail_row_height;
 
         self.state
             .set_filter_and_sorts(stored_state.filter, stored_state.sorts);
-        self.state.set_shortcuts(stored_state.shortcuts2);
+        if stored_state.shortcut_profiles.is_empty() {
+            // bindings saved before profiles existed become the default profile
+            self.state.set_shortcuts(stored_state.shortcuts2);
+        } else {
+            self.state
+                .set_app_shortcut_profiles(stored_state.shortcut_profiles);
+        }
+        if !stored_state.active_shortcut_profile.name.is_empty() {
+            self.state
+                .set_active_shortcut_profile(stored_state.active_shortcut_profile);
+        }
+
+        self.state
+            .set_transform_presets(stored_state.transform_presets);
+        self.state.set_task_log(stored_state.task_log);
+
+        let api_server = stored_state.api_server;
+        self.state.add_global_task("Start API server", |s, _| {
+            Promise::spawn_async(async move {
+                s.set_api_server_settings(api_server).await?;
+                Ok(AsyncTaskResult::None)
+            })
+        });
 
         Some(())
     }
@@ -225,11 +292,141 @@ impl App {
         self.load_persistent_state(storage);
     }
 
+    fn import_complete(&mut self, path: &Path, results: &[SingleImportResult]) {
+        let total = results.len();
+        let success = results.iter().filter(|r| r.is_ok()).count();
+        let body = format!(
+            "Import of {} complete. {success}/{total} images imported successfully.",
+            path.display()
+        );
+        // update thumbnail grid
+        self.thumbnail_grid.params.container_width = 0.0;
+        self.success("Import complete".to_string(), body);
+    }
+
+    fn link_complete(&mut self, other_vault_name: &str, results: &[SingleImportResult]) {
+        self.state.save_current_vault_deferred();
+        self.state.save_vault_by_name_deferred(other_vault_name);
+
+        let total = results.len();
+        let success = results.iter().filter(|r| r.is_ok()).count();
+        let body = format!(
+            "Link to vault {other_vault_name} complete. \
+            {success}/{total} images linked successfully.",
+        );
+        self.success("Link complete".to_string(), body);
+    }
+
+    fn batch_complete(&mut self, title: String, results: &[SingleImportResult]) {
+        let total = results.len();
+        let success = results.iter().filter(|r| r.is_ok()).count();
+        let mut body = format!("{success}/{total} items processed successfully.");
+        if success < total {
+            body.push_str(" The items that failed are listed in the task history.");
+        }
+        self.success(title, body);
+    }
+
+    fn select_imported(&mut self, ctx: &egui::Context, results: &[SingleImportResult]) {
+        let Some(vault) = self.state.current_vault_opt() else {
+            return;
+        };
+        let item_ids = results
+            .iter()
+            .filter_map(|r| vault.get_item_opt(r.as_ref().ok()?).ok().flatten())
+            .map(|item| ItemId::from_item(&vault, &item))
+            .collect::<Vec<_>>();
+        self.thumbnail_grid.select_items(ctx, &item_ids);
+    }
+
+    fn import_into_inbox(&mut self, paths: Vec<PathBuf>) {
+        let Ok(vault) = self.state.current_vault_catch() else {
+            return;
+        };
+        let inbox_dir = self.inbox_dir.clone();
+        self.add_task("Import dropped files", |_, p| {
+            Promise::spawn_async(crate::tasks::import::import_into_inbox(
+                vault, paths, inbox_dir, p,
+            ))
+        });
+    }
+
+    fn paste_from_clipboard(&mut self) {
+        let Ok(vault) = self.state.current_vault_catch() else {
+            return;
+        };
+        let inbox_dir = self.inbox_dir.clone();
+        self.add_task("Import from clipboard", |_, p| {
+            Promise::spawn_async(crate::tasks::import::import_from_clipboard(
+                vault, inbox_dir, p,
+            ))
+        });
+    }
+
+    fn drop_and_paste_ui(&mut self, ctx: &egui::Context) {
+        let (hovering, dropped_paths) = ctx.input(|i| {
+            (
+                !i.raw.hovered_files.is_empty(),
+                i.raw
+                    .dropped_files
+                    .iter()
+                    .filter_map(|f| f.path.clone())
+                    .collect::<Vec<_>>(),
+            )
+        });
+
+        if hovering {
+            let text = match self.state.current_vault_opt() {
+                Some(vault) => format!("Drop files to import them into {}", vault.name),
+                None => "Open a vault to import files".to_string(),
+            };
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                "drop_overlay".into(),
+            ));
+            let rect = ctx.screen_rect();
+            painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(192));
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                text,
+                egui::FontId::proportional(24.0),
+                egui::Color32::WHITE,
+            );
+        }
+
+        if !dropped_paths.is_empty() {
+            self.import_into_inbox(dropped_paths);
+        }
+
+        // egui only emits paste events for text, so the key press is also checked for in case the
+        // clipboard only holds an image
+        let text_has_focus =
+            ctx.memory(|m| m.focused()).is_some() && !self.thumbnail_grid.has_focus();
+        let pasted = !text_has_focus
+            && ctx.input_mut(|i| {
+                i.events.iter().any(|e| matches!(e, egui::Event::Paste(_)))
+                    || i.consume_key(egui::Modifiers::COMMAND, egui::Key::V)
+            });
+        if pasted {
+            self.paste_from_clipboard();
+        }
+    }
+
     fn process_tasks(&mut self, ctx: &egui::Context) {
         self.add_queued_tasks();
 
         let (results, request_results) = self.tasks.iter_ready();
-        for result in results {
+        for FinishedTask {
+            name,
+            vault_name,
+            started,
+            result,
+        } in results
+        {
+            if let Some(entry) = TaskLogEntry::new(name, vault_name, started, &result) {
+                self.state.record_task(entry);
+            }
             match result {
                 Ok(
                     AsyncTaskResult::None
@@ -237,6 +434,8 @@ impl App {
                     | AsyncTaskResult::SelectedDirectory(_)
                     | AsyncTaskResult::SelectedFile(_)
                     | AsyncTaskResult::QueryResult(_)
+                    | AsyncTaskResult::VaultStats(_)
+                    | AsyncTaskResult::DefinitionsLoaded { .. }
                     | AsyncTaskResult::NextItem,
                 ) => {}
                 Ok(AsyncTaskResult::VaultLoaded {
@@ -250,35 +449,24 @@ impl App {
                              as it could not be found"
                         ));
                     }
+                    self.offer_overdue_subscriptions(name.clone());
+                    self.offer_unfinished_jobs(name);
                 }
                 Ok(AsyncTaskResult::VaultLoaded { .. } | AsyncTaskResult::VaultSaved(_)) => {
                     self.state.reset_vault_loading();
                 }
                 Ok(AsyncTaskResult::ImportComplete { path, results }) => {
-                    let total = results.len();
-                    let success = results.iter().filter(|r| r.is_ok()).count();
-                    let body = format!(
-                        "Import of {} complete. {success}/{total} images imported successfully.",
-                        path.display()
-                    );
-                    // update thumbnail grid
-                    self.thumbnail_grid.params.container_width = 0.0;
-                    self.success("Import complete".to_string(), body);
+                    self.import_complete(&path, &results);
+                }
+                Ok(AsyncTaskResult::InboxImportComplete { path, results }) => {
+                    self.import_complete(&path, &results);
+                    self.select_imported(ctx, &results);
                 }
                 Ok(AsyncTaskResult::LinkComplete {
                     other_vault_name,
                     results,
                 }) => {
-                    self.state.save_current_vault_deferred();
-                    self.state.save_vault_by_name_deferred(&other_vault_name);
-
-                    let total = results.len();
-                    let success = results.iter().filter(|r| r.is_ok()).count();
-                    let body = format!(
-                        "Link to vault {other_vault_name} complete. \
-                        {success}/{total} images linked successfully.",
-                    );
-                    self.success("Link complete".to_string(), body);
+                    self.link_complete(&other_vault_name, &results);
                 }
                 Ok(AsyncTaskResult::ThumbnailLoaded { params, image }) => {
                     let hndl =
@@ -302,9 +490,13 @@ impl App {
                     );
                     self.add_modal_dialog(modals::Preview::new(id, hndl, *viewport_class));
                 }
-                Ok(AsyncTaskResult::TransformationComplete(results)) => {
-                    self.add_modal_dialog(modals::TransformResults::new(results));
+                Ok(AsyncTaskResult::BatchComplete { title, results }) => {
+                    self.batch_complete(title, &results);
+                }
+                Ok(AsyncTaskResult::TransformationComplete { results, cancelled }) => {
+                    self.add_modal_dialog(modals::TransformResults::new(results, cancelled));
                 }
+                Err(e) if AppError::UserCancelled.is_err(&e) => {}
                 Err(e) if AppError::NotImplemented.is_err(&e) => {
                     self.error("Not implemented".to_string());
                 }
@@ -374,6 +566,8 @@ impl App {
                 }
             }
 
+            self.vault_tools_menu_ui(ui, vault_loading);
+
             let manage_text = if self.state.has_unresolved_vaults() {
                 egui::RichText::new("Manage... \u{ff01}").color(theme::ERROR_TEXT)
             } else {
@@ -398,6 +592,83 @@ impl App {
         });
     }
 
+    fn vault_tools_menu_ui(&mut self, ui: &mut egui::Ui, vault_loading: bool) {
+        let enabled = !vault_loading && self.state.current_vault().is_ok();
+
+        if ui
+            .add_enabled(enabled, egui::Button::new("Statistics..."))
+            .clicked()
+        {
+            self.add_modal_dialog(modals::VaultStatistics::default());
+
+            ui.close_menu();
+        }
+
+        if ui
+            .add_enabled(enabled, egui::Button::new("Export items..."))
+            .clicked()
+        {
+            self.add_modal_dialog(modals::ExportItems::default());
+
+            ui.close_menu();
+        }
+
+        if ui
+            .add_enabled(enabled, egui::Button::new("Subscriptions..."))
+            .clicked()
+        {
+            self.add_modal_dialog(modals::Subscriptions::default());
+
+            ui.close_menu();
+        }
+
+        if ui
+            .add_enabled(enabled, egui::Button::new("Jobs..."))
+            .clicked()
+        {
+            self.add_modal_dialog(modals::Jobs::default());
+
+            ui.close_menu();
+        }
+
+        if ui
+            .add_enabled(enabled, egui::Button::new("Extract colour palettes"))
+            .on_hover_text("Find the dominant colours of images that don't have them yet")
+            .clicked()
+        {
+            if let Ok(vault) = self.state.current_vault_catch() {
+                self.add_task("Extract colour palettes", |_, p| {
+                    Promise::spawn_async(crate::tasks::palette::extract_missing_palettes(vault, p))
+                });
+            }
+
+            ui.close_menu();
+        }
+
+        if ui
+            .add_enabled(enabled, egui::Button::new("Merge..."))
+            .clicked()
+        {
+            self.add_modal_dialog(modals::MergeVault::default());
+
+            ui.close_menu();
+        }
+
+        if ui
+            .add_enabled(enabled, egui::Button::new("Split current view..."))
+            .clicked()
+        {
+            if let Ok(vault) = self.state.current_vault_catch() {
+                let filter = self.state.filter().clone();
+                self.add_task(format!("Split {} vault", vault.name), |s, p| {
+                    Promise::spawn_async(crate::tasks::merge::split_vault(s, vault, filter, p))
+                });
+            }
+
+            ui.close_menu();
+        }
+    }
+
     fn import_menu_ui(&mut self, ui: &mut egui::Ui) {
         ui.menu_button("Import", |ui| -> Result<(), ()> {
             if ui.button("Import...").clicked() {
@@ -413,8 +684,10 @@ impl App {
                 info!("Import all clicked!");
 
                 let vault = self.state.current_vault_catch()?;
-                self.add_task("Import to vault", |_, p| {
-                    Promise::spawn_async(crate::tasks::import::import_images_recursively(vault, p))
+                self.add_task("Import to vault", |s, p| {
+                    Promise::spawn_async(crate::tasks::import::import_images_recursively(
+                        s, vault, p,
+                    ))
                 });
 
                 ui.close_menu();
@@ -426,6 +699,31 @@ impl App {
                 ui.close_menu();
             }
 
+            if ui.button("Paste from clipboard").clicked() {
+                self.paste_from_clipboard();
+
+                ui.close_menu();
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Inbox folder:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.inbox_dir)
+                        .hint_text(crate::tasks::import::DEFAULT_INBOX_DIR)
+                        .desired_width(120.0),
+                );
+            })
+            .response
+            .on_hover_text("Dropped and pasted files are copied to this folder in the vault");
+
+            ui.separator();
+
+            if ui.button("API server...").clicked() {
+                self.add_modal_dialog(modals::ApiServer::default());
+
+                ui.close_menu();
+            }
+
             Ok(())
         });
     }
@@ -440,10 +738,38 @@ impl App {
                 self.add_modal_dialog(modals::EditTag::select());
                 ui.close_menu();
             }
+            if ui.button("Import...").clicked() {
+                self.add_modal_dialog(modals::ImportDefinitions::default());
+                ui.close_menu();
+            }
+            if ui.button("Export...").clicked() {
+                if let Ok(vault) = self.state.current_vault_catch() {
+                    self.add_task("Export definitions", |_, p| {
+                        Promise::spawn_async(crate::tasks::definitions::export_definitions(
+                            vault, p,
+                        ))
+                    });
+                }
+                ui.close_menu();
+            }
+            if ui.button("Review queue...").clicked() {
+                self.add_modal_dialog(modals::ReviewQueue::default());
+                ui.close_menu();
+            }
             if ui.button("Shortcuts...").clicked() {
                 self.add_modal_dialog(modals::TagShortcuts::default());
                 ui.close_menu();
             }
+            ui.menu_button("Shortcut profile", |ui| {
+                let active = self.state.active_shortcut_profile();
+                for profile_ref in self.state.shortcut_profiles() {
+                    let label = profile_ref.to_string();
+                    if ui.radio(profile_ref == active, label).clicked() {
+                        self.state.set_active_shortcut_profile(profile_ref);
+                        ui.close_menu();
+                    }
+                }
+            });
         });
     }
 
@@ -504,6 +830,26 @@ impl App {
                 self.add_modal_dialog(modals::TransformPaths::default());
                 ui.close_menu();
             }
+            ui.menu_button("Run preset", |ui| {
+                let presets = self.state.transform_presets();
+                if presets.is_empty() {
+                    ui.weak("No presets saved");
+                }
+                for preset in presets {
+                    let source = preset.params.bulk().source.kind;
+                    if ui
+                        .button(&preset.name)
+                        .on_hover_text(source.to_string())
+                        .clicked()
+                    {
+                        if let Ok(vault) = self.state.current_vault_catch() {
+                            let item_ids = preset_source_ids(&self.state, &vault, &preset);
+                            run_preset(&self.state, vault, item_ids, preset);
+                        }
+                        ui.close_menu();
+                    }
+                }
+            });
             if ui.button("Tags...").clicked() {
                 ui.close_menu();
             }
@@ -582,6 +928,8 @@ impl App {
                         .show_ui(ui, |ui| {
                             choice(ui, sort_type, SortType::Path);
                             choice(ui, sort_type, SortType::Field);
+                            choice(ui, sort_type, SortType::Rating);
+                            choice(ui, sort_type, SortType::Favourite);
 
                             ui.style_mut().visuals.widgets.inactive.rounding.ne = 0.0;
                             ui.style_mut().visuals.widgets.inactive.rounding.se = 0.0;
@@ -594,6 +942,7 @@ impl App {
                     widgets::SearchBox::new("main_search_box", &mut self.search_text, vault)
                         .desired_width(f32::INFINITY)
                         .interactive()
+                        .colour_search()
                         .show(ui);
 
                     let sorts = match self.sort_type {
@@ -605,6 +954,14 @@ impl App {
                                 vec![]
                             }
                         }
+                        SortType::Rating => vec![SortExpression::Field(
+                            fields::general::RATING.id,
+                            self.sort_direction,
+                        )],
+                        SortType::Favourite => vec![SortExpression::Field(
+                            fields::general::FAVOURITE.id,
+                            self.sort_direction,
+                        )],
                     };
 
                     let filter = self
@@ -628,31 +985,55 @@ impl App {
                     crate::built_info::built_time()
                 ));
 
-                #[allow(clippy::cast_possible_truncation)]
-                #[allow(clippy::cast_sign_loss)]
-                match &self.tasks.iter_progress()[..] {
-                    [] => {
-                        return;
-                    }
-                    [(name, ProgressState::NotStarted | ProgressState::Indeterminate), ..] => {
-                        ui.add(egui::ProgressBar::new(0.0).text(name).animate(true));
-                    }
-                    [(name, ProgressState::Determinate(progress)), ..] => {
-                        ui.add(
-                            egui::ProgressBar::new(*progress)
-                                .text(format!("{}% {name}", (progress * 100.0).floor() as u32)),
-                        );
-                    }
-                    [(name, ProgressState::DeterminateWithMessage(progress, msg)), ..] => {
-                        ui.add(egui::ProgressBar::new(*progress).text(format!(
-                            "{}% {name}: {msg}",
-                            (progress * 100.0).floor() as u32
-                        )));
-                    }
-                    [(name, ProgressState::Completed), ..] => {
-                        ui.add(egui::ProgressBar::new(1.0).text(name));
+                if ui
+                    .button("History")
+                    .on_hover_text("Show the results of finished tasks")
+                    .clicked()
+                {
+                    self.add_modal_dialog(modals::TaskLog::default());
+                }
+
+                let progresses = self.tasks.iter_progress();
+                if progresses.is_empty() {
+                    return;
+                }
+
+                #[allow(clippy::cast_precision_loss)]
+                let bar_width = ui.available_width() / progresses.len() as f32 - 32.0;
+                for (name, state, cancellation) in progresses {
+                    let cancelling = cancellation.is_cancelled();
+                    if ui
+                        .add_enabled(!cancelling, egui::Button::new("\u{2716}").small())
+                        .on_hover_text("Cancel")
+                        .clicked()
+                    {
+                        cancellation.cancel();
                     }
-                };
+
+                    let name = if cancelling {
+                        format!("{name} (cancelling)")
+                    } else {
+                        name
+                    };
+
+                    #[allow(clippy::cast_possible_truncation)]
+                    #[allow(clippy::cast_sign_loss)]
+                    let bar = match state {
+                        ProgressState::NotStarted | ProgressState::Indeterminate => {
+                            egui::ProgressBar::new(0.0).text(name).animate(true)
+                        }
+                        ProgressState::Determinate(progress) => egui::ProgressBar::new(progress)
+                            .text(format!("{}% {name}", (progress * 100.0).floor() as u32)),
+                        ProgressState::DeterminateWithMessage(progress, msg) => {
+                            egui::ProgressBar::new(progress).text(format!(
+                                "{}% {name}: {msg}",
+                                (progress * 100.0).floor() as u32
+                            ))
+                        }
+                        ProgressState::Completed => egui::ProgressBar::new(1.0).text(name),
+                    };
+                    ui.add(bar.desired_width(bar_width));
+                }
 
                 ctx.request_repaint();
             });
@@ -795,11 +1176,21 @@ impl eframe::App for App {
             self.modal_dialogs.insert(new_dialog.id(), new_dialog);
         }
 
+        if let Some(search_text) = self.state.take_search_text_request() {
+            self.search_text = search_text;
+        }
+
+        if let Some(item_ids) = self.state.take_selection_request() {
+            self.thumbnail_grid.select_items(ctx, &item_ids);
+        }
+
         self.modal_dialogs
             .retain(|_, dialog| dialog.update_or_dispose(ctx, self.state.clone()));
 
         self.process_tasks(ctx);
 
+        self.drop_and_paste_ui(ctx);
+
         self.top_panel_ui(ctx);
 
         self.search_panel_ui(ctx);
@@ -817,7 +1208,13 @@ impl eframe::App for App {
             sorts: self.state.sorts().clone(),
             filter: self.state.filter().clone(),
             search_text: self.search_text.clone(),
-            shortcuts2: self.state.shortcuts(),
+            shortcuts2: vec![],
+            shortcut_profiles: self.state.app_shortcut_profiles(),
+            active_shortcut_profile: self.state.active_shortcut_profile(),
+            transform_presets: self.state.transform_presets(),
+            api_server: self.state.api_server_settings(),
+            inbox_dir: self.inbox_dir.clone(),
+            task_log: self.state.task_log().clone(),
         };
 
         storage.set_string(
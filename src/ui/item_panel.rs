@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::ops::Deref;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
@@ -5,21 +6,33 @@ use std::sync::{Arc, Mutex};
 use dashmap::DashMap;
 use eframe::egui;
 use eframe::egui::{Response, Ui, Widget};
+use itertools::Itertools;
 use poll_promise::Promise;
 use uuid::Uuid;
 
+use crate::data::tag_stats;
 use crate::data::{
-    FieldDefinition, FieldStore, FieldType, FieldValue, Item, ShortcutAction, SimpleFieldStore,
-    Vault,
+    FieldDefinition, FieldStore, FieldType, FieldValue, Item, ItemId, ShortcutAction,
+    SimpleFieldStore, TagSuggestion, Vault,
 };
+use crate::errors::AppError;
 use crate::state::AppStateRef;
-use crate::take_shortcut;
+use crate::tasks::preset;
 use crate::tasks::transform::load_image_preview;
 use crate::tasks::AsyncTaskResult;
 use crate::ui::cloneable_state::CloneableTempState;
 use crate::ui::modals::EditTag;
 use crate::ui::widgets;
 use crate::ui::widgets::ListEditResult;
+use crate::{fields, take_shortcut};
+
+const MAX_SUGGESTIONS: usize = 8;
+
+/// Fields that are maintained automatically and aren't shown for editing. The tags recorded as
+/// implied are marked as such next to the tags themselves instead.
+fn is_hidden_field(id: &Uuid) -> bool {
+    *id == fields::general::IMPLIED.id
+}
 
 pub struct ItemPanel<'a, Ref: Deref<Target = Item> + 'a> {
     id: egui::Id,
@@ -27,6 +40,7 @@ pub struct ItemPanel<'a, Ref: Deref<Target = Item> + 'a> {
     vault: Arc<Vault>,
     state: State,
     app_state: AppStateRef,
+    next_item_request_id: egui::Id,
 }
 
 #[allow(clippy::struct_field_names)]
@@ -38,6 +52,18 @@ struct State {
     row_heights: Vec<f32>,
     field_store: SimpleFieldStore,
     quick_create_state: CreateState,
+    suggestion_tag_ids: Option<Vec<Uuid>>,
+    suggestions: Vec<TagSuggestion>,
+    rejected_suggestion_ids: Vec<Uuid>,
+    bulk_edit: Option<(Uuid, Option<FieldValue>)>,
+}
+
+/// A field as it appears across all the items in the panel.
+struct FieldSummary {
+    definition: FieldDefinition,
+    value: FieldValue,
+    count: usize,
+    mixed: bool,
 }
 
 impl CloneableTempState for State {}
@@ -74,9 +100,18 @@ impl<'a, Ref: Deref<Target = Item> + 'a> ItemPanel<'a, Ref> {
             id: egui::Id::new(id),
             state: State::default(),
             app_state,
+            next_item_request_id: egui::Id::new("main_thumbnail_grid")
+                .with(super::thumb_grid::TAB_REQUEST_ID),
         }
     }
 
+    /// Where to send the request for the next item after a shortcut that moves on is used,
+    /// which is the main thumbnail grid by default.
+    pub fn next_item_request_id(mut self, id: egui::Id) -> Self {
+        self.next_item_request_id = id;
+        self
+    }
+
     fn create_ui(
         &self,
         ui: &mut Ui,
@@ -155,11 +190,90 @@ impl<'a, Ref: Deref<Target = Item> + 'a> ItemPanel<'a, Ref> {
         ret
     }
 
+    fn update_suggestions(&mut self, item: &Item) {
+        let mut tag_ids: Vec<_> = self
+            .state
+            .field_store
+            .iter_fields()
+            .map(|f| *f.key())
+            .collect();
+        tag_ids.sort();
+        if self.state.suggestion_tag_ids.as_ref() == Some(&tag_ids) {
+            return;
+        }
+
+        let linked_tags: HashSet<_> = item
+            .links()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|link| self.app_state.resolve_link(link))
+            .flat_map(|(_, other_item)| {
+                other_item
+                    .iter_fields()
+                    .map(|f| *f.key())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        self.state.suggestions = self.vault.tag_stats().suggest(
+            &self.vault,
+            &tag_ids.iter().copied().collect(),
+            &tag_stats::folder_of(item.path()),
+            self.state
+                .field_store
+                .get_known_field_value(fields::tweet::AUTHOR_ID)
+                .ok()
+                .flatten(),
+            &linked_tags,
+            MAX_SUGGESTIONS,
+        );
+        self.state.suggestion_tag_ids = Some(tag_ids);
+    }
+
+    fn suggestions_ui(&mut self, ui: &mut Ui, item: &Item) {
+        self.update_suggestions(item);
+
+        let suggestions: Vec<_> = self
+            .state
+            .suggestions
+            .iter()
+            .filter(|s| !self.state.rejected_suggestion_ids.contains(&s.id))
+            .cloned()
+            .collect();
+        if suggestions.is_empty() {
+            return;
+        }
+
+        ui.label("Suggestions:");
+        for suggestion in suggestions {
+            let Some(def) = self.vault.get_definition(&suggestion.id) else {
+                continue;
+            };
+            ui.horizontal(|ui| {
+                ui.add(widgets::Tag::new(&def)).on_hover_text(format!(
+                    "Score {:.2}: {}",
+                    suggestion.score,
+                    suggestion.sources.iter().join(", ")
+                ));
+
+                if ui.add(egui::Button::new("\u{2714}").frame(false)).clicked() {
+                    self.state
+                        .field_store
+                        .set_field_value(suggestion.id, FieldValue::Tag);
+                }
+                if ui.add(egui::Button::new("\u{274c}").frame(false)).clicked() {
+                    self.state.rejected_suggestion_ids.push(suggestion.id);
+                }
+            });
+        }
+    }
+
     pub fn edit_ui(&mut self, ui: &mut Ui, item: &Item) {
         let mut fields: Vec<_> = self
             .state
             .field_store
             .iter_fields_with_defs(&self.vault)
+            .filter(|f| !is_hidden_field(&f.definition().id))
             .collect();
         fields.sort_by_key(|r| r.definition().name.clone());
 
@@ -234,6 +348,8 @@ impl<'a, Ref: Deref<Target = Item> + 'a> ItemPanel<'a, Ref> {
             }
         };
 
+        self.suggestions_ui(ui, item);
+
         ui.horizontal(|ui| {
             if ui.button("Cancel").clicked() || take_shortcut!(ui, Escape) {
                 self.state.is_editing = false;
@@ -251,19 +367,31 @@ impl<'a, Ref: Deref<Target = Item> + 'a> ItemPanel<'a, Ref> {
     }
 
     pub fn view_ui(&mut self, ui: &mut Ui, item: &Item) {
-        let mut fields: Vec<_> = item.iter_fields_with_defs(&self.vault).collect();
+        let mut fields: Vec<_> = item
+            .iter_fields_with_defs(&self.vault)
+            .filter(|f| !is_hidden_field(&f.definition().id))
+            .collect();
         fields.sort_by_key(|r| r.definition().name.clone());
 
         let existing_ids: Vec<_> = fields.iter().map(|f| f.definition().id).collect();
 
+        let implied_ids = item.implied_ids().unwrap_or_default();
         for def in fields {
-            ui.add(widgets::Tag::new(def.definition()).value(def.value()));
+            if implied_ids.contains(&def.definition().id) {
+                ui.horizontal(|ui| {
+                    ui.add(widgets::Tag::new(def.definition()).value(def.value()));
+                    ui.weak("(implied)");
+                });
+            } else {
+                ui.add(widgets::Tag::new(def.definition()).value(def.value()));
+            }
         }
 
         if self.state.is_adding {
             let mut create_state = self.state.quick_create_state.clone();
             if let Some((k, v)) = self.create_ui(ui, &mut create_state, 200.0, &existing_ids) {
                 item.set_field_value(k, v);
+                item.mark_manual(&k);
                 if self.app_state.commit_item_catch(None, item, false).is_err() {
                     return;
                 }
@@ -282,6 +410,8 @@ impl<'a, Ref: Deref<Target = Item> + 'a> ItemPanel<'a, Ref> {
             self.state.widest_tag_width = 100.0;
             self.state.field_store.clear();
             self.state.field_store.update(item);
+            self.state.suggestion_tag_ids = None;
+            self.state.rejected_suggestion_ids.clear();
         }
 
         if !self.state.is_adding && ui.button("Add tag").clicked()
@@ -291,37 +421,252 @@ impl<'a, Ref: Deref<Target = Item> + 'a> ItemPanel<'a, Ref> {
             self.state.quick_create_state = Default::default();
         }
 
+        self.shortcuts_ui(ui);
+    }
+
+    fn commit_items(&self) -> Result<(), ()> {
+        self.app_state
+            .commit_items_catch(&self.vault, self.items.iter().map(Deref::deref))
+    }
+
+    fn set_tag(&self, item: &Item, tag_id: Uuid) {
+        match self.vault.get_definition(&tag_id) {
+            Some(def) if def.field_type == FieldType::Tag => {
+                item.set_field_value(tag_id, FieldValue::Tag);
+                item.mark_manual(&tag_id);
+            }
+            _ => {}
+        }
+    }
+
+    fn open_preview(&self, item: &Item) {
+        let path = Path::new(item.path());
+        let Ok(abs_path) = self.app_state.catch(
+            || format!("resolving abs path for {}", path.display()),
+            || self.vault.resolve_abs_path(path),
+        ) else {
+            return;
+        };
+        self.app_state
+            .add_global_task("Load image preview", move |_, _| {
+                Promise::spawn_blocking(move || load_image_preview(abs_path))
+            });
+    }
+
+    fn remove_items(&self) -> Result<(), ()> {
+        for item in self.items {
+            self.app_state.catch(
+                || format!("removing item {}", item.path()),
+                || {
+                    self.app_state.unlink_item(&self.vault, item)?;
+                    self.vault.remove_item(Path::new(item.path()))
+                },
+            )?;
+        }
+        self.vault.set_last_updated();
+        self.app_state.save_vault_deferred(Arc::clone(&self.vault));
+        Ok(())
+    }
+
+    fn run_preset(&self, preset_name: &str) {
+        let Ok(preset) = self.app_state.catch(
+            || "running shortcut",
+            || {
+                self.app_state
+                    .get_transform_preset(preset_name)
+                    .ok_or_else(|| AppError::MissingPreset {
+                        name: preset_name.to_string(),
+                    })
+            },
+        ) else {
+            return;
+        };
+        let item_ids = self
+            .items
+            .iter()
+            .map(|item| ItemId::from_item(&self.vault, item))
+            .collect();
+        preset::run_preset(&self.app_state, Arc::clone(&self.vault), item_ids, preset);
+    }
+
+    fn set_rating(&self, rating: Option<i64>) -> Result<(), ()> {
+        for item in self.items {
+            match rating {
+                Some(rating) => item.set_known_field_value(fields::general::RATING, rating),
+                None => {
+                    item.remove_field(&fields::general::RATING.id);
+                }
+            }
+        }
+        self.commit_items()
+    }
+
+    fn set_favourite(&self, favourite: bool) -> Result<(), ()> {
+        for item in self.items {
+            if favourite {
+                item.set_known_field_value(fields::general::FAVOURITE, true);
+            } else {
+                item.remove_field(&fields::general::FAVOURITE.id);
+            }
+        }
+        self.commit_items()
+    }
+
+    /// Shows the rating shared by all items in the panel, which Alt+0 to Alt+5 also set and
+    /// Alt+F toggles the favourite of.
+    fn rating_ui(&mut self, ui: &mut Ui) {
+        const RATING_KEYS: [egui::Key; 6] = [
+            egui::Key::Num0,
+            egui::Key::Num1,
+            egui::Key::Num2,
+            egui::Key::Num3,
+            egui::Key::Num4,
+            egui::Key::Num5,
+        ];
+
+        let mut rating = self
+            .items
+            .iter()
+            .map(|item| {
+                item.get_known_field_value(fields::general::RATING)
+                    .ok()
+                    .flatten()
+            })
+            .all_equal_value()
+            .ok()
+            .flatten();
+        let mut favourite = self.items.iter().all(|item| {
+            item.get_known_field_value(fields::general::FAVOURITE)
+                .ok()
+                .flatten()
+                .unwrap_or(false)
+        });
+
+        let (rating_changed, favourite_changed) = ui
+            .horizontal(|ui| {
+                let rating_res = ui.add(widgets::Rating::new(self.id.with("rating"), &mut rating));
+                let favourite_res = ui.add(widgets::Favourite::new(&mut favourite));
+                (rating_res.changed(), favourite_res.changed())
+            })
+            .inner;
+
+        let mut key_rating = None;
+        for (i, key) in (0..).zip(RATING_KEYS) {
+            if ui.input_mut(|r| r.consume_key(egui::Modifiers::ALT, key)) {
+                key_rating = Some(i);
+            }
+        }
+
+        if let Some(i) = key_rating {
+            let _ = self.set_rating((i > 0).then_some(i));
+        } else if rating_changed {
+            let _ = self.set_rating(rating);
+        }
+
+        if take_shortcut!(ui, ALT + F) {
+            let _ = self.set_favourite(!favourite);
+        } else if favourite_changed {
+            let _ = self.set_favourite(favourite);
+        }
+    }
+
+    /// Runs the actions of any shortcuts of the active profile that were pressed, on every item
+    /// shown in the panel.
+    fn shortcuts_ui(&mut self, ui: &mut Ui) {
+        // bindings without modifiers would otherwise fire while typing into a text field
+        if ui.ctx().wants_keyboard_input() {
+            return;
+        }
+
         let shortcuts = self.app_state.shortcuts();
         for (shortcut, behaviour) in shortcuts {
-            if ui.input_mut(|i| i.consume_key(shortcut.modifiers, shortcut.logical_key)) {
-                match behaviour.action {
-                    ShortcutAction::None => {}
-                    ShortcutAction::ToggleTag(tag_id) => {
-                        if item.has_field(&tag_id) {
+            if !ui.input_mut(|i| i.consume_key(shortcut.modifiers, shortcut.logical_key)) {
+                continue;
+            }
+
+            match behaviour.action {
+                ShortcutAction::None => {}
+                ShortcutAction::ToggleTag(tag_id) => {
+                    for item in self.items {
+                        // an implied tag would just be implied again, so apply it explicitly
+                        let is_implied = item
+                            .implied_ids()
+                            .is_ok_and(|implied| implied.contains(&tag_id));
+                        if item.has_field(&tag_id) && !is_implied {
                             item.remove_field(&tag_id);
                         } else {
-                            match self.vault.get_definition(&tag_id) {
-                                Some(def) if def.field_type == FieldType::Tag => {
-                                    item.set_field_value(tag_id, FieldValue::Tag);
-                                }
-                                _ => {}
+                            self.set_tag(item, tag_id);
+                        }
+                    }
+                    if self.commit_items().is_err() {
+                        return;
+                    }
+                }
+                ShortcutAction::SetField(field_id, value) => {
+                    match self.vault.get_definition(&field_id) {
+                        Some(def) if def.field_type == value.get_type() => {
+                            for item in self.items {
+                                item.set_field_value(field_id, value.clone());
+                                item.mark_manual(&field_id);
                             }
                         }
-
-                        if self.app_state.commit_item_catch(None, item, false).is_err() {
-                            return;
+                        _ => {}
+                    }
+                    if self.commit_items().is_err() {
+                        return;
+                    }
+                }
+                ShortcutAction::RemoveField(field_id) => {
+                    for item in self.items {
+                        item.remove_field(&field_id);
+                    }
+                    if self.commit_items().is_err() {
+                        return;
+                    }
+                }
+                ShortcutAction::TagSelection(tag_id) => {
+                    for item in self.items {
+                        self.set_tag(item, tag_id);
+                    }
+                    if self.commit_items().is_err() {
+                        return;
+                    }
+                }
+                ShortcutAction::ToggleSkip => {
+                    let skip_id = fields::general::SKIP.id;
+                    for item in self.items {
+                        if item.has_field(&skip_id) {
+                            item.remove_field(&skip_id);
+                        } else {
+                            item.set_field_value(skip_id, FieldValue::Tag);
                         }
                     }
+                    if self.commit_items().is_err() {
+                        return;
+                    }
                 }
-
-                if behaviour.move_next {
-                    self.app_state.add_completed_task(
-                        egui::Id::new("main_thumbnail_grid")
-                            .with(super::thumb_grid::TAB_REQUEST_ID),
-                        Ok(AsyncTaskResult::NextItem),
-                    );
+                ShortcutAction::OpenPreview => {
+                    if let Some(item) = self.items.first() {
+                        self.open_preview(item);
+                    }
+                }
+                ShortcutAction::RemoveItem => {
+                    // the items no longer exist, so there is nothing to move on from
+                    let _ = self.remove_items();
+                    return;
+                }
+                ShortcutAction::Search(search_text) => {
+                    self.app_state.request_search_text(search_text);
+                }
+                ShortcutAction::RunPreset(preset_name) => {
+                    self.run_preset(&preset_name);
                 }
             }
+
+            if behaviour.move_next {
+                self.app_state
+                    .add_completed_task(self.next_item_request_id, Ok(AsyncTaskResult::NextItem));
+            }
         }
     }
 
@@ -329,32 +674,170 @@ impl<'a, Ref: Deref<Target = Item> + 'a> ItemPanel<'a, Ref> {
         ui.label(egui::RichText::new(item.path()).text_style(egui::TextStyle::Heading));
 
         if ui.button("Open Preview").clicked() {
-            let path = Path::new(item.path());
-            let Ok(abs_path) = self.app_state.catch(
-                || format!("resolving abs path for {}", path.display()),
-                || self.vault.resolve_abs_path(path),
-            ) else {
-                return;
-            };
-            self.app_state
-                .add_global_task("Load image preview", move |_, _| {
-                    Promise::spawn_blocking(move || load_image_preview(abs_path))
-                });
+            self.open_preview(item);
         }
 
         if self.state.is_editing {
             self.edit_ui(ui, item);
         } else {
+            self.rating_ui(ui);
             self.view_ui(ui, item);
         }
     }
 
+    fn field_summaries(&self) -> Vec<FieldSummary> {
+        let mut summaries: Vec<FieldSummary> = vec![];
+        for item in self.items {
+            for (definition, value) in item.cloned_fields_with_defs(&self.vault) {
+                if is_hidden_field(&definition.id) {
+                    continue;
+                }
+                if let Some(summary) = summaries
+                    .iter_mut()
+                    .find(|s| s.definition.id == definition.id)
+                {
+                    summary.count += 1;
+                    summary.mixed |= summary.value != value;
+                } else {
+                    summaries.push(FieldSummary {
+                        definition,
+                        value,
+                        count: 1,
+                        mixed: false,
+                    });
+                }
+            }
+        }
+        summaries.sort_by(|a, b| a.definition.name.cmp(&b.definition.name));
+        summaries
+    }
+
+    fn set_field_on_all(&self, field_id: Uuid, value: &FieldValue) -> Result<(), ()> {
+        for item in self.items {
+            item.set_field_value(field_id, value.clone());
+            item.mark_manual(&field_id);
+        }
+        self.commit_items()
+    }
+
+    fn remove_field_from_all(&self, field_id: &Uuid) -> Result<(), ()> {
+        for item in self.items {
+            item.remove_field(field_id);
+        }
+        self.commit_items()
+    }
+
+    /// Shows a row for a field present on any of the items, with buttons that change the field on
+    /// every item at once.
+    fn bulk_field_ui(&mut self, ui: &mut Ui, summary: &FieldSummary) {
+        let def = &summary.definition;
+        let on_all = summary.count == self.items.len();
+        let is_editing = matches!(&self.state.bulk_edit, Some((id, _)) if *id == def.id);
+
+        ui.horizontal(|ui| {
+            if summary.mixed {
+                ui.add(widgets::Tag::new(def));
+                ui.weak("mixed");
+            } else {
+                ui.add(widgets::Tag::new(def).value(&summary.value));
+            }
+            if !on_all {
+                ui.weak(format!("{} of {}", summary.count, self.items.len()))
+                    .on_hover_text("Only some of the items have this field");
+            }
+
+            if is_editing {
+                let Some((_, mut value)) = self.state.bulk_edit.take() else {
+                    return;
+                };
+                ui.add(widgets::TagValueEdit::new(
+                    self.id.with("bulk_value").with(def.id),
+                    def.field_type,
+                    &mut value,
+                ));
+                let accepted = ui.add(egui::Button::new("\u{2714}").frame(false)).clicked()
+                    || take_shortcut!(ui, Enter);
+                let cancelled = ui.add(egui::Button::new("\u{274c}").frame(false)).clicked()
+                    || take_shortcut!(ui, Escape);
+                match value {
+                    Some(v) if accepted && v.get_type() == def.field_type => {
+                        let _ = self.set_field_on_all(def.id, &v);
+                    }
+                    _ if cancelled => {}
+                    value => self.state.bulk_edit = Some((def.id, value)),
+                }
+                return;
+            }
+
+            if def.field_type == FieldType::Tag {
+                if !on_all
+                    && ui
+                        .add(egui::Button::new("\u{2714}").frame(false))
+                        .on_hover_text("Add to all items")
+                        .clicked()
+                {
+                    let _ = self.set_field_on_all(def.id, &FieldValue::Tag);
+                }
+            } else if ui
+                .add(egui::Button::new("\u{270f}").frame(false))
+                .on_hover_text("Set on all items")
+                .clicked()
+            {
+                let value = (!summary.mixed).then(|| summary.value.clone());
+                self.state.bulk_edit = Some((def.id, value));
+            }
+
+            if ui
+                .add(egui::Button::new("\u{1f5d1}").frame(false))
+                .on_hover_text("Remove from all items")
+                .clicked()
+            {
+                let _ = self.remove_field_from_all(&def.id);
+            }
+        });
+    }
+
     pub fn multiple_ui(&mut self, ui: &mut Ui) {
         ui.label(format!(
             "{} item{}",
             self.items.len(),
             if self.items.len() == 1 { "" } else { "s" }
         ));
+
+        self.rating_ui(ui);
+
+        let summaries = self.field_summaries();
+        for summary in &summaries {
+            self.bulk_field_ui(ui, summary);
+        }
+
+        if self.state.is_adding {
+            let existing_ids: Vec<_> = summaries
+                .iter()
+                .filter(|s| s.count == self.items.len())
+                .map(|s| s.definition.id)
+                .collect();
+            let mut create_state = self.state.quick_create_state.clone();
+            if let Some((k, v)) = self.create_ui(ui, &mut create_state, 200.0, &existing_ids) {
+                if self.set_field_on_all(k, &v).is_ok() {
+                    self.state.is_adding = false;
+                }
+            }
+            if create_state.cancelled {
+                self.state.is_adding = false;
+            }
+            self.state.quick_create_state = create_state;
+        }
+
+        let is_viewing = !self.state.is_adding && self.state.bulk_edit.is_none();
+        if !self.state.is_adding && ui.button("Add tag to all").clicked()
+            || (is_viewing && take_shortcut!(ui, Backtick))
+        {
+            self.state.is_adding = true;
+            self.state.quick_create_state = Default::default();
+        }
+
+        self.shortcuts_ui(ui);
     }
 }
 
@@ -1,32 +1,50 @@
 use crate::state::AppStateRef;
 
+mod api_server;
 mod delete_def;
 mod download;
 mod edit_tag;
+mod export_items;
+mod import_defs;
+mod jobs;
 mod link_vault;
 mod manage_vaults;
+mod merge_vault;
 mod message;
 mod new_vault;
 mod preview;
 mod query;
+mod review_queue;
+mod subscriptions;
 mod tag_shortcuts;
+mod task_log;
 mod transform_images;
 mod transform_paths;
 mod transform_results;
+mod vault_stats;
 
+pub use api_server::ApiServer;
 pub use delete_def::DeleteDefinition;
 pub use download::Download;
 pub use edit_tag::EditTag;
+pub use export_items::ExportItems;
+pub use import_defs::ImportDefinitions;
+pub use jobs::Jobs;
 pub use link_vault::LinkVault;
 pub use manage_vaults::ManageVaults;
+pub use merge_vault::MergeVault;
 pub use message::Message;
 pub use new_vault::NewVault;
 pub use preview::Preview;
 pub use query::{Query, QueryOptions, QueryResult};
+pub use review_queue::ReviewQueue;
+pub use subscriptions::Subscriptions;
 pub use tag_shortcuts::TagShortcuts;
+pub use task_log::TaskLog;
 pub use transform_images::TransformImages;
 pub use transform_paths::TransformPaths;
 pub use transform_results::TransformResults;
+pub use vault_stats::VaultStatistics;
 
 pub trait AppModal: Send + Sync + 'static {
     fn id(&self) -> eframe::egui::Id;
@@ -0,0 +1,130 @@
+use eframe::egui;
+use egui_modal::{Modal, ModalStyle};
+use poll_promise::Promise;
+
+use crate::server::{generate_token, ApiServerSettings};
+use crate::state::AppStateRef;
+use crate::tasks::AsyncTaskResult;
+use crate::ui::modals::AppModal;
+use crate::ui::theme;
+
+#[derive(Default)]
+pub struct ApiServer {
+    modal: Option<Modal>,
+    settings: Option<ApiServerSettings>,
+    error: Option<String>,
+    applying: bool,
+    opened: bool,
+}
+
+impl ApiServer {
+    fn apply_request_id(&self) -> egui::Id {
+        self.id().with("apply")
+    }
+
+    fn settings_ui(ui: &mut egui::Ui, settings: &mut ApiServerSettings) {
+        ui.checkbox(&mut settings.enabled, "Enable API server");
+
+        egui::Grid::new("api_server_settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Port:");
+                ui.add(egui::DragValue::new(&mut settings.port).clamp_range(1024..=65535));
+                ui.end_row();
+
+                ui.label("Access token:");
+                ui.horizontal(|ui| {
+                    ui.monospace(&settings.token);
+                    if ui.button("Copy").clicked() {
+                        ui.ctx().copy_text(settings.token.clone());
+                    }
+                    if ui.button("Regenerate").clicked() {
+                        settings.token = generate_token();
+                    }
+                });
+                ui.end_row();
+            });
+    }
+}
+
+impl AppModal for ApiServer {
+    fn id(&self) -> egui::Id {
+        "api_server_modal".into()
+    }
+
+    fn update(&mut self, ctx: &egui::Context, state: AppStateRef) {
+        let modal = Modal::new(ctx, self.id().value()).with_style(&ModalStyle {
+            default_width: Some(450.0),
+            ..Default::default()
+        });
+
+        if let Some(res) = state.try_take_request_result(self.apply_request_id()) {
+            self.applying = false;
+            self.error = res.err().map(|e| format!("Failed to start: {e}"));
+        }
+
+        let apply_request_id = self.apply_request_id();
+        let settings = self
+            .settings
+            .get_or_insert_with(|| state.api_server_settings());
+
+        modal.show(|ui| {
+            modal.title(ui, "API server");
+            modal.frame(ui, |ui| {
+                ui.label(
+                    "Lets other programs on this computer query and tag items over HTTP. \
+                     Requests must include the header \"Authorization: Bearer <token>\".",
+                );
+                ui.separator();
+
+                Self::settings_ui(ui, settings);
+
+                ui.separator();
+                match state.api_server_addr() {
+                    Some(addr) => ui.colored_label(
+                        theme::SUCCESS_TEXT,
+                        format!("Listening on http://{addr}/api/"),
+                    ),
+                    None => ui.label("Not running"),
+                };
+                if let Some(error) = &self.error {
+                    ui.colored_label(theme::ERROR_TEXT, error);
+                }
+            });
+            modal.buttons(ui, |ui| {
+                let apply_button = egui::Button::new("Apply").fill(ui.visuals().selection.bg_fill);
+                let stopped = settings.enabled && state.api_server_addr().is_none();
+                let changed = stopped || *settings != state.api_server_settings();
+                if ui
+                    .add_enabled(changed && !self.applying, apply_button)
+                    .clicked()
+                {
+                    self.applying = true;
+                    let settings = settings.clone();
+                    state.add_task_request(
+                        apply_request_id,
+                        "Apply API server settings",
+                        |s, _| {
+                            Promise::spawn_async(async move {
+                                s.set_api_server_settings(settings).await?;
+                                Ok(AsyncTaskResult::None)
+                            })
+                        },
+                    );
+                }
+                modal.button(ui, "Close");
+            });
+        });
+
+        if !self.opened {
+            modal.open();
+            self.opened = true;
+        }
+
+        self.modal = Some(modal);
+    }
+
+    fn is_open(&self) -> bool {
+        self.modal.as_ref().is_some_and(|m| m.is_open())
+    }
+}
@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 use uuid::Uuid;
 
+use crate::data::{Credential, CredentialRef, Subscription};
 use crate::state::AppStateRef;
 use crate::tasks::download::{
     GalleryDLLogin, GalleryDLLoginDiscriminants, GalleryDLParams, GalleryDLSource,
@@ -22,9 +23,16 @@ use crate::ui::{choice, theme};
 pub struct Download {
     modal: Option<Modal>,
     error_message: Option<String>,
+    success_message: Option<String>,
     params: GalleryDLParams,
     loading_find: bool,
     find_error: Option<String>,
+    subscription_name: String,
+    interval_hours: Option<u32>,
+    username: String,
+    password: String,
+    passphrase: String,
+    credential_error: Option<String>,
     opened: bool,
 }
 
@@ -78,63 +86,144 @@ impl Download {
                 egui::ComboBox::new("gallery_dl_source", "")
                     .selected_text(src_discriminant.to_string())
                     .show_ui(ui, |ui| {
-                        choice(
-                            ui,
-                            &mut src_discriminant,
+                        for d in [
                             GalleryDLSourceDiscriminants::None,
-                        );
-                        choice(
-                            ui,
-                            &mut src_discriminant,
                             GalleryDLSourceDiscriminants::TwitterLikes,
-                        );
-                        choice(
-                            ui,
-                            &mut src_discriminant,
+                            GalleryDLSourceDiscriminants::PixivBookmarks,
+                            GalleryDLSourceDiscriminants::DanbooruSearch,
+                            GalleryDLSourceDiscriminants::RedditSaved,
+                            GalleryDLSourceDiscriminants::BlueskyLikes,
                             GalleryDLSourceDiscriminants::CustomURL,
-                        );
+                        ] {
+                            choice(ui, &mut src_discriminant, d);
+                        }
                     });
             });
         });
 
         if src_discriminant != (&self.params.source).into() {
-            self.params.source = match src_discriminant {
-                GalleryDLSourceDiscriminants::None => GalleryDLSource::None,
-                GalleryDLSourceDiscriminants::TwitterLikes => GalleryDLSource::TwitterLikes {
-                    username: String::new(),
-                },
-                GalleryDLSourceDiscriminants::CustomURL => {
-                    GalleryDLSource::CustomURL { url: String::new() }
-                }
-            };
+            let new_source = GalleryDLSource::from_discriminant(src_discriminant);
+            // only replace the configuration if the user hasn't changed the preset
+            let old_config = self.params.source.default_config();
+            let is_preset = self.params.json_config == old_config
+                || self.params.json_config == GalleryDLParams::default().json_config;
+            if is_preset && new_source.category().is_some() {
+                self.params.json_config = new_source.default_config();
+            }
+            self.params.source = new_source;
         }
 
-        match &mut self.params.source {
-            GalleryDLSource::None => {}
-            GalleryDLSource::TwitterLikes { username } => {
-                body.row(ROW_HEIGHT, |mut row| {
-                    row.col(|ui| {
-                        ui.label("Twitter username: ");
-                    });
-                    row.col(|ui| {
-                        ui.text_edit_singleline(username);
-                    });
+        let (label, value) = match &mut self.params.source {
+            GalleryDLSource::None => return,
+            GalleryDLSource::TwitterLikes { username } => ("Twitter username: ", username),
+            GalleryDLSource::PixivBookmarks { user_id } => ("Pixiv user ID: ", user_id),
+            GalleryDLSource::DanbooruSearch { tags } => ("Danbooru tags: ", tags),
+            GalleryDLSource::RedditSaved { username } => ("Reddit username: ", username),
+            GalleryDLSource::BlueskyLikes { handle } => ("Bluesky handle: ", handle),
+            GalleryDLSource::CustomURL { url } => ("Custom URL: ", url),
+        };
+        body.row(ROW_HEIGHT, |mut row| {
+            row.col(|ui| {
+                ui.label(label);
+            });
+            row.col(|ui| {
+                ui.text_edit_singleline(value);
+            });
+        });
+    }
+
+    fn save_credential(&mut self, app_state: &AppStateRef) -> anyhow::Result<CredentialRef> {
+        let store = app_state.credentials();
+        if !store.keyring_available() && !store.is_unlocked() {
+            store.unlock(&self.passphrase)?;
+            self.passphrase.clear();
+        }
+
+        let label = match self.params.source.category() {
+            Some(category) => format!("{} ({category})", self.username),
+            None => self.username.clone(),
+        };
+        let credential = Credential {
+            username: std::mem::take(&mut self.username),
+            password: std::mem::take(&mut self.password),
+        };
+        store.save(label, &credential)
+    }
+
+    fn credential_form_fragment(&mut self, body: &mut TableBody, app_state: &AppStateRef) {
+        let store = app_state.credentials();
+        let needs_passphrase = !store.keyring_available() && !store.is_unlocked();
+
+        body.row(ROW_HEIGHT, |mut row| {
+            row.col(|ui| {
+                ui.label("Username: ");
+            });
+            row.col(|ui| {
+                ui.text_edit_singleline(&mut self.username);
+            });
+        });
+        body.row(ROW_HEIGHT, |mut row| {
+            row.col(|ui| {
+                ui.label("Password: ");
+            });
+            row.col(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+            });
+        });
+        if needs_passphrase {
+            body.row(ROW_HEIGHT, |mut row| {
+                row.col(|ui| {
+                    ui.label("Store passphrase: ");
                 });
-            }
-            GalleryDLSource::CustomURL { url } => {
-                body.row(ROW_HEIGHT, |mut row| {
-                    row.col(|ui| {
-                        ui.label("Custom URL: ");
-                    });
-                    row.col(|ui| {
-                        ui.text_edit_singleline(url);
-                    });
+                row.col(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.passphrase).password(true));
+                    if !store.file_exists() {
+                        ui.weak("(creates a new encrypted credential store)");
+                    }
                 });
-            }
+            });
+        } else if store.is_unlocked() {
+            body.row(ROW_HEIGHT, |mut row| {
+                row.col(|ui| {
+                    ui.label("Credential store: ");
+                });
+                row.col(|ui| {
+                    ui.label("Unlocked");
+                    if ui.button("Lock").clicked() {
+                        store.lock();
+                    }
+                });
+            });
         }
+        body.row(ROW_HEIGHT, |mut row| {
+            row.col(|_ui| {});
+            row.col(|ui| {
+                let can_save = !self.username.is_empty()
+                    && !self.password.is_empty()
+                    && (!needs_passphrase || !self.passphrase.is_empty());
+                if ui
+                    .add_enabled(can_save, egui::Button::new("Save credential"))
+                    .clicked()
+                {
+                    match self.save_credential(app_state) {
+                        Ok(credential_ref) => {
+                            self.credential_error = None;
+                            self.params.login = GalleryDLLogin::UsernamePassword {
+                                credential: Some(credential_ref),
+                                legacy: None,
+                            };
+                        }
+                        Err(e) => self.credential_error = Some(e.to_string()),
+                    }
+                }
+                if let Some(msg) = &self.credential_error {
+                    ui.colored_label(theme::ERROR_TEXT, msg);
+                }
+            });
+        });
     }
 
-    fn login_form_fragment(&mut self, body: &mut TableBody) {
+    fn login_form_fragment(&mut self, body: &mut TableBody, app_state: &AppStateRef) {
         let mut login: GalleryDLLoginDiscriminants = (&self.params.login).into();
 
         body.row(ROW_HEIGHT, |mut row| {
@@ -163,8 +252,8 @@ impl Download {
                 GalleryDLLoginDiscriminants::ChromeCookies => GalleryDLLogin::ChromeCookies,
                 GalleryDLLoginDiscriminants::FirefoxCookies => GalleryDLLogin::FirefoxCookies,
                 GalleryDLLoginDiscriminants::UsernamePassword => GalleryDLLogin::UsernamePassword {
-                    username: String::new(),
-                    password: String::new(),
+                    credential: None,
+                    legacy: None,
                 },
             };
         }
@@ -173,23 +262,44 @@ impl Download {
             GalleryDLLogin::None
             | GalleryDLLogin::FirefoxCookies
             | GalleryDLLogin::ChromeCookies => {}
-            GalleryDLLogin::UsernamePassword { username, password } => {
-                body.row(ROW_HEIGHT, |mut row| {
-                    row.col(|ui| {
-                        ui.label("Username: ");
-                    });
-                    row.col(|ui| {
-                        ui.text_edit_singleline(username);
-                    });
-                });
+            GalleryDLLogin::UsernamePassword {
+                credential: None, ..
+            } => {
+                self.credential_form_fragment(body, app_state);
+            }
+            GalleryDLLogin::UsernamePassword {
+                credential: Some(credential_ref),
+                ..
+            } => {
+                let (mut change, mut forget) = (false, false);
                 body.row(ROW_HEIGHT, |mut row| {
                     row.col(|ui| {
-                        ui.label("Password: ");
+                        ui.label("Saved credential: ");
                     });
                     row.col(|ui| {
-                        ui.add(egui::TextEdit::singleline(password).password(true));
+                        ui.label(format!(
+                            "{} (in {})",
+                            credential_ref.label, credential_ref.backend
+                        ));
+                        change = ui.button("Change...").clicked();
+                        forget = ui
+                            .button("Forget")
+                            .on_hover_text("Remove the credential from the credential store")
+                            .clicked();
                     });
                 });
+                if forget {
+                    if let Err(e) = app_state.credentials().remove(credential_ref) {
+                        self.credential_error = Some(e.to_string());
+                        return;
+                    }
+                }
+                if change || forget {
+                    self.params.login = GalleryDLLogin::UsernamePassword {
+                        credential: None,
+                        legacy: None,
+                    };
+                }
             }
         }
     }
@@ -215,6 +325,87 @@ impl Download {
         );
     }
 
+    fn options_ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(
+            &mut self.params.import_downloads,
+            "Import downloaded files and link their sidecars",
+        );
+        let tag_label = format!("Tag new items with {}", self.params.download_tag());
+        ui.add_enabled(
+            self.params.import_downloads,
+            egui::Checkbox::new(&mut self.params.tag_downloads, tag_label),
+        );
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Subscription name:");
+            ui.text_edit_singleline(&mut self.subscription_name);
+
+            let mut repeat = self.interval_hours.is_some();
+            ui.checkbox(&mut repeat, "Re-run every");
+            let mut hours = self.interval_hours.unwrap_or(24);
+            ui.add_enabled(
+                repeat,
+                egui::DragValue::new(&mut hours)
+                    .clamp_range(1..=24 * 365)
+                    .suffix(" hours"),
+            );
+            self.interval_hours = repeat.then_some(hours);
+        });
+    }
+
+    fn save_subscription(&self, app_state: &AppStateRef) -> Result<String, &'static str> {
+        self.validate()?;
+        let name = self.subscription_name.trim();
+        if name.is_empty() {
+            return Err("Please enter a name for the subscription.");
+        }
+        let Some(vault) = app_state.current_vault_opt() else {
+            return Err("A vault must be open to save a subscription.");
+        };
+
+        // keep the executable location out of the vault as it may be shared between machines
+        let params = GalleryDLParams {
+            location: None,
+            version: None,
+            log_file: None,
+            ..self.params.clone()
+        };
+        vault.set_subscription(
+            Subscription::new(name.to_string(), params).with_interval_hours(self.interval_hours),
+        );
+        app_state.save_vault_deferred(vault);
+        Ok(format!("Saved subscription '{name}'."))
+    }
+
+    /// Moves a plain text login saved by an older version into the credential store. If that
+    /// isn't possible yet, the login is put back into the form so that it can be saved manually.
+    fn migrate_legacy_login(&mut self, app_state: &AppStateRef) {
+        let context = self.params.source.category().unwrap_or("gallery-dl");
+        if let Err(e) = self
+            .params
+            .login
+            .migrate_legacy(app_state.credentials(), context)
+        {
+            if let GalleryDLLogin::UsernamePassword {
+                legacy: Some(legacy),
+                ..
+            } = &mut self.params.login
+            {
+                let legacy = std::mem::take(legacy);
+                self.username = legacy.username;
+                self.password = legacy.password;
+            }
+            self.params.login = GalleryDLLogin::UsernamePassword {
+                credential: None,
+                legacy: None,
+            };
+            self.credential_error = Some(format!(
+                "Your saved login needs to be moved into the credential store: {e}"
+            ));
+        }
+    }
+
     fn find_request_id(&self) -> egui::Id {
         self.id().with("find_gallery_dl")
     }
@@ -229,6 +420,18 @@ impl Download {
             GalleryDLSource::TwitterLikes { username } if username.is_empty() => {
                 return Err("Please enter your Twitter username.")
             }
+            GalleryDLSource::PixivBookmarks { user_id } if user_id.parse::<u64>().is_err() => {
+                return Err("Please enter a valid Pixiv user ID.")
+            }
+            GalleryDLSource::DanbooruSearch { tags } if tags.trim().is_empty() => {
+                return Err("Please enter some Danbooru tags to search for.")
+            }
+            GalleryDLSource::RedditSaved { username } if username.is_empty() => {
+                return Err("Please enter your Reddit username.")
+            }
+            GalleryDLSource::BlueskyLikes { handle } if handle.is_empty() => {
+                return Err("Please enter your Bluesky handle.")
+            }
             GalleryDLSource::CustomURL { url } if Url::parse(url.as_str()).is_err() => {
                 return Err("Please enter a valid URL.")
             }
@@ -237,11 +440,9 @@ impl Download {
 
         match &self.params.login {
             GalleryDLLogin::None => return Err("Please select a login method."),
-            GalleryDLLogin::UsernamePassword { username, password }
-                if username.is_empty() || password.is_empty() =>
-            {
-                return Err("Please enter a username and password.")
-            }
+            GalleryDLLogin::UsernamePassword {
+                credential: None, ..
+            } => return Err("Please save a username and password."),
             _ => {}
         }
 
@@ -273,6 +474,8 @@ impl AppModal for Download {
         self.params = std::mem::take(&mut state.params);
 
         if !self.opened {
+            self.migrate_legacy_login(&app_state);
+
             let mut log_file = std::env::temp_dir();
             log_file.push(format!("{}.txt", Uuid::new_v4()));
             self.params.log_file = Some(log_file.to_str().unwrap().to_string());
@@ -308,7 +511,7 @@ impl AppModal for Download {
                     .body(|mut body| {
                         self.executable_fragment(&mut body, app_state.clone());
                         self.source_form_fragment(&mut body);
-                        self.login_form_fragment(&mut body);
+                        self.login_form_fragment(&mut body, &app_state);
 
                         body.row(ROW_HEIGHT, |mut row| {
                             row.col(|ui| {
@@ -322,9 +525,14 @@ impl AppModal for Download {
 
                 self.configuration_ui(ui);
 
+                self.options_ui(ui);
+
                 if let Some(msg) = &self.error_message {
                     ui.colored_label(Color32::RED, msg);
                 }
+                if let Some(msg) = &self.success_message {
+                    ui.colored_label(theme::SUCCESS_TEXT, msg);
+                }
             });
             modal.buttons(ui, |ui| {
                 if modal.suggested_button(ui, "Download").clicked() {
@@ -342,6 +550,18 @@ impl AppModal for Download {
                         });
                     }
                 }
+                if ui.button("Save as subscription").clicked() {
+                    match self.save_subscription(&app_state) {
+                        Ok(msg) => {
+                            self.error_message = None;
+                            self.success_message = Some(msg);
+                        }
+                        Err(e) => {
+                            self.error_message = e.to_string().into();
+                            self.success_message = None;
+                        }
+                    }
+                }
                 if modal.button(ui, "Cancel").clicked() {}
             });
         });
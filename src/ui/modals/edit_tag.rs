@@ -1,5 +1,6 @@
 use eframe::egui;
 use eframe::egui::{Color32, Widget};
+use poll_promise::Promise;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -11,6 +12,7 @@ use crate::ui::widgets;
 use crate::ui::widgets::ListEditResult;
 use crate::{fields, take_shortcut};
 
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Default)]
 pub struct EditTag {
     definition: Option<FieldDefinition>,
@@ -19,6 +21,7 @@ pub struct EditTag {
 
     removed_parents: Vec<Uuid>,
     removed_children: Vec<Uuid>,
+    apply_implications: bool,
 
     widget_state: State,
     app_state: AppStateRef,
@@ -81,6 +84,7 @@ impl EditTag {
         self.is_new = is_new;
         self.removed_children = vec![];
         self.removed_parents = vec![];
+        self.apply_implications = false;
         self.updated = true;
 
         if is_new {
@@ -127,6 +131,16 @@ impl EditTag {
                         def.remove_parent(id);
                     }
                 }
+                if std::mem::take(&mut self.apply_implications) {
+                    self.app_state
+                        .add_global_task("Apply tag implications", move |state, p| {
+                            Promise::spawn_async(
+                                crate::tasks::implication::apply_implications_to_vault(
+                                    state, vault, p,
+                                ),
+                            )
+                        });
+                }
                 self.is_new = false;
                 self.updated = true;
                 true
@@ -268,6 +282,67 @@ impl EditTag {
         add_new_child
     }
 
+    #[allow(clippy::needless_pass_by_value)]
+    fn edit_implications_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        vault: Arc<Vault>,
+        implied_ids: &Vec<Uuid>,
+        exclude_ids: &[Uuid],
+    ) {
+        let Some(def) = self.definition.as_mut() else {
+            return;
+        };
+
+        ui.group(|ui| {
+            ui.vertical_centered_justified(|ui| {
+                let mut result = ListEditResult::None;
+                widgets::ListEdit::new("edit_tag_implied_table", implied_ids, &mut result)
+                    .row_height(22.0)
+                    .header_label("Implies:".into())
+                    .item_ui(|ui, implied_id| {
+                        ui.add(widgets::Tag::new(&*vault.get_definition(implied_id)?));
+                        None
+                    })
+                    .create_label("Add implied tag".into())
+                    .create_ui(|ui, create_state| {
+                        if ui
+                            .add(
+                                widgets::FindTag::new(
+                                    "edit_tag_new_implied",
+                                    create_state,
+                                    Arc::clone(&vault),
+                                )
+                                .exclude_ids(exclude_ids)
+                                .filter_types(&[FieldType::Tag]),
+                            )
+                            .changed()
+                            && create_state.is_some()
+                        {
+                            Some(create_state.unwrap())
+                        } else {
+                            None
+                        }
+                    })
+                    .updated(self.updated)
+                    .ui(ui);
+
+                match result {
+                    ListEditResult::None | ListEditResult::Edit(_, ()) => {}
+                    ListEditResult::Add(id) => def.add_implied(id),
+                    ListEditResult::Remove(i) => def.remove_implied(implied_ids[i]),
+                }
+
+                if !implied_ids.is_empty() {
+                    ui.checkbox(
+                        &mut self.apply_implications,
+                        "Apply to existing items on save",
+                    );
+                }
+            });
+        });
+    }
+
     fn edit_aliases_ui(&mut self, ui: &mut egui::Ui) {
         let Some(def) = self.definition.as_mut() else {
             return;
@@ -406,16 +481,23 @@ impl EditTag {
                     });
                 });
 
-            let (parent_ids, child_ids, exclude_ids, field_type) = {
+            let (parent_ids, child_ids, implied_ids, exclude_ids, field_type) = {
                 let def = self.definition.as_ref().ok_or(())?;
 
                 let parent_ids: Vec<Uuid> = def.iter_parent_ids().map(|u| *u).collect();
                 let child_ids: Vec<Uuid> = def.iter_child_ids().map(|u| *u).collect();
+                let implied_ids: Vec<Uuid> = def.iter_implied_ids().map(|u| *u).collect();
                 let mut exclude_ids = vec![def.id];
                 exclude_ids.extend(&parent_ids);
                 exclude_ids.extend(&child_ids);
 
-                (parent_ids, child_ids, exclude_ids, def.field_type)
+                (
+                    parent_ids,
+                    child_ids,
+                    implied_ids,
+                    exclude_ids,
+                    def.field_type,
+                )
             };
 
             let add_new_parent =
@@ -427,6 +509,10 @@ impl EditTag {
                 false
             };
 
+            let mut implied_exclude_ids = vec![self.definition.as_ref().ok_or(())?.id];
+            implied_exclude_ids.extend(&implied_ids);
+            self.edit_implications_ui(ui, Arc::clone(&vault), &implied_ids, &implied_exclude_ids);
+
             self.edit_aliases_ui(ui);
 
             if add_new_parent && self.save() {
@@ -455,6 +541,10 @@ impl EditTag {
                 return Err(format!("Hierarchy error: {e}"));
             }
 
+            if let Err(e) = vault.find_implication_error(def) {
+                return Err(format!("Implication error: {e}"));
+            }
+
             Ok(())
         } else {
             Err("Definition does not exist".into())
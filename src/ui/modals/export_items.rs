@@ -0,0 +1,178 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use eframe::egui;
+use egui_modal::{Modal, ModalStyle};
+use itertools::Itertools;
+use poll_promise::Promise;
+use uuid::Uuid;
+
+use crate::data::transform::SourceKind;
+use crate::data::{Item, Vault};
+use crate::state::AppStateRef;
+use crate::tasks::export::{ExportFormat, ExportParams};
+use crate::ui::modals::AppModal;
+use crate::ui::{choice, theme, widgets};
+
+#[derive(Default)]
+pub struct ExportItems {
+    modal: Option<Modal>,
+    source_kind: SourceKind,
+    format: ExportFormat,
+    selected_ids: Option<HashSet<Uuid>>,
+    write_xmp_ratings: bool,
+    error_message: Option<String>,
+    opened: bool,
+}
+
+impl ExportItems {
+    fn source_items(state: &AppStateRef, vault: &Vault, kind: SourceKind) -> Vec<Arc<Item>> {
+        match kind {
+            SourceKind::Selection => vault.resolve_item_ids(&state.selected_item_ids()),
+            SourceKind::Filtered => vault.resolve_item_ids(&state.item_list_ids()),
+            SourceKind::All => vault.iter_items().map(|i| Arc::clone(&i)).collect(),
+        }
+    }
+
+    fn source_len(state: &AppStateRef, vault: &Vault, kind: SourceKind) -> usize {
+        match kind {
+            SourceKind::Selection => state.len_selected_items(),
+            SourceKind::Filtered => state.len_item_list(),
+            SourceKind::All => vault.len_items(),
+        }
+    }
+
+    fn fields_ui(&mut self, ui: &mut egui::Ui, vault: &Vault) {
+        let selected_ids = self.selected_ids.get_or_insert_with(HashSet::new);
+        let defs = vault
+            .iter_field_defs()
+            .map(|d| d.clone())
+            .sorted_by_key(|d| d.name.to_lowercase())
+            .collect_vec();
+
+        ui.horizontal(|ui| {
+            ui.label(format!("Fields ({} selected):", selected_ids.len()));
+            if ui.button("All").clicked() {
+                selected_ids.extend(defs.iter().map(|d| d.id));
+            }
+            if ui.button("None").clicked() {
+                selected_ids.clear();
+            }
+        });
+
+        egui::ScrollArea::vertical()
+            .max_height(300.0)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for def in &defs {
+                    ui.horizontal(|ui| {
+                        let mut checked = selected_ids.contains(&def.id);
+                        if ui.checkbox(&mut checked, "").changed() {
+                            if checked {
+                                selected_ids.insert(def.id);
+                            } else {
+                                selected_ids.remove(&def.id);
+                            }
+                        }
+                        ui.add(widgets::Tag::new(def));
+                    });
+                }
+            });
+    }
+}
+
+impl AppModal for ExportItems {
+    fn id(&self) -> egui::Id {
+        "export_items_modal".into()
+    }
+
+    fn update(&mut self, ctx: &egui::Context, state: AppStateRef) {
+        let modal = Modal::new(ctx, self.id().value()).with_style(&ModalStyle {
+            default_width: Some(400.0),
+            ..Default::default()
+        });
+        let Some(vault) = state.current_vault_opt() else {
+            return;
+        };
+
+        if self.selected_ids.is_none() {
+            let items = Self::source_items(&state, &vault, self.source_kind);
+            self.selected_ids = Some(crate::tasks::export::used_field_ids(&vault, &items));
+        }
+
+        modal.show(|ui| {
+            modal.title(ui, "Export items");
+            modal.frame(ui, |ui| {
+                ui.label("Items:");
+                for kind in [SourceKind::Selection, SourceKind::Filtered, SourceKind::All] {
+                    let label = format!("{kind} ({})", Self::source_len(&state, &vault, kind));
+                    ui.radio_value(&mut self.source_kind, kind, label);
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Format:");
+                    egui::ComboBox::new(self.id().with("format_box"), "")
+                        .selected_text(self.format.to_string())
+                        .show_ui(ui, |ui| {
+                            choice(ui, &mut self.format, ExportFormat::Csv);
+                            choice(ui, &mut self.format, ExportFormat::JsonLines);
+                            choice(ui, &mut self.format, ExportFormat::Sqlite);
+                        });
+                });
+
+                self.fields_ui(ui, &vault);
+
+                ui.checkbox(
+                    &mut self.write_xmp_ratings,
+                    "Also write ratings to XMP sidecar files",
+                );
+
+                if let Some(msg) = &self.error_message {
+                    ui.colored_label(theme::ERROR_TEXT, msg);
+                }
+            });
+            modal.buttons(ui, |ui| {
+                if modal.suggested_button(ui, "Export").clicked() {
+                    let items = Self::source_items(&state, &vault, self.source_kind);
+                    if items.is_empty() {
+                        self.error_message = "There are no items to export.".to_string().into();
+                        modal.open();
+                    } else {
+                        let params = ExportParams {
+                            format: self.format,
+                            field_ids: self
+                                .selected_ids
+                                .iter()
+                                .flatten()
+                                .copied()
+                                .sorted_by_key(|id| {
+                                    vault.get_definition(id).map(|d| d.name.to_lowercase())
+                                })
+                                .collect(),
+                            write_xmp_ratings: self.write_xmp_ratings,
+                        };
+                        let vault = Arc::clone(&vault);
+                        state.add_global_task(format!("Export {} items", items.len()), |_, p| {
+                            Promise::spawn_async(crate::tasks::export::export_items(
+                                vault, items, params, p,
+                            ))
+                        });
+                        modal.close();
+                    }
+                }
+                modal.button(ui, "Cancel");
+            });
+        });
+
+        if !self.opened {
+            modal.open();
+            self.opened = true;
+        }
+
+        self.modal = Some(modal);
+    }
+
+    fn is_open(&self) -> bool {
+        self.modal.as_ref().is_some_and(|m| m.is_open())
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::{HashMap, HashSet};
+
+use eframe::egui;
+use egui_modal::{Modal, ModalStyle};
+use itertools::Itertools;
+use poll_promise::Promise;
+use uuid::Uuid;
+
+use crate::data::{DefinitionImport, FieldDefinition};
+use crate::errors::{AppError, HierarchyError};
+use crate::state::AppStateRef;
+use crate::tasks::AsyncTaskResult;
+use crate::ui::modals::AppModal;
+use crate::ui::{indent, theme, widgets};
+
+const MAX_TREE_DEPTH: usize = 32;
+
+#[derive(Default)]
+pub struct ImportDefinitions {
+    modal: Option<Modal>,
+    source_name: String,
+    source: HashMap<Uuid, FieldDefinition>,
+    selected_ids: HashSet<Uuid>,
+    plan: Option<Result<DefinitionImport, HierarchyError>>,
+    error_message: Option<String>,
+    opened: bool,
+}
+
+impl ImportDefinitions {
+    fn set_source(&mut self, name: String, definitions: impl IntoIterator<Item = FieldDefinition>) {
+        self.source_name = name;
+        self.source = definitions.into_iter().map(|def| (def.id, def)).collect();
+        self.selected_ids.clear();
+        self.plan = None;
+    }
+
+    fn vault_request_id(&self) -> egui::Id {
+        self.id().with("load_vault")
+    }
+
+    fn file_request_id(&self) -> egui::Id {
+        self.id().with("load_file")
+    }
+
+    fn take_results(&mut self, state: &AppStateRef) {
+        match state.try_take_request_result(self.vault_request_id()) {
+            None => {}
+            Some(Ok(AsyncTaskResult::VaultLoaded { name, .. })) => {
+                if let Ok(vault) = state.get_vault(&name) {
+                    let defs = vault.iter_field_defs().map(|d| d.clone()).collect_vec();
+                    self.set_source(name, defs);
+                }
+            }
+            Some(Ok(res)) => self.error_message = Some(format!("Unexpected task result: {res:?}")),
+            Some(Err(e)) if AppError::UserCancelled.is_err(&e) => {}
+            Some(Err(e)) => self.error_message = Some(e.to_string()),
+        }
+        match state.try_take_request_result(self.file_request_id()) {
+            None => {}
+            Some(Ok(AsyncTaskResult::DefinitionsLoaded { path, definitions })) => {
+                self.set_source(path.display().to_string(), definitions);
+            }
+            Some(Ok(res)) => self.error_message = Some(format!("Unexpected task result: {res:?}")),
+            Some(Err(e)) if AppError::UserCancelled.is_err(&e) => {}
+            Some(Err(e)) => self.error_message = Some(e.to_string()),
+        }
+    }
+
+    fn update_plan(&mut self, state: &AppStateRef) {
+        if self.plan.is_some() || self.selected_ids.is_empty() {
+            return;
+        }
+        let Some(vault) = state.current_vault_opt() else {
+            return;
+        };
+        self.plan = Some(DefinitionImport::plan(
+            &self.source,
+            self.selected_ids.iter().copied(),
+            &vault,
+        ));
+    }
+
+    fn tree_ui(&mut self, ui: &mut egui::Ui, id: Uuid, depth: usize) {
+        let Some(def) = self.source.get(&id) else {
+            return;
+        };
+        let child_ids = def
+            .iter_child_ids()
+            .map(|cid| *cid)
+            .sorted_by_key(|cid| self.source.get(cid).map(|d| d.name.to_string()))
+            .collect_vec();
+
+        ui.horizontal(|ui| {
+            let mut checked = self.selected_ids.contains(&id);
+            if ui.checkbox(&mut checked, "").changed() {
+                if checked {
+                    self.selected_ids.insert(id);
+                } else {
+                    self.selected_ids.remove(&id);
+                }
+                self.plan = None;
+            }
+            ui.add(widgets::Tag::new(def));
+        });
+
+        if depth < MAX_TREE_DEPTH && !child_ids.is_empty() {
+            indent(ui, |ui| {
+                for child_id in child_ids {
+                    self.tree_ui(ui, child_id, depth + 1);
+                }
+            });
+        }
+    }
+
+    fn plan_ui(&self, ui: &mut egui::Ui) {
+        match &self.plan {
+            None => {}
+            Some(Err(e)) => {
+                ui.colored_label(theme::ERROR_TEXT, format!("Invalid hierarchy: {e}"));
+            }
+            Some(Ok(plan)) => {
+                ui.label(format!(
+                    "{} new, {} matched to existing definitions",
+                    plan.created.len(),
+                    plan.matched.len()
+                ));
+                for conflict in &plan.conflicts {
+                    ui.colored_label(theme::ERROR_TEXT, format!("Skipped {conflict}"));
+                }
+            }
+        }
+    }
+}
+
+impl AppModal for ImportDefinitions {
+    fn id(&self) -> egui::Id {
+        "import_definitions_modal".into()
+    }
+
+    fn update(&mut self, ctx: &egui::Context, state: AppStateRef) {
+        let vault_request_id = self.vault_request_id();
+        let file_request_id = self.file_request_id();
+        let modal = Modal::new(ctx, self.id().value()).with_style(&ModalStyle {
+            default_width: Some(500.0),
+            ..Default::default()
+        });
+
+        let curr_name = state.current_vault_name().unwrap_or_default();
+        let vault_names = state.valid_vault_names();
+
+        self.take_results(&state);
+        self.update_plan(&state);
+
+        modal.show(|ui| {
+            modal.title(ui, "Import definitions");
+            modal.frame(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Source:");
+                    egui::ComboBox::new(self.id().with("source_box"), "")
+                        .selected_text(&self.source_name)
+                        .show_ui(ui, |ui| {
+                            for vault_name in vault_names {
+                                if vault_name == curr_name {
+                                    continue;
+                                }
+                                if ui
+                                    .selectable_label(self.source_name == vault_name, &vault_name)
+                                    .clicked()
+                                {
+                                    if let Ok(vault) = state.get_vault(&vault_name) {
+                                        let defs = vault
+                                            .iter_field_defs()
+                                            .map(|d| d.clone())
+                                            .collect_vec();
+                                        self.set_source(vault_name, defs);
+                                    }
+                                }
+                            }
+                        });
+                    if ui.button("Load vault...").clicked() {
+                        state.add_task_request(vault_request_id, "Load vault", |s, p| {
+                            Promise::spawn_async(crate::tasks::vault::choose_and_load_vault(
+                                s, p, false,
+                            ))
+                        });
+                    }
+                    if ui.button("Load file...").clicked() {
+                        state.add_task_request(file_request_id, "Load definitions", |_, p| {
+                            Promise::spawn_async(
+                                crate::tasks::definitions::choose_and_load_definitions(p),
+                            )
+                        });
+                    }
+                });
+
+                if !self.source.is_empty() {
+                    ui.label("Select subtrees to import:");
+                    let root_ids = self
+                        .source
+                        .values()
+                        .filter(|def| def.iter_parent_ids().next().is_none())
+                        .sorted_by_key(|def| def.name.to_string())
+                        .map(|def| def.id)
+                        .collect_vec();
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .auto_shrink([false, true])
+                        .show(ui, |ui| {
+                            for id in root_ids {
+                                self.tree_ui(ui, id, 0);
+                            }
+                        });
+                }
+
+                self.plan_ui(ui);
+
+                if let Some(msg) = &self.error_message {
+                    ui.colored_label(theme::ERROR_TEXT, msg);
+                }
+            });
+            modal.buttons(ui, |ui| {
+                let can_import = matches!(&self.plan, Some(Ok(plan)) if !plan.is_empty());
+                if ui
+                    .add_enabled(can_import, egui::Button::new("Import"))
+                    .clicked()
+                {
+                    if let (Some(Ok(plan)), Ok(vault)) =
+                        (self.plan.take(), state.current_vault_catch())
+                    {
+                        plan.apply(&vault);
+                        state.save_current_vault_deferred();
+                        modal.close();
+                    }
+                }
+                modal.button(ui, "Cancel");
+            });
+        });
+
+        if !self.opened {
+            modal.open();
+            self.opened = true;
+        }
+
+        self.modal = Some(modal);
+    }
+
+    fn is_open(&self) -> bool {
+        self.modal.as_ref().is_some_and(|m| m.is_open())
+    }
+}
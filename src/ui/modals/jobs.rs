@@ -0,0 +1,202 @@
+use std::sync::Arc;
+
+use chrono::Local;
+use eframe::egui;
+use egui_modal::{Modal, ModalStyle};
+use itertools::Itertools;
+
+use crate::data::{Job, JobPriority, JobStatus, Vault};
+use crate::state::AppStateRef;
+use crate::tasks::job::{save_jobs_deferred, start_job_runner};
+use crate::ui::modals::AppModal;
+use crate::ui::{choice, theme};
+
+#[derive(Default)]
+pub struct Jobs {
+    modal: Option<Modal>,
+    vault_name: Option<String>,
+    prompt_resume: bool,
+    modified: bool,
+    opened: bool,
+}
+
+impl Jobs {
+    /// Opens the dialog for `vault_name`, asking whether the jobs left unfinished from a
+    /// previous session should be resumed.
+    pub fn resume(vault_name: String) -> Self {
+        Self {
+            vault_name: Some(vault_name),
+            prompt_resume: true,
+            ..Default::default()
+        }
+    }
+
+    fn job_ui(&mut self, ui: &mut egui::Ui, state: &AppStateRef, vault: &Arc<Vault>, job: &Job) {
+        let id = job.id;
+
+        ui.horizontal(|ui| {
+            ui.strong(&job.name);
+            ui.label(format!(
+                "(created {})",
+                job.created.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            ));
+            match job.status {
+                JobStatus::Paused => ui.colored_label(theme::ERROR_TEXT, "Paused"),
+                status => ui.label(status.to_string()),
+            };
+        });
+
+        ui.indent(id, |ui| {
+            let total = job.items.len();
+            let done = total - job.len_pending();
+            ui.add(
+                egui::ProgressBar::new(job.progress())
+                    .text(format!("{done}/{total} items"))
+                    .animate(job.status == JobStatus::Running),
+            );
+
+            let failed = job.len_failed();
+            if failed > 0 {
+                ui.colored_label(theme::ERROR_TEXT, format!("{failed} items failed"));
+            }
+
+            ui.horizontal(|ui| {
+                if !job.is_finished() {
+                    let mut priority = job.priority;
+                    ui.label("Priority: ");
+                    choice(ui, &mut priority, JobPriority::Low);
+                    choice(ui, &mut priority, JobPriority::Normal);
+                    choice(ui, &mut priority, JobPriority::High);
+                    if priority != job.priority {
+                        vault.jobs().update(&id, |job| job.priority = priority);
+                        self.modified = true;
+                    }
+                    ui.separator();
+                }
+
+                match job.status {
+                    JobStatus::Queued | JobStatus::Running => {
+                        if ui.button("Pause").clicked() {
+                            vault.jobs().pause(&id);
+                            self.modified = true;
+                        }
+                    }
+                    JobStatus::Paused => {
+                        if ui.button("Resume").clicked() {
+                            vault.jobs().resume(&id);
+                            start_job_runner(state, Arc::clone(vault));
+                            self.modified = true;
+                        }
+                    }
+                    JobStatus::Completed => {}
+                }
+                if ui.button("Remove").clicked() {
+                    vault.jobs().remove(&id);
+                    self.modified = true;
+                }
+            });
+        });
+    }
+}
+
+impl AppModal for Jobs {
+    fn id(&self) -> egui::Id {
+        "jobs_modal".into()
+    }
+
+    fn update(&mut self, ctx: &egui::Context, state: AppStateRef) {
+        let modal = Modal::new(ctx, self.id().value()).with_style(&ModalStyle {
+            default_width: Some(500.0),
+            ..Default::default()
+        });
+
+        let vault = match &self.vault_name {
+            Some(name) => state.get_vault(name).ok(),
+            None => state.current_vault_opt(),
+        };
+        let Some(vault) = vault else {
+            return;
+        };
+
+        let jobs = vault
+            .jobs()
+            .lock()
+            .iter()
+            .cloned()
+            .sorted_by_key(|job| {
+                (
+                    job.is_finished(),
+                    std::cmp::Reverse(job.priority),
+                    job.created,
+                )
+            })
+            .collect_vec();
+
+        modal.show(|ui| {
+            modal.title(ui, format!("Jobs of {}", vault.name));
+            modal.frame(ui, |ui| {
+                if self.prompt_resume {
+                    ui.label(format!(
+                        "{} jobs were left unfinished when the app was last closed. \
+                         Resume them now?",
+                        vault.jobs().len_unfinished()
+                    ));
+                    ui.separator();
+                }
+
+                if jobs.is_empty() {
+                    ui.label(
+                        "There are no jobs. \
+                         Use \"Run as resumable job\" when transforming items to add one.",
+                    );
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        for job in &jobs {
+                            self.job_ui(ui, &state, &vault, job);
+                            ui.separator();
+                        }
+                    });
+            });
+            modal.buttons(ui, |ui| {
+                let any_paused = jobs.iter().any(|job| job.status == JobStatus::Paused);
+                let resume_button =
+                    egui::Button::new("Resume all").fill(ui.visuals().selection.bg_fill);
+                if ui.add_enabled(any_paused, resume_button).clicked() {
+                    vault.jobs().resume_all();
+                    start_job_runner(&state, Arc::clone(&vault));
+                    self.modified = true;
+                    self.prompt_resume = false;
+                }
+                let any_finished = jobs.iter().any(Job::is_finished);
+                if ui
+                    .add_enabled(any_finished, egui::Button::new("Clear completed"))
+                    .clicked()
+                {
+                    for job in jobs.iter().filter(|job| job.is_finished()) {
+                        vault.jobs().remove(&job.id);
+                    }
+                    self.modified = true;
+                }
+                if modal.button(ui, "Close").clicked() && self.modified {
+                    self.modified = false;
+                    save_jobs_deferred(&state, Arc::clone(&vault));
+                }
+            });
+        });
+
+        if !self.opened {
+            modal.open();
+            self.opened = true;
+        }
+
+        self.modal = Some(modal);
+    }
+
+    fn is_open(&self) -> bool {
+        self.modal.as_ref().is_some_and(|m| m.is_open())
+    }
+}
@@ -0,0 +1,94 @@
+use eframe::egui;
+use egui_modal::Modal;
+use poll_promise::Promise;
+
+use crate::data::FieldConflictPolicy;
+use crate::state::AppStateRef;
+use crate::tasks::merge::MergeParams;
+use crate::ui::modals::AppModal;
+use crate::ui::{behaviour_select, choice, theme};
+
+#[derive(Default)]
+pub struct MergeVault {
+    modal: Option<Modal>,
+    params: MergeParams,
+    error_message: Option<String>,
+    opened: bool,
+}
+
+impl AppModal for MergeVault {
+    fn id(&self) -> egui::Id {
+        "merge_vault_modal".into()
+    }
+
+    fn update(&mut self, ctx: &egui::Context, state: AppStateRef) {
+        let modal = Modal::new(ctx, self.id().value());
+        let curr_name = state.current_vault_name().unwrap_or_default();
+
+        modal.show(|ui| {
+            modal.title(ui, "Merge vault");
+            modal.frame(ui, |ui| {
+                ui.label(format!(
+                    "Move all items and files into the current vault ({curr_name}) from:"
+                ));
+                egui::ComboBox::new(self.id().with("source_box"), "")
+                    .selected_text(&self.params.source_vault_name)
+                    .show_ui(ui, |ui| {
+                        let v = &mut self.params.source_vault_name;
+                        for vault_name in state.valid_vault_names() {
+                            if vault_name != curr_name {
+                                ui.selectable_value(v, vault_name.clone(), vault_name);
+                            }
+                        }
+                    });
+
+                ui.label("If a file already exists at the destination:");
+                behaviour_select(ui, &mut self.params.existing_behaviour);
+
+                ui.label("If a field has different values:");
+                egui::ComboBox::new(self.id().with("policy_box"), "")
+                    .selected_text(self.params.conflict_policy.to_string())
+                    .show_ui(ui, |ui| {
+                        let v = &mut self.params.conflict_policy;
+                        choice(ui, v, FieldConflictPolicy::KeepTarget);
+                        choice(ui, v, FieldConflictPolicy::TakeSource);
+                    });
+
+                if let Some(msg) = &self.error_message {
+                    ui.colored_label(theme::ERROR_TEXT, msg);
+                }
+            });
+            modal.buttons(ui, |ui| {
+                if modal.suggested_button(ui, "Merge").clicked() {
+                    if self.params.source_vault_name.is_empty() {
+                        self.error_message = "Please select a vault.".to_string().into();
+                        modal.open();
+                    } else if let Ok(vault) = state.current_vault_catch() {
+                        let params = std::mem::take(&mut self.params);
+                        state.add_global_task(
+                            format!("Merge {} into {}", params.source_vault_name, vault.name),
+                            |s, p| {
+                                Promise::spawn_async(crate::tasks::merge::merge_vaults(
+                                    s, vault, params, p,
+                                ))
+                            },
+                        );
+                        modal.close();
+                    }
+                }
+                modal.button(ui, "Cancel");
+            });
+        });
+
+        if !self.opened {
+            modal.open();
+            self.opened = true;
+        }
+
+        self.modal = Some(modal);
+    }
+
+    fn is_open(&self) -> bool {
+        self.modal.as_ref().is_some_and(|m| m.is_open())
+    }
+}
@@ -1,3 +1,4 @@
+use eframe::egui;
 use eframe::egui::Color32;
 use egui_modal::Modal;
 use poll_promise::Promise;
@@ -11,6 +12,7 @@ use crate::ui::modals::AppModal;
 pub struct NewVault {
     modal: Option<Modal>,
     name: String,
+    template_name: String,
     error_message: Option<String>,
     opened: bool,
 }
@@ -29,6 +31,17 @@ impl AppModal for NewVault {
                 ui.label("Enter name of new vault:");
                 ui.text_edit_singleline(&mut self.name);
 
+                ui.label("Copy definitions from:");
+                egui::ComboBox::new(self.id().with("template_box"), "")
+                    .selected_text(&self.template_name)
+                    .show_ui(ui, |ui| {
+                        let v = &mut self.template_name;
+                        ui.selectable_value(v, String::new(), "--");
+                        for vault_name in state.valid_vault_names() {
+                            ui.selectable_value(v, vault_name.clone(), vault_name);
+                        }
+                    });
+
                 if let Some(msg) = &self.error_message {
                     ui.colored_label(Color32::RED, msg);
                 }
@@ -39,10 +52,15 @@ impl AppModal for NewVault {
                         self.error_message = "Please enter a vault name.".to_string().into();
                         modal.open();
                     } else {
-                        let Self { name, .. } = std::mem::take(self);
+                        let Self {
+                            name,
+                            template_name,
+                            ..
+                        } = std::mem::take(self);
+                        let template = state.get_vault(&template_name).ok();
                         state.set_vault_loading();
                         state.add_global_task("Create vault", |s, p| {
-                            Promise::spawn_async(tasks::vault::save_new_vault(s, name, p))
+                            Promise::spawn_async(tasks::vault::save_new_vault(s, name, template, p))
                         });
                         modal.close();
                     }
@@ -0,0 +1,294 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use eframe::egui;
+
+use crate::data::parse::FilterExpressionParseResult;
+use crate::data::{
+    FieldStore, FieldValue, FilterExpression, ReviewSession, ThumbnailCacheItem, ThumbnailParams,
+    Vault,
+};
+use crate::fields;
+use crate::state::AppStateRef;
+use crate::take_shortcut;
+use crate::tasks::filter::evaluate_items_filter;
+use crate::tasks::sort::sort_items_unstable;
+use crate::tasks::AsyncTaskResult;
+use crate::ui::cloneable_state::CloneableTempState;
+use crate::ui::item_panel::ItemPanel;
+use crate::ui::modals::AppModal;
+use crate::ui::{theme, widgets};
+
+const REVIEW_IMAGE_HEIGHT: usize = 1024;
+const ITEM_PANEL_WIDTH: f32 = 320.0;
+
+pub struct ReviewQueue {
+    widget_state: State,
+    opened: bool,
+    filter_text: String,
+    error_message: Option<String>,
+}
+
+impl Default for ReviewQueue {
+    fn default() -> Self {
+        Self {
+            widget_state: Default::default(),
+            opened: false,
+            filter_text: format!("is:untagged -field:{}", fields::general::SKIP.id),
+            error_message: None,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct State {
+    opened: bool,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self { opened: true }
+    }
+}
+
+impl CloneableTempState for State {}
+
+impl ReviewQueue {
+    fn start(&mut self, state: &AppStateRef, vault: &Arc<Vault>) {
+        let filter = self
+            .filter_text
+            .parse::<FilterExpressionParseResult>()
+            .map_or(FilterExpression::None, |r| r.expr);
+        let Ok(mut items) = state.catch(
+            || "building review queue",
+            || -> anyhow::Result<_> {
+                let mut items = evaluate_items_filter(vault, &filter)?;
+                sort_items_unstable(&mut items, vault, &state.sorts())?;
+                Ok(items)
+            },
+        ) else {
+            return;
+        };
+
+        if items.is_empty() {
+            self.error_message = Some("There are no items matching this filter.".to_string());
+            return;
+        }
+
+        let paths = items
+            .drain(..)
+            .map(|item| item.path().to_string())
+            .collect();
+        vault.set_review_session(Some(ReviewSession::new(self.filter_text.clone(), paths)));
+        state.save_vault_deferred(Arc::clone(vault));
+        self.error_message = None;
+    }
+
+    fn start_ui(&mut self, ui: &mut egui::Ui, state: &AppStateRef, vault: &Arc<Vault>) {
+        ui.label("Review each item matching the filter one at a time:");
+        widgets::SearchBox::new(
+            self.id().with("filter"),
+            &mut self.filter_text,
+            Arc::clone(vault),
+        )
+        .desired_width(f32::INFINITY)
+        .show(ui);
+
+        if let Some(msg) = &self.error_message {
+            ui.colored_label(theme::ERROR_TEXT, msg);
+        }
+
+        if ui.button("Start").clicked() {
+            self.start(state, vault);
+        }
+    }
+
+    fn image_ui(ui: &mut egui::Ui, state: &AppStateRef, vault: &Vault, path: &str) {
+        let Some(item) = vault.get_item_opt(Path::new(path)).ok().flatten() else {
+            return;
+        };
+        let Ok(abs_path) = vault.resolve_abs_path(Path::new(path)) else {
+            return;
+        };
+        let params = ThumbnailParams {
+            abs_path,
+            rel_path: path.to_string(),
+            last_modified: item
+                .get_known_field_value(fields::general::LAST_MODIFIED)
+                .ok()
+                .flatten(),
+            height: REVIEW_IMAGE_HEIGHT,
+            transform_params: None,
+        };
+
+        ui.centered_and_justified(|ui| {
+            if let ThumbnailCacheItem::Loaded(hndl) = state.resolve_thumbnail(&params) {
+                ui.add(
+                    egui::Image::from_texture(egui::load::SizedTexture::from_handle(&hndl))
+                        .shrink_to_fit(),
+                );
+            } else {
+                ui.spinner();
+            }
+        });
+    }
+
+    /// Returns whether the session was changed.
+    fn session_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        state: &AppStateRef,
+        vault: &Arc<Vault>,
+        session: &mut ReviewSession,
+    ) -> bool {
+        let mut changed = false;
+
+        // items removed from the vault since the session started are dropped from the queue
+        while let Some(path) = session.current() {
+            if vault.get_item_opt(Path::new(path)).ok().flatten().is_some() {
+                break;
+            }
+            session.remove_current();
+            changed = true;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let progress = session.reviewed as f32 / session.len().max(1) as f32;
+        ui.add(egui::ProgressBar::new(progress).text(format!(
+            "{} / {} reviewed",
+            session.reviewed,
+            session.len()
+        )));
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    session.can_go_back(),
+                    egui::Button::new("\u{23f4} Previous"),
+                )
+                .on_hover_text("Alt+Left")
+                .clicked()
+                || (session.can_go_back() && take_shortcut!(ui, ALT + ArrowLeft))
+            {
+                session.back();
+                changed = true;
+            }
+            if !session.is_finished() {
+                if ui
+                    .button("Next \u{23f5}")
+                    .on_hover_text("Alt+Right")
+                    .clicked()
+                    || take_shortcut!(ui, ALT + ArrowRight)
+                {
+                    session.advance();
+                    changed = true;
+                }
+                if ui
+                    .button("Skip")
+                    .on_hover_text("Mark as skipped and move on")
+                    .clicked()
+                {
+                    if let Some(item) = session
+                        .current()
+                        .and_then(|path| vault.get_item_opt(Path::new(path)).ok().flatten())
+                    {
+                        item.set_field_value(fields::general::SKIP.id, FieldValue::Tag);
+                        let _ = state.commit_item_catch(Some(Arc::clone(vault)), &item, true);
+                    }
+                    session.advance();
+                    changed = true;
+                }
+            }
+            if ui.button("End session").clicked() {
+                vault.set_review_session(None);
+                state.save_vault_deferred(Arc::clone(vault));
+            }
+        });
+        ui.separator();
+
+        let Some(path) = session.current().map(str::to_string) else {
+            ui.label(format!("All {} items have been reviewed.", session.len()));
+            return changed;
+        };
+        let Some(item) = vault.get_item_opt(Path::new(&path)).ok().flatten() else {
+            return changed;
+        };
+
+        let request_id = self.id().with("next_item");
+        egui::SidePanel::right(self.id().with("item_panel"))
+            .resizable(false)
+            .exact_width(ITEM_PANEL_WIDTH)
+            .show_inside(ui, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let items = vec![item];
+                    ui.add(
+                        ItemPanel::new(
+                            self.id().with("item_panel"),
+                            &items,
+                            Arc::clone(vault),
+                            state.clone(),
+                        )
+                        .next_item_request_id(request_id),
+                    );
+                });
+            });
+        egui::CentralPanel::default().show_inside(ui, |ui| {
+            ui.label(&path);
+            Self::image_ui(ui, state, vault, &path);
+        });
+
+        if matches!(
+            state.try_take_request_result(request_id),
+            Some(Ok(AsyncTaskResult::NextItem))
+        ) {
+            session.advance();
+            changed = true;
+        }
+
+        changed
+    }
+}
+
+impl AppModal for ReviewQueue {
+    fn id(&self) -> egui::Id {
+        "review_queue_window".into()
+    }
+
+    fn update(&mut self, ctx: &egui::Context, state: AppStateRef) {
+        self.widget_state = State::load(ctx, self.id()).unwrap_or_default();
+        let mut opened = self.widget_state.opened;
+
+        egui::Window::new("Review queue")
+            .id(self.id())
+            .open(&mut opened)
+            .default_size([900.0, 650.0])
+            .show(ctx, |ui| {
+                let Ok(vault) = state.current_vault_catch() else {
+                    return;
+                };
+                match vault.review_session() {
+                    None => self.start_ui(ui, &state, &vault),
+                    Some(mut session) => {
+                        if self.session_ui(ui, &state, &vault, &mut session)
+                            && vault.review_session().is_some()
+                        {
+                            vault.set_review_session(Some(session));
+                            state.save_vault_deferred(vault);
+                        }
+                    }
+                }
+            });
+
+        self.widget_state.opened = opened;
+        self.opened = self.widget_state.opened;
+        std::mem::take(&mut self.widget_state).store(ctx, self.id());
+    }
+
+    fn dispose(&mut self, ctx: &egui::Context, _state: AppStateRef) {
+        State::dispose(ctx, self.id());
+    }
+
+    fn is_open(&self) -> bool {
+        self.opened
+    }
+}
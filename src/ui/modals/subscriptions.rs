@@ -0,0 +1,237 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use chrono::{DateTime, Local, Utc};
+use eframe::egui;
+use egui_modal::{Modal, ModalStyle};
+use itertools::Itertools;
+use poll_promise::Promise;
+use uuid::Uuid;
+
+use crate::data::{Subscription, Vault};
+use crate::state::AppStateRef;
+use crate::ui::modals::AppModal;
+use crate::ui::theme;
+
+#[derive(Default)]
+pub struct Subscriptions {
+    modal: Option<Modal>,
+    vault_name: Option<String>,
+    selected_ids: HashSet<Uuid>,
+    prompt_overdue: bool,
+    modified: bool,
+    opened: bool,
+}
+
+fn format_date(date: DateTime<Utc>) -> String {
+    date.with_timezone(&Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+impl Subscriptions {
+    /// Opens the dialog for `vault_name` with its overdue subscriptions selected, asking whether
+    /// they should be run now.
+    pub fn overdue(vault_name: String, ids: Vec<Uuid>) -> Self {
+        Self {
+            vault_name: Some(vault_name),
+            selected_ids: ids.into_iter().collect(),
+            prompt_overdue: true,
+            ..Default::default()
+        }
+    }
+
+    fn run(state: &AppStateRef, vault: &Vault, ids: Vec<Uuid>) {
+        let vault_name = vault.name.clone();
+        let task_name = if ids.len() == 1 {
+            vault
+                .get_subscription(&ids[0])
+                .map(|s| format!("Run subscription {}", s.name))
+                .unwrap_or_default()
+        } else {
+            format!("Run {} subscriptions", ids.len())
+        };
+        state.add_global_task(task_name, |s, p| {
+            Promise::spawn_async(crate::tasks::download::run_subscriptions(
+                s, vault_name, ids, p,
+            ))
+        });
+    }
+
+    fn subscription_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        state: &AppStateRef,
+        vault: &Vault,
+        mut subscription: Subscription,
+    ) {
+        let now = Utc::now();
+        let id = subscription.id;
+
+        ui.horizontal(|ui| {
+            let mut checked = self.selected_ids.contains(&id);
+            if ui.checkbox(&mut checked, "").changed() {
+                if checked {
+                    self.selected_ids.insert(id);
+                } else {
+                    self.selected_ids.remove(&id);
+                }
+            }
+            ui.strong(&subscription.name);
+            ui.label(format!("({})", subscription.params.source));
+            if subscription.is_overdue(now) {
+                ui.colored_label(theme::ERROR_TEXT, "Overdue");
+            }
+        });
+
+        ui.indent(id, |ui| {
+            ui.horizontal(|ui| {
+                let mut repeat = subscription.interval_hours.is_some();
+                let mut hours = subscription.interval_hours.unwrap_or(24);
+                ui.checkbox(&mut repeat, "Re-run every");
+                ui.add_enabled(
+                    repeat,
+                    egui::DragValue::new(&mut hours)
+                        .clamp_range(1..=24 * 365)
+                        .suffix(" hours"),
+                );
+                let interval_hours = repeat.then_some(hours);
+                if interval_hours != subscription.interval_hours {
+                    subscription.interval_hours = interval_hours;
+                    vault.set_subscription(subscription.clone());
+                    self.modified = true;
+                }
+            });
+
+            ui.horizontal(|ui| {
+                match subscription.last_run {
+                    Some(last_run) => ui.label(format!("Last run: {}", format_date(last_run))),
+                    None => ui.label("Never run"),
+                };
+                if let Some(next_run) = subscription.next_run().filter(|d| *d > now) {
+                    ui.label(format!("Next run: {}", format_date(next_run)));
+                }
+            });
+
+            egui::CollapsingHeader::new(format!(
+                "History ({} new files in total)",
+                subscription.total_new_files()
+            ))
+            .id_source(id.to_string())
+            .show(ui, |ui| {
+                if subscription.history.is_empty() {
+                    ui.weak("No runs yet");
+                }
+                for run in subscription.history.iter().rev() {
+                    ui.horizontal(|ui| {
+                        ui.label(format_date(run.started));
+                        match &run.error {
+                            None => ui.label(format!("{} new files", run.new_files)),
+                            Some(e) => ui.colored_label(theme::ERROR_TEXT, e),
+                        };
+                    });
+                }
+            });
+
+            ui.horizontal(|ui| {
+                if ui.button("Run now").clicked() {
+                    Self::run(state, vault, vec![id]);
+                }
+                if ui.button("Delete").clicked() {
+                    vault.remove_subscription(&id);
+                    self.selected_ids.remove(&id);
+                    self.modified = true;
+                }
+            });
+        });
+    }
+}
+
+impl AppModal for Subscriptions {
+    fn id(&self) -> egui::Id {
+        "subscriptions_modal".into()
+    }
+
+    fn update(&mut self, ctx: &egui::Context, state: AppStateRef) {
+        let modal = Modal::new(ctx, self.id().value()).with_style(&ModalStyle {
+            default_width: Some(500.0),
+            ..Default::default()
+        });
+
+        let vault = match &self.vault_name {
+            Some(name) => state.get_vault(name).ok(),
+            None => state.current_vault_opt(),
+        };
+        let Some(vault) = vault else {
+            return;
+        };
+
+        let subscriptions = vault
+            .iter_subscriptions()
+            .map(|s| s.clone())
+            .sorted_by_key(|s| s.name.to_lowercase())
+            .collect_vec();
+
+        modal.show(|ui| {
+            modal.title(ui, format!("Subscriptions of {}", vault.name));
+            modal.frame(ui, |ui| {
+                if self.prompt_overdue {
+                    ui.label(format!(
+                        "{} subscriptions are due to be run again. Run the selected subscriptions \
+                         now?",
+                        self.selected_ids.len()
+                    ));
+                    ui.separator();
+                }
+
+                if subscriptions.is_empty() {
+                    ui.label(
+                        "There are no subscriptions yet. \
+                         Use \"Save as subscription\" in the Download dialog to add one.",
+                    );
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .auto_shrink([false, true])
+                    .show(ui, |ui| {
+                        for subscription in subscriptions {
+                            self.subscription_ui(ui, &state, &vault, subscription);
+                            ui.separator();
+                        }
+                    });
+            });
+            modal.buttons(ui, |ui| {
+                let ids = self
+                    .selected_ids
+                    .iter()
+                    .copied()
+                    .filter(|id| vault.get_subscription(id).is_some())
+                    .collect_vec();
+                let run_button = egui::Button::new(format!("Run selected ({})", ids.len()))
+                    .fill(ui.visuals().selection.bg_fill);
+                if ui.add_enabled(!ids.is_empty(), run_button).clicked() {
+                    // the task saves the vault once it has run
+                    self.modified = false;
+                    Self::run(&state, &vault, ids);
+                    modal.close();
+                }
+                if modal.button(ui, "Close").clicked() && self.modified {
+                    self.modified = false;
+                    state.save_vault_deferred(Arc::clone(&vault));
+                }
+            });
+        });
+
+        if !self.opened {
+            modal.open();
+            self.opened = true;
+        }
+
+        self.modal = Some(modal);
+    }
+
+    fn is_open(&self) -> bool {
+        self.modal.as_ref().is_some_and(|m| m.is_open())
+    }
+}
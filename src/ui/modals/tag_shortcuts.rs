@@ -1,17 +1,26 @@
+use std::sync::Arc;
+
 use eframe::egui;
 use eframe::egui::KeyboardShortcut;
+use uuid::Uuid;
 
-use crate::data::{FieldType, ShortcutAction, ShortcutBehaviour};
+use crate::data::{
+    FieldType, ShortcutAction, ShortcutBehaviour, ShortcutProfile, ShortcutProfileRef,
+    ShortcutProfileScope, Vault,
+};
 use crate::state::AppStateRef;
 use crate::ui::cloneable_state::CloneableTempState;
 use crate::ui::modals::AppModal;
-use crate::ui::{buttons, widgets};
+use crate::ui::{buttons, choice, widgets};
 
 #[derive(Default)]
 pub struct TagShortcuts {
     widget_state: State,
     opened: bool,
     updated: bool,
+    recording: bool,
+    new_profile_name: String,
+    new_profile_scope: ShortcutProfileScope,
 }
 
 #[derive(Clone)]
@@ -27,15 +36,119 @@ impl Default for State {
 
 impl CloneableTempState for State {}
 
+/// Picks a field for an action, where an unset field is stored as the nil UUID.
+fn find_field_ui(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    vault: Arc<Vault>,
+    field_id: Uuid,
+    field_types: &[FieldType],
+) -> Uuid {
+    let mut field_id_opt = (!field_id.is_nil()).then_some(field_id);
+    ui.add(
+        widgets::FindTag::new(id.with("find_tag"), &mut field_id_opt, vault)
+            .show_tag(!field_id.is_nil())
+            .filter_types(field_types)
+            .exclude_ids(&[field_id]),
+    );
+    field_id_opt.unwrap_or(field_id)
+}
+
+const VALUE_TYPES: [FieldType; 7] = [
+    FieldType::Boolean,
+    FieldType::Int,
+    FieldType::Float,
+    FieldType::String,
+    FieldType::ItemRef,
+    FieldType::Colour,
+    FieldType::DateTime,
+];
+
 impl TagShortcuts {
+    fn action_ui(
+        ui: &mut egui::Ui,
+        id: egui::Id,
+        state: &AppStateRef,
+        vault: Arc<Vault>,
+        action: &mut ShortcutAction,
+    ) {
+        egui::ComboBox::new(id.with("action_kind"), "")
+            .selected_text(action.label())
+            .width(130.0)
+            .show_ui(ui, |ui| {
+                for kind in ShortcutAction::kinds() {
+                    let label = kind.label();
+                    if ui
+                        .selectable_label(action.is_same_kind(&kind), label)
+                        .clicked()
+                        && !action.is_same_kind(&kind)
+                    {
+                        *action = kind;
+                    }
+                }
+            });
+
+        match action {
+            ShortcutAction::ToggleTag(tag_id) | ShortcutAction::TagSelection(tag_id) => {
+                *tag_id = find_field_ui(ui, id, vault, *tag_id, &[FieldType::Tag]);
+            }
+            ShortcutAction::RemoveField(field_id) => {
+                let mut field_types = VALUE_TYPES.to_vec();
+                field_types.push(FieldType::Tag);
+                *field_id = find_field_ui(ui, id, Arc::clone(&vault), *field_id, &field_types);
+            }
+            ShortcutAction::SetField(field_id, value) => {
+                let new_field_id =
+                    find_field_ui(ui, id, Arc::clone(&vault), *field_id, &VALUE_TYPES);
+                let Some(def) = vault.get_definition(&new_field_id) else {
+                    return;
+                };
+                let mut value_opt = (new_field_id == *field_id
+                    && value.get_type() == def.field_type)
+                    .then(|| value.clone());
+                ui.add(widgets::TagValueEdit::new(
+                    id.with("value"),
+                    def.field_type,
+                    &mut value_opt,
+                ));
+                *field_id = new_field_id;
+                if let Some(new_value) = value_opt.filter(|v| v.get_type() == def.field_type) {
+                    *value = new_value;
+                }
+            }
+            ShortcutAction::Search(search_text) => {
+                ui.add(
+                    egui::TextEdit::singleline(search_text)
+                        .hint_text("Search query")
+                        .desired_width(200.0),
+                );
+            }
+            ShortcutAction::RunPreset(preset_name) => {
+                egui::ComboBox::new(id.with("preset"), "")
+                    .selected_text(preset_name.as_str())
+                    .width(200.0)
+                    .show_ui(ui, |ui| {
+                        for preset in state.transform_presets() {
+                            ui.selectable_value(preset_name, preset.name.clone(), preset.name);
+                        }
+                    });
+            }
+            ShortcutAction::None
+            | ShortcutAction::ToggleSkip
+            | ShortcutAction::OpenPreview
+            | ShortcutAction::RemoveItem => {}
+        }
+    }
+
     fn table_row(
         &mut self,
         shortcut: KeyboardShortcut,
         behaviour: &mut ShortcutBehaviour,
         row: &mut egui_extras::Strip,
-        state: AppStateRef,
-    ) {
+        state: &AppStateRef,
+    ) -> bool {
         let id = self.id().with(shortcut);
+        let mut removed = false;
 
         row.cell(|ui| {
             ui.label(shortcut.format(&egui::ModifierNames::NAMES, false));
@@ -45,47 +158,138 @@ impl TagShortcuts {
             let Ok(vault) = state.current_vault_catch() else {
                 return;
             };
-            match behaviour.action {
-                ShortcutAction::None => {
-                    let mut tag_id_opt = None;
-                    ui.add(
-                        widgets::FindTag::new(id.with("find_tag"), &mut tag_id_opt, vault)
-                            .filter_types(&[FieldType::Tag]),
-                    );
-                    if let Some(new_tag_id) = tag_id_opt {
-                        behaviour.action = ShortcutAction::ToggleTag(new_tag_id);
-                    }
-                }
-                ShortcutAction::ToggleTag(tag_id) => {
-                    let mut tag_id_opt = Some(tag_id);
-                    ui.add(
-                        widgets::FindTag::new(id.with("find_tag"), &mut tag_id_opt, vault)
-                            .show_tag(true)
-                            .filter_types(&[FieldType::Tag])
-                            .exclude_ids(&[tag_id]),
-                    );
-                    match tag_id_opt {
-                        Some(new_tag_id) if new_tag_id != tag_id => {
-                            behaviour.action = ShortcutAction::ToggleTag(new_tag_id);
+            ui.horizontal(|ui| {
+                Self::action_ui(ui, id, state, vault, &mut behaviour.action);
+            });
+        });
+
+        row.cell(|ui| {
+            ui.checkbox(&mut behaviour.move_next, "Move next?");
+        });
+
+        row.cell(|ui| {
+            if ui.button("Remove").clicked() {
+                removed = true;
+            }
+        });
+
+        removed
+    }
+
+    fn profile_ui(&mut self, ui: &mut egui::Ui, state: &AppStateRef) {
+        let active = state.active_shortcut_profile();
+
+        ui.horizontal(|ui| {
+            ui.label("Profile:");
+            egui::ComboBox::new(self.id().with("profile"), "")
+                .selected_text(active.to_string())
+                .show_ui(ui, |ui| {
+                    for profile_ref in state.shortcut_profiles() {
+                        let label = profile_ref.to_string();
+                        if ui.selectable_label(profile_ref == active, label).clicked() {
+                            state.set_active_shortcut_profile(profile_ref);
                         }
-                        _ => {}
                     }
-                }
+                });
+
+            let can_delete = active.scope == ShortcutProfileScope::Vault
+                || state
+                    .shortcut_profiles()
+                    .iter()
+                    .filter(|r| r.scope == ShortcutProfileScope::App)
+                    .count()
+                    > 1;
+            if ui
+                .add_enabled(can_delete, egui::Button::new("Delete"))
+                .clicked()
+            {
+                state.remove_shortcut_profile(&active);
             }
         });
 
-        row.cell(|ui| {
-            if !matches!(behaviour.action, ShortcutAction::None) && ui.button("Clear").clicked() {
-                behaviour.action = ShortcutAction::None;
+        ui.horizontal(|ui| {
+            ui.label("New profile:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_profile_name)
+                    .hint_text("Name")
+                    .desired_width(150.0),
+            );
+            egui::ComboBox::new(self.id().with("new_profile_scope"), "")
+                .selected_text(self.new_profile_scope.to_string())
+                .show_ui(ui, |ui| {
+                    choice(ui, &mut self.new_profile_scope, ShortcutProfileScope::App);
+                    if state.current_vault_opt().is_some() {
+                        choice(ui, &mut self.new_profile_scope, ShortcutProfileScope::Vault);
+                    }
+                });
+
+            let new_ref = ShortcutProfileRef {
+                name: self.new_profile_name.trim().to_string(),
+                scope: self.new_profile_scope,
+            };
+            let valid = !new_ref.name.is_empty() && state.get_shortcut_profile(&new_ref).is_none();
+            if ui
+                .add_enabled(valid, egui::Button::new("Copy current"))
+                .on_hover_text("Create a profile with the same bindings as the current profile")
+                .clicked()
+            {
+                state.set_shortcut_profile(
+                    new_ref.scope,
+                    ShortcutProfile {
+                        name: new_ref.name.clone(),
+                        shortcuts: state.shortcuts(),
+                    },
+                );
+                state.set_active_shortcut_profile(new_ref);
+                self.new_profile_name.clear();
             }
         });
+    }
 
-        row.cell(|ui| {
-            ui.checkbox(&mut behaviour.move_next, "Move next?");
+    fn record_ui(&mut self, ui: &mut egui::Ui, state: &AppStateRef) {
+        if !self.recording {
+            if ui.button("Add binding...").clicked() {
+                self.recording = true;
+            }
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Press a key combination, or Escape to cancel");
+            ui.spinner();
+        });
+
+        let pressed = ui.input(|i| {
+            i.events.iter().find_map(|e| match e {
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } => Some(KeyboardShortcut::new(*modifiers, *key)),
+                _ => None,
+            })
         });
+        let Some(shortcut) = pressed else {
+            return;
+        };
+
+        // stop the key press from also running the action it's being bound to
+        ui.input_mut(|i| i.consume_shortcut(&shortcut));
+        self.recording = false;
+        if shortcut.logical_key == egui::Key::Escape && shortcut.modifiers.is_none() {
+            return;
+        }
+        if !state.shortcuts().iter().any(|(k, _)| *k == shortcut) {
+            state.set_shortcut(shortcut, ShortcutBehaviour::default());
+        }
     }
+
     //noinspection DuplicatedCode
     fn edit_ui(&mut self, ui: &mut egui::Ui, state: AppStateRef) {
+        self.profile_ui(ui, &state);
+        ui.separator();
+
         let shortcuts = state.shortcuts();
 
         egui::ScrollArea::vertical().show_viewport(ui, |ui, _vp| {
@@ -100,16 +304,18 @@ impl TagShortcuts {
                                         .size(egui_extras::Size::exact(100.0))
                                         .size(egui_extras::Size::remainder())
                                         .size(egui_extras::Size::exact(100.0))
-                                        .size(egui_extras::Size::exact(100.0))
+                                        .size(egui_extras::Size::exact(70.0))
                                         .horizontal(|mut strip| {
                                             let old_behaviour = behaviour.clone();
-                                            self.table_row(
+                                            let removed = self.table_row(
                                                 shortcut,
                                                 &mut behaviour,
                                                 &mut strip,
-                                                state.clone(),
+                                                &state,
                                             );
-                                            if behaviour != old_behaviour {
+                                            if removed {
+                                                state.remove_shortcut(shortcut);
+                                            } else if behaviour != old_behaviour {
                                                 state.set_shortcut(shortcut, behaviour);
                                             }
                                         });
@@ -118,6 +324,8 @@ impl TagShortcuts {
                         })
                 });
             });
+
+            self.record_ui(ui, &state);
         });
     }
 }
@@ -134,10 +342,10 @@ impl AppModal for TagShortcuts {
 
         let mut do_close = false;
 
-        egui::Window::new("Tag shortcuts")
+        egui::Window::new("Shortcuts")
             .id(self.id())
             .open(&mut opened)
-            .min_width(500.0)
+            .min_width(650.0)
             .show(ctx, |ui| {
                 buttons(self.id(), ui, |ui| {
                     if ui.button("Close").clicked() {
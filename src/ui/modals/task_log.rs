@@ -0,0 +1,240 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::Local;
+use eframe::egui;
+use itertools::Itertools;
+use poll_promise::Promise;
+
+use crate::data::{ItemId, TaskLogEntry, TaskLogError, Vault};
+use crate::state::AppStateRef;
+use crate::tasks::task_log::{export_task_log, TaskLogFormat};
+use crate::ui::modals::AppModal;
+use crate::ui::{buttons, theme};
+
+pub struct TaskLog {
+    kind_filter: Option<String>,
+    is_open: bool,
+}
+
+impl Default for TaskLog {
+    fn default() -> Self {
+        Self {
+            kind_filter: None,
+            is_open: true,
+        }
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn format_duration(duration: chrono::Duration) -> String {
+    let secs = duration.num_seconds();
+    if secs < 60 {
+        format!("{:.1}s", duration.num_milliseconds() as f64 / 1000.0)
+    } else {
+        format!("{}m {}s", secs / 60, secs % 60)
+    }
+}
+
+fn error_ui(ui: &mut egui::Ui, error: &TaskLogError) {
+    let mut chain = error.chain.iter();
+    if let Some(first) = chain.next() {
+        ui.colored_label(theme::ERROR_TEXT, first);
+    }
+    for cause in chain {
+        ui.label(format!("  caused by: {cause}"));
+    }
+}
+
+impl TaskLog {
+    fn matches_filter(&self, error: &TaskLogError) -> bool {
+        match &self.kind_filter {
+            Some(kind) => error.kind.as_ref() == Some(kind),
+            None => true,
+        }
+    }
+
+    /// The vault the task ran on, if it is still the current vault. The paths of failed items
+    /// are only selected in the grid of the vault they belong to.
+    fn entry_vault(state: &AppStateRef, entry: &TaskLogEntry) -> Option<Arc<Vault>> {
+        let vault = state.current_vault_opt()?;
+        (entry.vault.as_ref() == Some(&vault.name)).then_some(vault)
+    }
+
+    fn select_failed_items(state: &AppStateRef, entry: &TaskLogEntry) {
+        let Some(vault) = Self::entry_vault(state, entry) else {
+            return;
+        };
+        let item_ids = entry
+            .failed
+            .iter()
+            .filter_map(|item| item.path.as_deref())
+            .filter_map(|path| vault.get_item_opt(Path::new(path)).ok().flatten())
+            .map(|item| ItemId::from_item(&vault, &item))
+            .unique()
+            .collect_vec();
+        state.request_selection(item_ids);
+    }
+
+    fn entry_ui(&self, ui: &mut egui::Ui, state: &AppStateRef, entry: &TaskLogEntry) {
+        let mut summary = vec![format!("{} succeeded", entry.succeeded)];
+        if !entry.failed.is_empty() {
+            summary.push(format!("{} failed", entry.failed.len()));
+        }
+        if entry.cancelled > 0 {
+            summary.push(format!("{} cancelled", entry.cancelled));
+        }
+        let title = format!(
+            "{} {} ({}) \u{2014} {}",
+            if entry.is_success() {
+                "\u{2714}"
+            } else {
+                "\u{2716}"
+            },
+            entry.name,
+            entry.finished.with_timezone(&Local).format("%H:%M:%S"),
+            summary.join(", ")
+        );
+
+        egui::CollapsingHeader::new(title)
+            .id_source((&entry.name, entry.started))
+            .show(ui, |ui| {
+                ui.label(format!(
+                    "Started {}, took {}",
+                    entry
+                        .started
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M:%S"),
+                    format_duration(entry.duration())
+                ));
+
+                if let Some(error) = entry.error.as_ref().filter(|e| self.matches_filter(e)) {
+                    error_ui(ui, error);
+                }
+
+                let failed = entry
+                    .failed
+                    .iter()
+                    .filter(|item| self.matches_filter(&item.error))
+                    .collect_vec();
+                if failed.is_empty() {
+                    return;
+                }
+
+                let vault = Self::entry_vault(state, entry);
+                let button = ui.add_enabled(
+                    vault.is_some() && failed.iter().any(|item| item.path.is_some()),
+                    egui::Button::new("Select failed items in grid"),
+                );
+                let button = match (&vault, &entry.vault) {
+                    (None, Some(name)) => button.on_disabled_hover_text(format!(
+                        "Open the {name} vault to select the items that failed"
+                    )),
+                    _ => button,
+                };
+                if button.clicked() {
+                    Self::select_failed_items(state, entry);
+                }
+
+                for item in failed {
+                    ui.separator();
+                    if let Some(path) = &item.path {
+                        ui.strong(path);
+                    }
+                    error_ui(ui, &item.error);
+                }
+            });
+    }
+}
+
+impl AppModal for TaskLog {
+    fn id(&self) -> egui::Id {
+        "task_log_modal".into()
+    }
+
+    fn update(&mut self, ctx: &egui::Context, state: AppStateRef) {
+        let mut is_open = self.is_open;
+        let mut do_close = false;
+
+        let (entries, kinds) = {
+            let log = state.task_log();
+            (log.iter().cloned().collect_vec(), log.error_kinds())
+        };
+        let visible = entries
+            .iter()
+            .filter(|entry| {
+                self.kind_filter.is_none() || entry.iter_errors().any(|e| self.matches_filter(e))
+            })
+            .collect_vec();
+
+        egui::Window::new("Task History")
+            .id(self.id())
+            .open(&mut is_open)
+            .min_size([500.0, 250.0])
+            .show(ctx, |ui| {
+                buttons(self.id(), ui, |ui| {
+                    if ui.button("Close").clicked() {
+                        do_close = true;
+                    }
+                    if ui
+                        .add_enabled(!entries.is_empty(), egui::Button::new("Clear"))
+                        .clicked()
+                    {
+                        state.task_log().clear();
+                    }
+                    for format in [TaskLogFormat::Json, TaskLogFormat::Csv] {
+                        if ui
+                            .add_enabled(
+                                !visible.is_empty(),
+                                egui::Button::new(format!("Export {format}...")),
+                            )
+                            .clicked()
+                        {
+                            let exported = visible.iter().copied().cloned().collect_vec();
+                            state.add_global_task("Export task log", move |_, p| {
+                                Promise::spawn_async(export_task_log(exported, format, p))
+                            });
+                        }
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Error kind: ");
+                    egui::ComboBox::from_id_source(self.id().with("kind_filter"))
+                        .selected_text(self.kind_filter.as_deref().unwrap_or("All"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.kind_filter, None, "All");
+                            for kind in kinds {
+                                let label = kind.clone();
+                                ui.selectable_value(&mut self.kind_filter, Some(kind), label);
+                            }
+                        });
+                });
+                ui.separator();
+
+                if entries.is_empty() {
+                    ui.label("No tasks have finished yet.");
+                } else if visible.is_empty() {
+                    ui.label("No tasks failed with this kind of error.");
+                }
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        for entry in &visible {
+                            self.entry_ui(ui, &state, entry);
+                        }
+                    });
+            });
+
+        if do_close {
+            is_open = false;
+        }
+
+        self.is_open = is_open;
+    }
+
+    fn is_open(&self) -> bool {
+        self.is_open
+    }
+}
@@ -1,22 +1,28 @@
 use crate::data::transform::{
-    ChromaSubsampling, CompressionFileType, DestinationExistingBehaviour, DestinationKind,
-    EsrganModel, FitAlgorithm, InfillTechnique, ScaleAlgorithm, SourceKind,
+    ChromaSubsampling, CompressionFileType, CompressionMode, CropAnchor,
+    DestinationExistingBehaviour, DestinationKind, EsrganModel, FitAlgorithm, InfillTechnique,
+    PresetParams, Rotation, ScaleAlgorithm, SourceKind,
+};
+use crate::data::{
+    FieldStore, ItemId, Job, JobKind, ThumbnailCacheItem, ThumbnailParams, TransformBulkParams,
+    TransformImageParams,
 };
-use crate::data::{ItemId, TransformBulkParams, TransformImageParams};
 use crate::errors::AppError;
+use crate::fields;
 use crate::state::AppStateRef;
 use crate::tasks::sort::sort_items_unstable;
 use crate::tasks::transform::{
-    get_transformed_size, list_destination_paths, load_transformed_image_preview,
+    get_transformed_size, list_destination_paths, load_transformed_image_preview, params_for_item,
 };
 use crate::tasks::AsyncTaskResult;
 use crate::ui::cloneable_state::CloneablePersistedState;
 use crate::ui::modals::query::{DefaultButton, QueryKind};
 use crate::ui::modals::{AppModal, QueryOptions};
 use crate::ui::thumb_grid::ThumbnailGrid;
-use crate::ui::{behaviour_select, buttons, choice, indent, modals, theme, QueryResult};
+use crate::ui::{behaviour_select, buttons, choice, indent, modals, theme, widgets, QueryResult};
 use eframe::egui;
 use egui_modal::{Modal, ModalStyle};
+use itertools::Itertools;
 use ordered_float::OrderedFloat;
 use poll_promise::Promise;
 use serde::{Deserialize, Serialize};
@@ -32,6 +38,7 @@ enum FormSection {
     #[default]
     Source,
     Destination,
+    Geometry,
     Scale,
     Infill,
     Compression,
@@ -81,6 +88,8 @@ struct State {
     transform_params: TransformImageParams,
     bulk_params: TransformBulkParams,
     form_section: FormSection,
+    #[serde(default)]
+    run_as_job: bool,
 }
 
 impl CloneablePersistedState for State {}
@@ -164,6 +173,22 @@ impl TransformImages {
         Ok(())
     }
 
+    fn preset_ui(&mut self, ui: &mut egui::Ui) {
+        let mut preset_params = PresetParams::Image {
+            bulk: self.state().bulk_params.clone(),
+            params: self.state().transform_params.clone(),
+        };
+        ui.add(widgets::PresetSelect::new(
+            self.id().with("preset"),
+            &mut preset_params,
+            self.app_state.clone(),
+        ));
+        if let PresetParams::Image { bulk, params } = preset_params {
+            self.state_mut().bulk_params = bulk;
+            self.state_mut().transform_params = params;
+        }
+    }
+
     fn type_choice_grid_inner(&mut self, ui: &mut egui::Ui) {
         let (form_section, params) = {
             let state = self.state_mut();
@@ -184,6 +209,12 @@ impl TransformImages {
                     row.col(|_| {});
                     row.col(|ui| choice(ui, form_section, FormSection::Destination));
                 });
+                body.row(row_height, |mut row| {
+                    row.col(|ui| {
+                        ui.checkbox(&mut params.geometry.enabled, "");
+                    });
+                    row.col(|ui| choice(ui, form_section, FormSection::Geometry));
+                });
                 body.row(row_height, |mut row| {
                     row.col(|ui| {
                         ui.checkbox(&mut params.scale.enabled, "");
@@ -422,6 +453,153 @@ impl TransformImages {
             });
     }
 
+    fn geometry_fragment(&mut self, ui: &mut egui::Ui, p: &mut TransformImageParams) {
+        ui.add_enabled_ui(p.geometry.enabled, |ui| {
+            egui::Grid::new(self.id().with("geometry_options_grid"))
+                .num_columns(2)
+                .min_col_width(200.0)
+                .show(ui, |ui| {
+                    ui.checkbox(&mut p.geometry.auto_orient, "Orient using image metadata");
+                    ui.end_row();
+
+                    let rotation = &mut p.geometry.rotation;
+                    ui.label("Rotate clockwise: ");
+                    ui.horizontal(|ui| {
+                        choice(ui, rotation, Rotation::None);
+                        choice(ui, rotation, Rotation::Rotate90);
+                        choice(ui, rotation, Rotation::Rotate180);
+                        choice(ui, rotation, Rotation::Rotate270);
+                    });
+                    ui.end_row();
+
+                    ui.checkbox(&mut p.geometry.flip_horizontal, "Flip horizontally");
+                    ui.checkbox(&mut p.geometry.flip_vertical, "Flip vertically");
+                    ui.end_row();
+
+                    ui.checkbox(&mut p.geometry.trim_borders, "Trim uniform borders:");
+                    ui.add_enabled(
+                        p.geometry.trim_borders,
+                        egui::Slider::new(&mut p.geometry.trim_tolerance.0, 0.0..=0.5)
+                            .text("tolerance")
+                            .custom_formatter(|n, _| format!("{:.0}%", n * 100.0)),
+                    );
+                    ui.end_row();
+
+                    ui.checkbox(&mut p.geometry.crop, "Crop to aspect ratio:");
+                    ui.add_enabled_ui(p.geometry.crop, |ui| {
+                        ui.horizontal(|ui| {
+                            let (mut a, mut b) = p.geometry.crop_aspect_ratio;
+                            ui.add(
+                                egui::DragValue::new(&mut a.0)
+                                    .fixed_decimals(0)
+                                    .clamp_range(1..=32),
+                            );
+                            ui.label(" : ");
+                            ui.add(
+                                egui::DragValue::new(&mut b.0)
+                                    .fixed_decimals(0)
+                                    .clamp_range(1..=32),
+                            );
+                            p.geometry.crop_aspect_ratio = (a, b);
+                        });
+                    });
+                    ui.end_row();
+
+                    let anchor = &mut p.geometry.crop_anchor;
+                    ui.add_enabled(p.geometry.crop, egui::Label::new("Crop anchor: "));
+                    ui.add_enabled_ui(p.geometry.crop, |ui| {
+                        egui::ComboBox::new(self.id().with("crop_anchor_choice"), "")
+                            .selected_text(anchor.to_string())
+                            .show_ui(ui, |ui| {
+                                choice(ui, anchor, CropAnchor::Centre);
+                                choice(ui, anchor, CropAnchor::Top);
+                                choice(ui, anchor, CropAnchor::Bottom);
+                                choice(ui, anchor, CropAnchor::Left);
+                                choice(ui, anchor, CropAnchor::Right);
+                                choice(ui, anchor, CropAnchor::FocalPoint);
+                            });
+                    });
+                    ui.end_row();
+                });
+
+            if p.geometry.crop && p.geometry.crop_anchor == CropAnchor::FocalPoint {
+                ui.add_space(ui.style().spacing.item_spacing.y * 2.0);
+                self.focal_point_fragment(ui);
+            }
+        });
+    }
+
+    /// Shows the untransformed selected image, where clicking sets the focal point of the item.
+    fn focal_point_fragment(&mut self, ui: &mut egui::Ui) -> Option<()> {
+        const FOCAL_POINT_HEIGHT: usize = 256;
+        const MARKER_RADIUS: f32 = 6.0;
+
+        let Some(item_id) = self.selected_item_id else {
+            ui.label("Select an image to choose its focal point");
+            return None;
+        };
+        let vault = self.app_state.current_vault_opt()?;
+        let item = vault.get_item_opt_by_id(item_id)?;
+        let thumb_params = ThumbnailParams {
+            abs_path: vault.resolve_abs_path(Path::new(item.path())).ok()?,
+            rel_path: item.path().to_string(),
+            last_modified: item
+                .get_known_field_value(fields::general::LAST_MODIFIED)
+                .ok()
+                .flatten(),
+            height: FOCAL_POINT_HEIGHT,
+            transform_params: None,
+        };
+
+        ui.label("Focal point of the selected image (click to change):");
+        let ThumbnailCacheItem::Loaded(hndl) = self.app_state.resolve_thumbnail(&thumb_params)
+        else {
+            ui.spinner();
+            return None;
+        };
+
+        let res = ui.add(
+            egui::Image::from_texture(egui::load::SizedTexture::from_handle(&hndl))
+                .sense(egui::Sense::click()),
+        );
+
+        let focal_point = params_for_item(&self.state().transform_params, &item)
+            .geometry
+            .focal_point;
+        if let Some((x, y)) = focal_point {
+            ui.painter().circle_stroke(
+                res.rect.min + egui::vec2(x.0, y.0) * res.rect.size(),
+                MARKER_RADIUS,
+                egui::Stroke::new(2.0, theme::FOCAL_POINT),
+            );
+        }
+
+        if ui
+            .add_enabled(focal_point.is_some(), egui::Button::new("Reset"))
+            .on_hover_text("Centre the crop, or let the smart crop choose again")
+            .clicked()
+        {
+            item.remove_field(&fields::image::FOCAL_X.id);
+            item.remove_field(&fields::image::FOCAL_Y.id);
+            let _ = self
+                .app_state
+                .commit_item_catch(Some(Arc::clone(&vault)), &item, false);
+            self.source_items_updated = true;
+        }
+
+        if let Some(pos) = res.interact_pointer_pos().filter(|_| res.clicked()) {
+            let point = ((pos - res.rect.min) / res.rect.size())
+                .clamp(egui::Vec2::ZERO, egui::Vec2::splat(1.0));
+            item.set_known_field_value(fields::image::FOCAL_X, f64::from(point.x).into());
+            item.set_known_field_value(fields::image::FOCAL_Y, f64::from(point.y).into());
+            let _ = self.app_state.commit_item_catch(Some(vault), &item, false);
+            // the preview grid thumbnails depend on the focal point
+            self.source_items_updated = true;
+        }
+
+        Some(())
+    }
+
     fn scaling_fragment(&mut self, ui: &mut egui::Ui, p: &mut TransformImageParams) {
         ui.add_enabled_ui(p.scale.enabled, |ui| {
             egui::Grid::new(self.id().with("scaling_options_width_height_grid"))
@@ -555,13 +733,33 @@ impl TransformImages {
                     let tech = &mut p.infill.technique;
 
                     ui.label("Technique: ");
-                    ui.horizontal(|ui| {
+                    ui.horizontal_wrapped(|ui| {
                         choice(ui, tech, InfillTechnique::Automatic);
                         choice(ui, tech, InfillTechnique::Blur);
                         choice(ui, tech, InfillTechnique::Solid);
+                        choice(ui, tech, InfillTechnique::Mirror);
+                        choice(ui, tech, InfillTechnique::EdgeExtend);
+                        choice(ui, tech, InfillTechnique::Gradient);
+                        choice(ui, tech, InfillTechnique::Tile);
+                        choice(ui, tech, InfillTechnique::BlurredTile);
+                        choice(ui, tech, InfillTechnique::SmartCrop);
                     });
                     ui.end_row();
 
+                    let is_auto = p.infill.technique == InfillTechnique::Automatic;
+                    ui.add_enabled(is_auto, egui::Label::new("Colour tolerance: "))
+                        .on_hover_text(
+                            "How different the colours along an edge can be while still \
+                             being filled with a solid colour or gradient",
+                        );
+                    #[allow(clippy::cast_possible_truncation)]
+                    ui.add_enabled(
+                        is_auto,
+                        egui::Slider::new(&mut p.infill.colour_tolerance.0, 0.0..=0.5)
+                            .custom_formatter(|v, _| format!("{}%", (v * 100.0).round() as isize)),
+                    );
+                    ui.end_row();
+
                     let is_blur = p.infill.technique == InfillTechnique::Blur;
                     ui.add_enabled(is_blur, egui::Label::new("Choose infill colour: "));
                     ui.end_row();
@@ -589,6 +787,11 @@ impl TransformImages {
                     ui.end_row();
                 });
 
+            if p.infill.technique == InfillTechnique::SmartCrop {
+                ui.add_space(ui.style().spacing.item_spacing.y * 2.0);
+                self.focal_point_fragment(ui);
+            }
+
             ui.add_space(ui.style().spacing.item_spacing.y * 2.0);
 
             egui::Grid::new(self.id().with("infill_options_postproc_grid"))
@@ -643,29 +846,74 @@ impl TransformImages {
                     let file_type = &mut p.compression.file_type;
 
                     ui.label("File type: ");
-                    ui.horizontal(|ui| {
+                    ui.horizontal_wrapped(|ui| {
                         choice(ui, file_type, CompressionFileType::Jpeg);
                         choice(ui, file_type, CompressionFileType::Png);
                         choice(ui, file_type, CompressionFileType::Webp);
+                        choice(ui, file_type, CompressionFileType::WebpLossless);
                         choice(ui, file_type, CompressionFileType::Avif);
+                        choice(ui, file_type, CompressionFileType::JpegXl);
+                    });
+                    ui.end_row();
+
+                    let mode = &mut p.compression.mode;
+                    ui.label("Mode: ");
+                    ui.horizontal(|ui| {
+                        choice(ui, mode, CompressionMode::Quality);
+                        choice(ui, mode, CompressionMode::TargetSize);
                     });
                     ui.end_row();
 
-                    ui.label("Quality: ");
-                    ui.add(egui::Slider::new(&mut p.compression.quality, 0..=100));
+                    let target_size = *mode == CompressionMode::TargetSize;
+                    ui.label(if target_size {
+                        "Maximum quality: "
+                    } else {
+                        "Quality: "
+                    });
+                    ui.add_enabled(
+                        !p.compression.file_type.is_lossless(),
+                        egui::Slider::new(&mut p.compression.quality, 0..=100),
+                    );
                     ui.end_row();
 
+                    if target_size {
+                        ui.label("Maximum file size: ");
+                        ui.add(
+                            egui::DragValue::new(&mut p.compression.max_file_size_kib)
+                                .clamp_range(1..=u32::MAX)
+                                .suffix(" KiB"),
+                        );
+                        ui.end_row();
+
+                        ui.label("");
+                        ui.checkbox(
+                            &mut p.compression.allow_downscale,
+                            "Downscale if the file size cannot be reached",
+                        );
+                        ui.end_row();
+                    }
+
+                    ui.label("");
+                    ui.checkbox(
+                        &mut p.compression.strip_metadata,
+                        "Strip metadata (EXIF, ICC, XMP)",
+                    );
+                    ui.end_row();
+
+                    let is_jpeg = p.compression.file_type == CompressionFileType::Jpeg;
                     let chroma_id = self.id().with("chroma_select");
                     let chroma = &mut p.compression.chroma_subsampling;
                     ui.label("Chroma subsampling: ");
-                    egui::ComboBox::new(chroma_id, "")
-                        .selected_text(chroma.to_string())
-                        .show_ui(ui, |ui| {
-                            choice(ui, chroma, ChromaSubsampling::Chroma444);
-                            choice(ui, chroma, ChromaSubsampling::Chroma440);
-                            choice(ui, chroma, ChromaSubsampling::Chroma422);
-                            choice(ui, chroma, ChromaSubsampling::Chroma420);
-                        });
+                    ui.add_enabled_ui(is_jpeg, |ui| {
+                        egui::ComboBox::new(chroma_id, "")
+                            .selected_text(chroma.to_string())
+                            .show_ui(ui, |ui| {
+                                choice(ui, chroma, ChromaSubsampling::Chroma444);
+                                choice(ui, chroma, ChromaSubsampling::Chroma440);
+                                choice(ui, chroma, ChromaSubsampling::Chroma422);
+                                choice(ui, chroma, ChromaSubsampling::Chroma420);
+                            });
+                    });
                     ui.end_row();
                 });
         });
@@ -730,7 +978,9 @@ impl TransformImages {
     fn update_preview_image(&mut self) -> Option<()> {
         let id = self.id().with(request::LOAD_PREVIEW);
         let item_id = self.preview_grid.get_first_selected_id()?;
-        let params = self.state().transform_params.clone();
+        let vault = self.app_state.current_vault_opt()?;
+        let item = vault.get_item_opt_by_id(item_id)?;
+        let params = params_for_item(&self.state().transform_params, &item);
         if self.selected_item_id.as_ref() == Some(&item_id)
             && self.params_of_selected_preview.as_ref() == Some(&params)
         {
@@ -739,8 +989,6 @@ impl TransformImages {
         self.selected_item_id = Some(item_id);
         self.params_of_selected_preview = Some(params.clone());
 
-        let vault = self.app_state.current_vault_opt()?;
-        let item = vault.get_item_opt_by_id(item_id)?;
         let path = vault.resolve_abs_path(Path::new(item.path())).ok()?;
 
         self.selected_preview_hndl = None;
@@ -779,8 +1027,8 @@ impl TransformImages {
                 let vault = self.app_state.current_vault_opt()?;
                 let item = vault.get_item_opt_by_id(item_id)?;
                 let src_img_size = item.get_image_size().ok().flatten()?;
-                let params = &self.state().transform_params;
-                let dst_img_size = get_transformed_size(src_img_size, params);
+                let params = params_for_item(&self.state().transform_params, &item);
+                let dst_img_size = get_transformed_size(src_img_size, &params);
 
                 let width = ui.available_width();
                 let img_rect =
@@ -834,7 +1082,12 @@ impl TransformImages {
                     self.source_items_updated,
                 );
 
-                if let Some(abs_path) = self.preview_grid.get_double_clicked_item_path() {
+                let double_clicked = self.app_state.current_vault_opt().and_then(|vault| {
+                    let item = self.preview_grid.get_double_clicked_item(&vault)?;
+                    let abs_path = vault.resolve_abs_path(Path::new(item.path())).ok()?;
+                    Some((abs_path, params_for_item(&params, &item)))
+                });
+                if let Some((abs_path, params)) = double_clicked {
                     self.app_state
                         .add_global_task("Load image preview", move |_, _| {
                             Promise::spawn_blocking(move || {
@@ -868,6 +1121,8 @@ impl TransformImages {
                         ui.with_layout(
                             egui::Layout::top_down(egui::Align::LEFT).with_cross_justify(true),
                             |ui| {
+                                self.preset_ui(ui);
+                                ui.separator();
                                 self.type_choice_grid_inner(ui);
                             },
                         );
@@ -891,6 +1146,9 @@ impl TransformImages {
                                 FormSection::Destination => {
                                     self.destination_fragment(ui, &mut bulk_params);
                                 }
+                                FormSection::Geometry => {
+                                    self.geometry_fragment(ui, &mut global_params);
+                                }
                                 FormSection::Scale => self.scaling_fragment(ui, &mut global_params),
                                 FormSection::Infill => self.infill_fragment(ui, &mut global_params),
                                 FormSection::Compression => {
@@ -979,6 +1237,15 @@ impl TransformImages {
             }
         }
 
+        if p.geometry.enabled && p.geometry.crop {
+            let (OrderedFloat(width), OrderedFloat(height)) = p.geometry.crop_aspect_ratio;
+            if !width.is_normal() || width <= 0.0 || !height.is_normal() || height <= 0.0 {
+                return Err(
+                    "Crop aspect ratio must consist of two valid finite numbers greater than 0.",
+                );
+            }
+        }
+
         if p.infill.enabled {
             let (OrderedFloat(m), OrderedFloat(n)) = p.infill.target_aspect_ratio;
             if !m.is_normal() || m <= 0.0 || !n.is_normal() || n <= 0.0 {
@@ -1016,6 +1283,22 @@ impl TransformImages {
         let source_ids = self.source_item_ids.clone();
         let bulk = self.state().bulk_params.clone();
         let params = self.state().transform_params.clone();
+
+        if self.state().run_as_job {
+            let paths = source_ids
+                .iter()
+                .filter_map(|id| vault.get_item_by_id(*id).ok())
+                .map(|item| item.path().to_string())
+                .collect_vec();
+            let job = Job::new(
+                format!("Transform {} images", paths.len()),
+                JobKind::TransformImages { bulk, params },
+                paths,
+            );
+            crate::tasks::job::queue_job(&self.app_state, vault, job);
+            return;
+        }
+
         self.app_state.add_global_task("Transform images", |s, p| {
             info!("spawn task");
             Promise::spawn_async(crate::tasks::transform::apply_image_transformations(
@@ -1091,6 +1374,11 @@ impl AppModal for TransformImages {
                     if ui.button("Reset").clicked() {
                         *self.state_mut() = Default::default();
                     }
+                    ui.checkbox(&mut self.state_mut().run_as_job, "Run as resumable job")
+                        .on_hover_text(
+                            "Record the progress of each item so that the transformation \
+                             can be paused, or resumed after the app is closed",
+                        );
                 });
 
                 self.modal_contents(ui);
@@ -1,5 +1,7 @@
-use crate::data::transform::{DestinationExistingBehaviour, DestinationKind, SourceKind};
-use crate::data::{ItemId, TransformBulkParams, TransformPathParams};
+use crate::data::transform::{
+    DestinationExistingBehaviour, DestinationKind, PresetParams, SourceKind,
+};
+use crate::data::{ItemId, Job, JobKind, TransformBulkParams, TransformPathParams};
 use crate::errors::AppError;
 use crate::state::AppStateRef;
 use crate::tasks::sort::sort_items_unstable;
@@ -9,10 +11,11 @@ use crate::ui::cloneable_state::CloneablePersistedState;
 use crate::ui::modals::query::{DefaultButton, QueryKind};
 use crate::ui::modals::{AppModal, QueryOptions};
 use crate::ui::{
-    behaviour_select, buttons, choice, indent, modals, radio_choice, theme, QueryResult,
+    behaviour_select, buttons, choice, indent, modals, radio_choice, theme, widgets, QueryResult,
 };
 use eframe::egui;
 use egui_modal::{Modal, ModalStyle};
+use itertools::Itertools;
 use poll_promise::Promise;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
@@ -69,6 +72,8 @@ struct State {
     transform_params: TransformPathParams,
     bulk_params: TransformBulkParams,
     form_section: FormSection,
+    #[serde(default)]
+    run_as_job: bool,
 }
 
 impl CloneablePersistedState for State {}
@@ -175,6 +180,22 @@ impl TransformPaths {
         Ok(())
     }
 
+    fn preset_ui(&mut self, ui: &mut egui::Ui) {
+        let mut preset_params = PresetParams::Path {
+            bulk: self.state().bulk_params.clone(),
+            params: self.state().transform_params.clone(),
+        };
+        ui.add(widgets::PresetSelect::new(
+            self.id().with("preset"),
+            &mut preset_params,
+            self.app_state.clone(),
+        ));
+        if let PresetParams::Path { bulk, params } = preset_params {
+            self.state_mut().bulk_params = bulk;
+            self.state_mut().transform_params = params;
+        }
+    }
+
     fn type_choice_grid_inner(&mut self, ui: &mut egui::Ui) {
         let form_section = &mut self.state_mut().form_section;
 
@@ -540,6 +561,8 @@ impl TransformPaths {
                         ui.with_layout(
                             egui::Layout::top_down(egui::Align::LEFT).with_cross_justify(true),
                             |ui| {
+                                self.preset_ui(ui);
+                                ui.separator();
                                 self.type_choice_grid_inner(ui);
                             },
                         );
@@ -625,6 +648,22 @@ impl TransformPaths {
         let source_ids = self.source_item_ids.clone();
         let bulk = self.state().bulk_params.clone();
         let params = self.state().transform_params.clone();
+
+        if self.state().run_as_job {
+            let paths = source_ids
+                .iter()
+                .filter_map(|id| vault.get_item_by_id(*id).ok())
+                .map(|item| item.path().to_string())
+                .collect_vec();
+            let job = Job::new(
+                format!("Transform {} paths", paths.len()),
+                JobKind::TransformPaths { bulk, params },
+                paths,
+            );
+            crate::tasks::job::queue_job(&self.app_state, vault, job);
+            return;
+        }
+
         self.app_state.add_global_task("Transform paths", |s, p| {
             Promise::spawn_async(crate::tasks::transform::apply_path_transformations(
                 s, vault, source_ids, bulk, params, p,
@@ -699,6 +738,11 @@ impl AppModal for TransformPaths {
                     if ui.button("Reset").clicked() {
                         *self.state_mut() = Default::default();
                     }
+                    ui.checkbox(&mut self.state_mut().run_as_job, "Run as resumable job")
+                        .on_hover_text(
+                            "Record the progress of each item so that the transformation \
+                             can be paused, or resumed after the app is closed",
+                        );
                 });
 
                 self.modal_contents(ui);
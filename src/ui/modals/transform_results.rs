@@ -1,7 +1,7 @@
 use crate::state::AppStateRef;
-use crate::tasks::transform::{TransformResult, TransformReturn};
+use crate::tasks::transform::{EncodedImage, TransformResult, TransformReturn};
 use crate::ui::modals::AppModal;
-use crate::ui::{buttons, modals};
+use crate::ui::{buttons, modals, theme};
 use anyhow::anyhow;
 use eframe::egui;
 use egui_modal::{Modal, ModalStyle};
@@ -9,6 +9,7 @@ use egui_modal::{Modal, ModalStyle};
 pub struct TransformResults {
     modal: Option<Modal>,
     results: Vec<anyhow::Result<TransformResult>>,
+    cancelled: usize,
     app_state: AppStateRef,
     opened: bool,
     is_open: bool,
@@ -21,6 +22,7 @@ enum ResultType {
     InPlace,
     Skip,
     Delete,
+    Import,
     Error,
 }
 
@@ -29,19 +31,33 @@ impl From<&anyhow::Result<TransformResult>> for ResultType {
         match value {
             Ok(TransformResult::CopySuccess { .. }) => Self::Copy,
             Ok(TransformResult::MoveSuccess { .. }) => Self::Move,
-            Ok(TransformResult::InPlaceTransform(_)) => Self::InPlace,
+            Ok(TransformResult::InPlaceTransform(..)) => Self::InPlace,
             Ok(TransformResult::RemovedWithoutTransform(_)) => Self::Delete,
             Ok(TransformResult::NoTransform(_)) => Self::Skip,
+            Ok(TransformResult::Imported(_)) => Self::Import,
             Err(_) => Self::Error,
         }
     }
 }
 
+#[allow(clippy::cast_precision_loss)]
+fn describe_output(encoded: &EncodedImage) -> String {
+    let mut parts = vec![format!("{:.1} KiB", encoded.file_size as f64 / 1024.0)];
+    if let Some(quality) = encoded.quality {
+        parts.push(format!("quality {quality}"));
+    }
+    if encoded.scale < 1.0 {
+        parts.push(format!("scaled to {:.0}%", encoded.scale * 100.0));
+    }
+    parts.join(", ")
+}
+
 impl TransformResults {
-    pub fn new(results: Vec<anyhow::Result<TransformResult>>) -> Self {
+    pub fn new(results: Vec<anyhow::Result<TransformResult>>, cancelled: usize) -> Self {
         Self {
             modal: None,
             results,
+            cancelled,
             app_state: Default::default(),
             opened: false,
             is_open: true,
@@ -54,7 +70,8 @@ impl TransformResults {
             .show(ui, |ui| {
                 egui_extras::TableBuilder::new(ui)
                     .column(egui_extras::Column::exact(100.0))
-                    .column(egui_extras::Column::exact(800.0))
+                    .column(egui_extras::Column::exact(600.0))
+                    .column(egui_extras::Column::exact(600.0))
                     .column(egui_extras::Column::remainder())
                     .header(18.0, |mut row| {
                         row.col(|ui| {
@@ -66,6 +83,9 @@ impl TransformResults {
                         row.col(|ui| {
                             ui.label("New");
                         });
+                        row.col(|ui| {
+                            ui.label("Output");
+                        });
                     })
                     .body(|body| {
                         body.rows(24.0, self.results.len(), |mut row| {
@@ -97,6 +117,11 @@ impl TransformResults {
                                     ui.label(s);
                                 }
                             });
+                            row.col(|ui| {
+                                if let Some(encoded) = item.encoded() {
+                                    ui.label(describe_output(encoded));
+                                }
+                            });
                         });
                     });
             });
@@ -129,6 +154,18 @@ impl AppModal for TransformResults {
                     }
                 });
 
+                if self.cancelled > 0 {
+                    ui.colored_label(
+                        theme::ERROR_TEXT,
+                        format!(
+                            "The transformation was cancelled after processing {} items; \
+                             {} items were left unchanged.",
+                            self.results.len(),
+                            self.cancelled
+                        ),
+                    );
+                }
+
                 self.modal_contents(ui);
             });
 
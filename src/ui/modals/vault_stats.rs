@@ -0,0 +1,287 @@
+use eframe::egui;
+use eframe::egui::{vec2, Sense};
+use egui_modal::Modal;
+use poll_promise::Promise;
+
+use crate::errors::AppError;
+use crate::state::AppStateRef;
+use crate::tasks::stats::{StatsBucket, VaultStats};
+use crate::tasks::AsyncTaskResult;
+use crate::ui::modals::AppModal;
+use crate::ui::{buttons, theme, widgets};
+
+const ROW_HEIGHT: f32 = 18.0;
+const LABEL_WIDTH: f32 = 160.0;
+const BAR_WIDTH: f32 = 240.0;
+
+#[derive(Default)]
+pub struct VaultStatistics {
+    modal: Option<Modal>,
+    stats: Option<Box<VaultStats>>,
+    loading: bool,
+    error_message: Option<String>,
+    opened: bool,
+    is_open: bool,
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} {}", UNITS[unit])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Draws a horizontal bar proportional to `value`, which can be clicked if `clickable` is set.
+#[allow(clippy::cast_precision_loss)]
+fn bar(ui: &mut egui::Ui, value: u64, max_value: u64, clickable: bool) -> egui::Response {
+    let sense = if clickable {
+        Sense::click()
+    } else {
+        Sense::hover()
+    };
+    let (rect, response) = ui.allocate_exact_size(vec2(BAR_WIDTH, ROW_HEIGHT), sense);
+    let fraction = if max_value == 0 {
+        0.0
+    } else {
+        value as f32 / max_value as f32
+    };
+
+    let visuals = ui.visuals();
+    let fill = if response.hovered() && clickable {
+        theme::PROGRESS_TEXT
+    } else {
+        visuals.selection.bg_fill
+    };
+    let painter = ui.painter();
+    painter.rect_filled(rect.shrink(2.0), 2.0, visuals.extreme_bg_color);
+    let mut bar_rect = rect.shrink(2.0);
+    bar_rect.set_width(bar_rect.width() * fraction);
+    painter.rect_filled(bar_rect, 2.0, fill);
+
+    if clickable {
+        response.on_hover_cursor(egui::CursorIcon::PointingHand)
+    } else {
+        response
+    }
+}
+
+impl VaultStatistics {
+    fn request_id(&self) -> egui::Id {
+        self.id().with("compute")
+    }
+
+    fn compute(&mut self, app_state: &AppStateRef) {
+        let Ok(vault) = app_state.current_vault_catch() else {
+            return;
+        };
+        self.loading = true;
+        self.error_message = None;
+        app_state.add_task_request(
+            self.request_id(),
+            format!("Compute statistics for {}", vault.name),
+            |_, p| Promise::spawn_async(crate::tasks::stats::compute_vault_stats(vault, p)),
+        );
+    }
+
+    fn buckets_ui(
+        ui: &mut egui::Ui,
+        app_state: &AppStateRef,
+        buckets: &[StatsBucket],
+        format_value: impl Fn(u64) -> String,
+    ) {
+        if buckets.is_empty() {
+            ui.weak("No data");
+            return;
+        }
+
+        let max_value = buckets.iter().map(|b| b.value).max().unwrap_or_default();
+        for bucket in buckets {
+            ui.horizontal(|ui| {
+                ui.add_sized(
+                    [LABEL_WIDTH, ROW_HEIGHT],
+                    egui::Label::new(&bucket.label).truncate(true),
+                );
+                let res = bar(ui, bucket.value, max_value, bucket.search_text.is_some());
+                if let Some(search_text) = &bucket.search_text {
+                    if res.on_hover_text(search_text).clicked() {
+                        app_state.request_search_text(search_text.clone());
+                    }
+                }
+                ui.label(format_value(bucket.value));
+            });
+        }
+    }
+
+    fn tags_ui(ui: &mut egui::Ui, app_state: &AppStateRef, stats: &VaultStats) {
+        let Some(vault) = app_state.current_vault_opt() else {
+            return;
+        };
+
+        let max_value = stats
+            .tags
+            .iter()
+            .map(|t| t.inherited)
+            .max()
+            .unwrap_or_default();
+        for count in stats.tags.iter().filter(|c| c.inherited > 0) {
+            let Some(def) = vault.get_definition(&count.id) else {
+                continue;
+            };
+            ui.horizontal(|ui| {
+                ui.allocate_ui(vec2(LABEL_WIDTH, ROW_HEIGHT), |ui| {
+                    ui.add(widgets::Tag::new(&def).small(true));
+                });
+                if bar(ui, count.inherited as u64, max_value as u64, true).clicked() {
+                    app_state.request_search_text(format!("field:{}", count.id));
+                }
+                if count.direct == count.inherited {
+                    ui.label(count.direct.to_string());
+                } else {
+                    ui.label(format!("{} ({} directly)", count.inherited, count.direct));
+                }
+            });
+        }
+
+        if !stats.unused_tag_ids.is_empty() {
+            ui.add_space(4.0);
+            ui.label(format!("Unused tags ({}):", stats.unused_tag_ids.len()));
+            ui.horizontal_wrapped(|ui| {
+                for id in &stats.unused_tag_ids {
+                    if let Some(def) = vault.get_definition(id) {
+                        ui.add(widgets::Tag::new(&def).small(true));
+                    }
+                }
+            });
+        }
+    }
+
+    fn stats_ui(ui: &mut egui::Ui, app_state: &AppStateRef, stats: &VaultStats) {
+        let count = |v: u64| v.to_string();
+
+        ui.label(format!(
+            "{} items in vault {}, {} on disk",
+            stats.total_items,
+            stats.vault_name,
+            format_size(stats.total_bytes)
+        ));
+
+        egui::CollapsingHeader::new("Tags")
+            .default_open(true)
+            .show(ui, |ui| Self::tags_ui(ui, app_state, stats));
+        egui::CollapsingHeader::new("Media types")
+            .default_open(true)
+            .show(ui, |ui| {
+                Self::buckets_ui(ui, app_state, &stats.media_types, count);
+            });
+        egui::CollapsingHeader::new("Resolutions").show(ui, |ui| {
+            Self::buckets_ui(ui, app_state, &stats.resolutions, count);
+        });
+        egui::CollapsingHeader::new("Aspect ratios").show(ui, |ui| {
+            Self::buckets_ui(ui, app_state, &stats.aspect_ratios, count);
+        });
+        egui::CollapsingHeader::new("Disk usage by folder").show(ui, |ui| {
+            Self::buckets_ui(ui, app_state, &stats.folder_sizes, format_size);
+        });
+        egui::CollapsingHeader::new("Links to other vaults").show(ui, |ui| {
+            Self::buckets_ui(ui, app_state, &stats.links, count);
+        });
+        egui::CollapsingHeader::new("Last modified").show(ui, |ui| {
+            Self::buckets_ui(ui, app_state, &stats.last_modified, count);
+        });
+        egui::CollapsingHeader::new("Post date").show(ui, |ui| {
+            Self::buckets_ui(ui, app_state, &stats.post_date, count);
+        });
+    }
+}
+
+impl AppModal for VaultStatistics {
+    fn id(&self) -> egui::Id {
+        "vault_statistics_modal".into()
+    }
+
+    fn update(&mut self, ctx: &egui::Context, app_state: AppStateRef) {
+        let modal = Modal::new(ctx, self.id().value());
+
+        if !self.opened {
+            self.compute(&app_state);
+            self.is_open = true;
+            self.opened = true;
+        }
+
+        if let Some(res) = app_state.try_take_request_result(self.request_id()) {
+            self.loading = false;
+            match res {
+                Ok(AsyncTaskResult::VaultStats(stats)) => self.stats = Some(stats),
+                Ok(res) => self.error_message = Some(format!("Unexpected task result: {res:?}")),
+                Err(e) if AppError::UserCancelled.is_err(&e) => {}
+                Err(e) => self.error_message = Some(e.to_string()),
+            }
+        }
+
+        let mut is_open = self.is_open;
+        let mut do_close = false;
+        let mut do_refresh = false;
+
+        egui::Window::new("Vault Statistics")
+            .id(self.id())
+            .open(&mut is_open)
+            .default_size([600.0, 500.0])
+            .show(ctx, |ui| {
+                buttons(self.id(), ui, |ui| {
+                    if ui.button("Close").clicked() {
+                        do_close = true;
+                    }
+                    if ui
+                        .add_enabled(!self.loading, egui::Button::new("Refresh"))
+                        .clicked()
+                    {
+                        do_refresh = true;
+                    }
+                });
+
+                if let Some(msg) = &self.error_message {
+                    ui.colored_label(theme::ERROR_TEXT, msg);
+                }
+
+                if self.loading {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(
+                            egui::RichText::new("Computing statistics...")
+                                .color(theme::PROGRESS_TEXT),
+                        );
+                    });
+                }
+
+                if let Some(stats) = &self.stats {
+                    egui::ScrollArea::vertical()
+                        .auto_shrink([false, false])
+                        .show(ui, |ui| Self::stats_ui(ui, &app_state, stats));
+                }
+            });
+
+        if do_refresh {
+            self.compute(&app_state);
+        }
+        if do_close {
+            is_open = false;
+        }
+
+        self.is_open = is_open;
+
+        self.modal = Some(modal);
+    }
+
+    fn is_open(&self) -> bool {
+        self.is_open
+    }
+}
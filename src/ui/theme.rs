@@ -32,3 +32,6 @@ pub const SUCCESS_TEXT: Color32 = Color32::from_rgb(80, 255, 80);
 pub const PROGRESS_TEXT: Color32 = Color32::from_rgb(80, 160, 255);
 pub const WHITE_TEXT: Color32 = Color32::from_rgb(240, 240, 240);
 pub const BLACK_TEXT: Color32 = Color32::from_rgb(20, 20, 20);
+pub const RATING_STAR: Color32 = Color32::from_rgb(255, 200, 40);
+pub const FAVOURITE_HEART: Color32 = Color32::from_rgb(255, 90, 120);
+pub const FOCAL_POINT: Color32 = Color32::from_rgb(255, 60, 60);
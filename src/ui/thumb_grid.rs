@@ -1,12 +1,15 @@
-use crate::data::{Item, ItemId, ThumbnailCacheItem, TransformImageParams, Vault};
+use crate::data::transform::{CropAnchor, InfillTechnique};
+use crate::data::{FieldStore, Item, ItemId, ThumbnailCacheItem, TransformImageParams, Vault};
 use crate::state::{AppStateRef, THUMBNAIL_LOW_QUALITY_HEIGHT};
-use crate::take_shortcut;
+use crate::tasks::preset;
 use crate::tasks::thumb_grid::{river_layout, ThumbnailPosition};
 use crate::tasks::thumbnail::{load_image_thumbnail, load_image_thumbnail_with_fs};
-use crate::tasks::transform::get_transformed_size;
+use crate::tasks::transform::{get_transformed_size, params_for_item};
 use crate::tasks::{AsyncTaskResult, RiverParams, ThumbnailGridInfo};
 use crate::ui::cloneable_state::CloneablePersistedState;
+use crate::ui::theme;
 use crate::ui::theme::get_accent_color;
+use crate::{fields, take_shortcut};
 use chrono::{DateTime, TimeDelta, Utc};
 use dashmap::DashMap;
 use eframe::egui;
@@ -30,12 +33,16 @@ const CHECKBOX_ALIGN: egui::Align2 = egui::Align2::RIGHT_TOP;
 const CHECKBOX_SIZE: egui::Vec2 = egui::vec2(32.0, 32.0);
 const CHECKBOX_INTERACT_SIZE: f32 = 16.0;
 const HIGHLIGHT_PADDING: f32 = 2.0;
+const BADGE_ALIGN: egui::Align2 = egui::Align2::LEFT_BOTTOM;
+const BADGE_MARGIN: f32 = 4.0;
+const BADGE_BACKGROUND: egui::Color32 = egui::Color32::from_rgba_premultiplied(0, 0, 0, 160);
 pub const TAB_REQUEST_ID: Uuid = uuid!("524b6f5c-385e-4ee9-a1a8-ccc234765564");
 
 pub struct ThumbnailGrid {
     id: egui::Id,
     pub params: RiverParams,
     pub transform_params: Option<TransformImageParams>,
+    show_context_menu: bool,
     info: ThumbnailGridInfo,
     app_state: AppStateRef,
     state: State,
@@ -92,6 +99,7 @@ impl ThumbnailGrid {
             id: egui::Id::new(id),
             params: Default::default(),
             transform_params: Default::default(),
+            show_context_menu: false,
             info: Default::default(),
             app_state: Default::default(),
             state: Default::default(),
@@ -106,6 +114,11 @@ impl ThumbnailGrid {
         }
     }
 
+    pub fn with_context_menu(mut self) -> Self {
+        self.show_context_menu = true;
+        self
+    }
+
     pub fn id(&self) -> egui::Id {
         self.id
     }
@@ -120,6 +133,23 @@ impl ThumbnailGrid {
         state.store(ctx, self.id());
     }
 
+    pub fn has_focus(&self) -> bool {
+        self.has_focus
+    }
+
+    pub fn select_items(&self, ctx: &egui::Context, item_ids: &[ItemId]) {
+        let mut state = State::load(ctx, self.id()).unwrap_or_default();
+        state.checked_items.clear();
+        for id in item_ids {
+            state.checked_items.insert(*id, true);
+        }
+        if item_ids.len() > 1 {
+            state.select_mode = SelectMode::Multiple;
+        }
+        state.middle_item = item_ids.first().copied();
+        state.store(ctx, self.id());
+    }
+
     pub fn get_selected_ids(&self) -> Vec<ItemId> {
         self.state
             .checked_items
@@ -257,7 +287,10 @@ impl ThumbnailGrid {
                 return;
             };
 
-            params.transform_params.clone_from(&self.transform_params);
+            params.transform_params = self
+                .transform_params
+                .as_ref()
+                .map(|tf_params| self.item_transform_params(tf_params, item.id));
 
             let thumb = self.app_state.resolve_thumbnail(&params);
 
@@ -270,6 +303,25 @@ impl ThumbnailGrid {
         }
     }
 
+    fn item_transform_params(
+        &self,
+        params: &TransformImageParams,
+        item_id: ItemId,
+    ) -> TransformImageParams {
+        let geometry = &params.geometry;
+        let uses_focal_point =
+            geometry.enabled && geometry.crop && geometry.crop_anchor == CropAnchor::FocalPoint;
+        let uses_smart_crop =
+            params.infill.enabled && params.infill.technique == InfillTechnique::SmartCrop;
+        if !(uses_focal_point || uses_smart_crop) {
+            return params.clone();
+        }
+        self.app_state
+            .current_vault_opt()
+            .and_then(|vault| vault.get_item_opt_by_id(item_id))
+            .map_or_else(|| params.clone(), |item| params_for_item(params, &item))
+    }
+
     fn render_thumbnail(
         &mut self,
         ui: &mut egui::Ui,
@@ -317,6 +369,12 @@ impl ThumbnailGrid {
         ui.ctx()
             .check_for_id_clash(res.id, res.rect, "thumbnail image");
 
+        self.render_badge(ui, item, res.rect);
+
+        if self.show_context_menu {
+            res.context_menu(|ui| self.context_menu_ui(ui, item.id));
+        }
+
         if res.hover_pos().map_or(false, |p| outer_bounds.contains(p)) {
             self.next_hover = Some(item.id);
         }
@@ -351,6 +409,87 @@ impl ThumbnailGrid {
         }
     }
 
+    /// Offers the transform presets for the selected items, or for the clicked item if it isn't
+    /// one of them.
+    fn context_menu_ui(&self, ui: &mut egui::Ui, item_id: ItemId) {
+        let selected_ids = self.get_selected_ids();
+        let item_ids = if selected_ids.contains(&item_id) {
+            selected_ids
+        } else {
+            vec![item_id]
+        };
+
+        ui.menu_button("Run preset", |ui| {
+            let presets = self.app_state.transform_presets();
+            if presets.is_empty() {
+                ui.weak("No presets saved");
+            }
+            for preset in presets {
+                if ui.button(&preset.name).clicked() {
+                    if let Ok(vault) = self.app_state.current_vault_catch() {
+                        preset::run_preset(&self.app_state, vault, item_ids.clone(), preset);
+                    }
+                    ui.close_menu();
+                }
+            }
+        });
+    }
+
+    /// Marks rated and favourite items in the corner of their thumbnail.
+    fn render_badge(&self, ui: &mut egui::Ui, item: &ThumbnailPosition, rect: egui::Rect) {
+        let Some(item) = self
+            .app_state
+            .current_vault_opt()
+            .and_then(|vault| vault.get_item_opt_by_id(item.id))
+        else {
+            return;
+        };
+
+        let rating = item
+            .get_known_field_value(fields::general::RATING)
+            .ok()
+            .flatten()
+            .filter(|r| *r > 0);
+        let favourite = item
+            .get_known_field_value(fields::general::FAVOURITE)
+            .ok()
+            .flatten()
+            .unwrap_or(false);
+
+        let mut job = egui::text::LayoutJob::default();
+        let font_id = egui::TextStyle::Small.resolve(ui.style());
+        if favourite {
+            job.append(
+                "\u{2665}",
+                0.0,
+                egui::TextFormat::simple(font_id.clone(), theme::FAVOURITE_HEART),
+            );
+        }
+        if let Some(rating) = rating {
+            job.append(
+                &format!("\u{2605}{rating}"),
+                if favourite { 2.0 } else { 0.0 },
+                egui::TextFormat::simple(font_id, theme::RATING_STAR),
+            );
+        }
+        if job.is_empty() {
+            return;
+        }
+
+        let galley = ui.fonts(|f| f.layout_job(job));
+        let badge_rect = BADGE_ALIGN.align_size_within_rect(
+            galley.size() + egui::Vec2::splat(BADGE_MARGIN),
+            rect.shrink(BADGE_MARGIN),
+        );
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(badge_rect, ROUNDING, BADGE_BACKGROUND);
+        painter.galley(
+            badge_rect.min + egui::Vec2::splat(BADGE_MARGIN / 2.0),
+            galley,
+            egui::Color32::WHITE,
+        );
+    }
+
     fn render_checkbox(
         &mut self,
         ui: &mut egui::Ui,
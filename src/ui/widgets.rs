@@ -1,5 +1,7 @@
 mod find_tag;
 mod list_edit;
+mod preset_select;
+mod rating;
 mod search_box;
 mod tag;
 mod tag_tree;
@@ -9,6 +11,8 @@ mod tag_value_edit;
 pub use find_tag::FindTag;
 pub use list_edit::ListEdit;
 pub use list_edit::ListEditResult;
+pub use preset_select::PresetSelect;
+pub use rating::{Favourite, Rating};
 pub use search_box::SearchBox;
 pub use tag::Tag;
 pub use tag_tree::TagTree;
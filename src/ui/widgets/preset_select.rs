@@ -0,0 +1,112 @@
+use eframe::egui;
+use eframe::egui::{Response, Ui, Widget};
+use poll_promise::Promise;
+
+use crate::data::transform::PresetParams;
+use crate::data::TransformPreset;
+use crate::state::AppStateRef;
+use crate::tasks::preset::{choose_and_import_presets, export_presets};
+
+/// Loads, saves, imports and exports transform presets of the same kind as `params`.
+pub struct PresetSelect<'a> {
+    id: egui::Id,
+    params: &'a mut PresetParams,
+    app_state: AppStateRef,
+}
+
+impl<'a> PresetSelect<'a> {
+    pub fn new(
+        id: impl std::hash::Hash,
+        params: &'a mut PresetParams,
+        app_state: AppStateRef,
+    ) -> Self {
+        Self {
+            id: egui::Id::new(id),
+            params,
+            app_state,
+        }
+    }
+}
+
+impl Widget for PresetSelect<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let name_id = self.id.with("name");
+        let mut name = ui
+            .data(|r| r.get_temp::<String>(name_id))
+            .unwrap_or_default();
+
+        let presets: Vec<_> = self
+            .app_state
+            .transform_presets()
+            .into_iter()
+            .filter(|p| std::mem::discriminant(&p.params) == std::mem::discriminant(self.params))
+            .collect();
+
+        let res = ui
+            .vertical(|ui| {
+                ui.label("Preset:");
+                let selected_text = presets
+                    .iter()
+                    .find(|p| &p.params == self.params)
+                    .map_or("Custom", |p| p.name.as_str());
+                egui::ComboBox::from_id_source(self.id.with("combo"))
+                    .selected_text(selected_text)
+                    .width(ui.available_width())
+                    .show_ui(ui, |ui| {
+                        for preset in &presets {
+                            if ui
+                                .selectable_label(&preset.params == self.params, &preset.name)
+                                .clicked()
+                            {
+                                self.params.clone_from(&preset.params);
+                                name.clone_from(&preset.name);
+                            }
+                        }
+                    });
+
+                ui.add(egui::TextEdit::singleline(&mut name).hint_text("Preset name"));
+
+                let trimmed = name.trim();
+                let existing = presets.iter().any(|p| p.name == trimmed);
+                ui.horizontal(|ui| {
+                    let label = if existing { "Update" } else { "Save" };
+                    if ui
+                        .add_enabled(!trimmed.is_empty(), egui::Button::new(label))
+                        .clicked()
+                    {
+                        self.app_state.set_transform_preset(TransformPreset::new(
+                            trimmed.to_string(),
+                            self.params.clone(),
+                        ));
+                    }
+                    if ui
+                        .add_enabled(existing, egui::Button::new("Delete"))
+                        .clicked()
+                    {
+                        self.app_state.remove_transform_preset(trimmed);
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button("Import...").clicked() {
+                        self.app_state
+                            .add_global_task("Import transform presets", |s, p| {
+                                Promise::spawn_async(choose_and_import_presets(s, p))
+                            });
+                    }
+                    if ui.button("Export...").clicked() {
+                        let presets = self.app_state.transform_presets();
+                        self.app_state
+                            .add_global_task("Export transform presets", |_, p| {
+                                Promise::spawn_async(export_presets(presets, p))
+                            });
+                    }
+                });
+            })
+            .response;
+
+        ui.data_mut(|wr| wr.insert_temp(name_id, name));
+
+        res
+    }
+}
@@ -0,0 +1,107 @@
+use eframe::egui;
+use eframe::egui::{Response, RichText, Sense, Ui, Widget};
+
+use crate::ui::theme;
+
+pub const MAX_RATING: i64 = 5;
+
+const SIZE: f32 = 18.0;
+
+const FILLED_STAR: &str = "\u{2605}";
+const EMPTY_STAR: &str = "\u{2606}";
+const FILLED_HEART: &str = "\u{2665}";
+const EMPTY_HEART: &str = "\u{2661}";
+
+/// A row of clickable stars. Clicking the star matching the current rating clears it.
+pub struct Rating<'a> {
+    id: egui::Id,
+    value: &'a mut Option<i64>,
+}
+
+impl<'a> Rating<'a> {
+    pub fn new(id: impl std::hash::Hash, value: &'a mut Option<i64>) -> Self {
+        Self {
+            id: egui::Id::new(id),
+            value,
+        }
+    }
+}
+
+impl Widget for Rating<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let current = self.value.unwrap_or(0);
+        let mut clicked = None;
+
+        let mut res = ui
+            .horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+
+                let hovered_id = self.id.with("hovered");
+                let hovered = ui.data(|r| r.get_temp::<i64>(hovered_id));
+                let shown = hovered.unwrap_or(current);
+                let mut next_hovered = None;
+
+                for i in 1..=MAX_RATING {
+                    let text = if i <= shown { FILLED_STAR } else { EMPTY_STAR };
+                    let mut text = RichText::new(text).size(SIZE);
+                    if i <= shown {
+                        text = text.color(theme::RATING_STAR);
+                    }
+                    let star = ui
+                        .add(egui::Label::new(text).sense(Sense::click()))
+                        .on_hover_text(format!("{i} star{}", if i == 1 { "" } else { "s" }));
+                    if star.hovered() {
+                        next_hovered = Some(i);
+                    }
+                    if star.clicked() {
+                        clicked = Some(i);
+                    }
+                }
+
+                ui.data_mut(|wr| match next_hovered {
+                    Some(i) => wr.insert_temp(hovered_id, i),
+                    None => wr.remove::<i64>(hovered_id),
+                });
+            })
+            .response;
+
+        if let Some(i) = clicked {
+            *self.value = (i != current).then_some(i);
+            res.mark_changed();
+        }
+
+        res
+    }
+}
+
+/// A heart that toggles whether an item is a favourite.
+pub struct Favourite<'a> {
+    value: &'a mut bool,
+}
+
+impl<'a> Favourite<'a> {
+    pub fn new(value: &'a mut bool) -> Self {
+        Self { value }
+    }
+}
+
+impl Widget for Favourite<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let text = if *self.value {
+            RichText::new(FILLED_HEART)
+                .size(SIZE)
+                .color(theme::FAVOURITE_HEART)
+        } else {
+            RichText::new(EMPTY_HEART).size(SIZE)
+        };
+
+        let mut res = ui
+            .add(egui::Label::new(text).sense(Sense::click()))
+            .on_hover_text("Favourite");
+        if res.clicked() {
+            *self.value ^= true;
+            res.mark_changed();
+        }
+        res
+    }
+}
@@ -44,6 +44,7 @@ pub struct SearchBox<'a> {
     state: State,
     vault: Arc<Vault>,
     interactive: bool,
+    colour_search: bool,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -150,6 +151,7 @@ impl<'a> SearchBox<'a> {
             state: Default::default(),
             vault,
             interactive: false,
+            colour_search: false,
             reserved_left: 0.0,
             wrap_width: 0.0,
         }
@@ -170,6 +172,12 @@ impl<'a> SearchBox<'a> {
         self
     }
 
+    /// Shows a colour picker that adds a search for images of the picked colour.
+    pub fn colour_search(mut self) -> Self {
+        self.colour_search = true;
+        self
+    }
+
     pub fn icon(mut self, icon: impl Into<String>) -> Self {
         self.icon = icon.into();
         self
@@ -808,6 +816,49 @@ impl<'a> SearchBox<'a> {
         (res, galley)
     }
 
+    fn colour_search_ui(&mut self, ui: &mut Ui, output: &Response) {
+        if !self.colour_search {
+            return;
+        }
+
+        let size = output.rect.size().y;
+        let rect = Rect::from_min_size(
+            output.rect.right_top() - vec2(size * 2.0, 0.0),
+            Vec2::splat(size),
+        );
+        let popup_id = self.id.with("colour_search_popup");
+        let colour_id = self.id.with("colour_search_colour");
+
+        let res = ui
+            .put(rect, egui::Button::new("\u{1f3a8}").frame(false))
+            .on_hover_text("Search by colour")
+            .on_hover_cursor(CursorIcon::Default);
+        if res.clicked() {
+            ui.memory_mut(|mem| mem.toggle_popup(popup_id));
+        }
+
+        let mut colour = ui
+            .data(|r| r.get_temp::<Color32>(colour_id))
+            .unwrap_or(Color32::from_rgb(0, 128, 128));
+        egui::popup::popup_below_widget(ui, popup_id, &res, |ui| {
+            egui::color_picker::color_picker_color32(
+                ui,
+                &mut colour,
+                egui::color_picker::Alpha::Opaque,
+            );
+            if ui.button("Find images mostly of this colour").clicked() {
+                if !self.text.is_empty() && !self.text.ends_with(' ') {
+                    self.text.push(' ');
+                }
+                let (r, g, b) = (colour.r(), colour.g(), colour.b());
+                let term = format!("colour near #{r:02x}{g:02x}{b:02x}");
+                self.text.push_str(&term);
+                ui.memory_mut(|mem| mem.close_popup());
+            }
+        });
+        ui.data_mut(|wr| wr.insert_temp(colour_id, colour));
+    }
+
     fn paint_tags(&self, ui: &Ui, min: Vec2, galley: &Galley, clip_rect: Rect) {
         const TAG_PADDING: Vec2 = vec2(4.0, 0.0);
 
@@ -961,6 +1012,7 @@ impl<'a> SearchBox<'a> {
         }
     }
 
+    #[allow(clippy::too_many_lines)]
     pub fn show(mut self, ui: &mut Ui) -> Response {
         self.state = State::load(ui.ctx(), self.id).unwrap_or_default();
 
@@ -994,9 +1046,10 @@ impl<'a> SearchBox<'a> {
         }
 
         let (output, galley) = self.show_edit_field(ui);
+        let buttons = if self.colour_search { 2.0 } else { 1.0 };
         let clip_rect = Rect::from_min_max(
             output.interact_rect.min + vec2(icon_reserved_width, 0.0),
-            output.interact_rect.max - vec2(output.rect.size().y, 0.0),
+            output.interact_rect.max - vec2(output.rect.size().y * buttons, 0.0),
         );
 
         let style = ui.style();
@@ -1049,6 +1102,8 @@ impl<'a> SearchBox<'a> {
             }
         }
 
+        self.colour_search_ui(ui, &output);
+
         if self.interactive {
             let ui = ui.child_ui(clip_rect, Layout::default());
             self.paint_tags(&ui, output.rect.min.to_vec2(), &galley, clip_rect);